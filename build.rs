@@ -2,6 +2,8 @@
 fn main() {
     // Tell Cargo that if the given file changes, to rerun this build script.
     println!("cargo:rerun-if-changed=src/asm/asm.S");
+    println!("cargo:rerun-if-changed=src/asm/xlen32.S");
+    println!("cargo:rerun-if-changed=src/asm/xlen64.S");
     println!("cargo:rerun-if-changed=src/lds/link.x");
     println!("cargo:rerun-if-changed=src/lds/memory.x");
     println!("cargo:rerun-if-changed=build.rs");
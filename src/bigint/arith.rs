@@ -0,0 +1,211 @@
+use core::cmp::Ordering;
+
+// Arithmetic over little-endian u32 limb slices. Used by fixed width types and
+// arbitrary length numbers (e.g. MODEXP) alike
+
+#[must_use]
+pub fn cmp(a: &[u32], b: &[u32]) -> Ordering {
+    let len = core::cmp::max(a.len(), b.len());
+    for i in (0..len).rev() {
+        let a_limb = a.get(i).copied().unwrap_or(0);
+        let b_limb = b.get(i).copied().unwrap_or(0);
+        match a_limb.cmp(&b_limb) {
+            Ordering::Equal => continue,
+            ordering => return ordering,
+        }
+    }
+
+    Ordering::Equal
+}
+
+/// Number of significant limbs
+#[must_use]
+pub fn significant_limbs(a: &[u32]) -> usize {
+    let mut len = a.len();
+    while len > 0 && a[len - 1] == 0 {
+        len -= 1;
+    }
+
+    len
+}
+
+/// `dst += src`, returns carry. `dst` must be at least as long as `src`
+pub fn add_assign(dst: &mut [u32], src: &[u32]) -> bool {
+    let mut carry = 0u64;
+    for (i, d) in dst.iter_mut().enumerate() {
+        let s = src.get(i).copied().unwrap_or(0) as u64;
+        if i >= src.len() && carry == 0 {
+            break;
+        }
+        let t = (*d as u64) + s + carry;
+        *d = t as u32;
+        carry = t >> 32;
+    }
+
+    carry != 0
+}
+
+/// `dst -= src`, returns borrow. `dst` must be at least as long as `src`
+pub fn sub_assign(dst: &mut [u32], src: &[u32]) -> bool {
+    let mut borrow = false;
+    for (i, d) in dst.iter_mut().enumerate() {
+        let s = src.get(i).copied().unwrap_or(0);
+        if i >= src.len() && !borrow {
+            break;
+        }
+        let (t, of0) = d.overflowing_sub(s);
+        let (t, of1) = t.overflowing_sub(borrow as u32);
+        *d = t;
+        borrow = of0 | of1;
+    }
+
+    borrow
+}
+
+/// `dst = a * b` truncated to the length of `dst`
+pub fn mul(dst: &mut [u32], a: &[u32], b: &[u32]) {
+    for d in dst.iter_mut() {
+        *d = 0;
+    }
+    let a_len = significant_limbs(a);
+    let b_len = significant_limbs(b);
+    for i in 0..core::cmp::min(a_len, dst.len()) {
+        let mut carry = 0u64;
+        let a_limb = a[i] as u64;
+        for j in 0..b_len {
+            if i + j >= dst.len() {
+                break;
+            }
+            let t = a_limb * (b[j] as u64) + (dst[i + j] as u64) + carry;
+            dst[i + j] = t as u32;
+            carry = t >> 32;
+        }
+        if i + b_len < dst.len() {
+            dst[i + b_len] = carry as u32;
+        }
+    }
+}
+
+/// Long division (Knuth's algorithm D). Writes `num / den` into `quotient` and `num % den`
+/// into `remainder` (both are zeroed first and truncated to their lengths). `scratch` must be
+/// at least `num.len() + 1` limbs, and `den_scratch` at least `den.len()` limbs.
+/// Division by zero gives zero quotient and zero remainder, as in EVM
+pub fn div_rem(
+    num: &[u32],
+    den: &[u32],
+    quotient: &mut [u32],
+    remainder: &mut [u32],
+    scratch: &mut [u32],
+    den_scratch: &mut [u32],
+) {
+    for q in quotient.iter_mut() {
+        *q = 0;
+    }
+    for r in remainder.iter_mut() {
+        *r = 0;
+    }
+
+    let n = significant_limbs(den);
+    let m = significant_limbs(num);
+    if n == 0 {
+        return;
+    }
+    if cmp(&num[..m], &den[..n]) == Ordering::Less {
+        let len = core::cmp::min(m, remainder.len());
+        remainder[..len].copy_from_slice(&num[..len]);
+        return;
+    }
+
+    if n == 1 {
+        let d = den[0] as u64;
+        let mut rem = 0u64;
+        for i in (0..m).rev() {
+            let t = (rem << 32) | (num[i] as u64);
+            if i < quotient.len() {
+                quotient[i] = (t / d) as u32;
+            }
+            rem = t % d;
+        }
+        if !remainder.is_empty() {
+            remainder[0] = rem as u32;
+        }
+        return;
+    }
+
+    // normalize so the top bit of the divisor is set
+    let shift = den[n - 1].leading_zeros();
+    let u = &mut scratch[..m + 1];
+    let v = &mut den_scratch[..n];
+    shl_into(v, &den[..n], shift);
+    u[m] = shl_into(&mut u[..m], &num[..m], shift);
+
+    let v_top = v[n - 1] as u64;
+    let v_next = v[n - 2] as u64;
+    for j in (0..=(m - n)).rev() {
+        let numerator = ((u[j + n] as u64) << 32) | (u[j + n - 1] as u64);
+        let mut q_hat = numerator / v_top;
+        let mut r_hat = numerator % v_top;
+        while q_hat > u32::MAX as u64
+            || q_hat * v_next > ((r_hat << 32) | (u[j + n - 2] as u64))
+        {
+            q_hat -= 1;
+            r_hat += v_top;
+            if r_hat > u32::MAX as u64 {
+                break;
+            }
+        }
+
+        // u[j..j + n + 1] -= q_hat * v
+        let mut borrow = 0i64;
+        let mut carry = 0u64;
+        for i in 0..n {
+            let p = q_hat * (v[i] as u64) + carry;
+            carry = p >> 32;
+            let t = (u[i + j] as i64) - borrow - ((p & 0xffff_ffff) as i64);
+            u[i + j] = t as u32;
+            borrow = if t < 0 { 1 } else { 0 };
+        }
+        let t = (u[j + n] as i64) - borrow - (carry as i64);
+        u[j + n] = t as u32;
+
+        if t < 0 {
+            // estimate was one too large, add the divisor back
+            q_hat -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let t = (u[i + j] as u64) + (v[i] as u64) + carry;
+                u[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+
+        if j < quotient.len() {
+            quotient[j] = q_hat as u32;
+        }
+    }
+
+    // denormalize the remainder
+    for i in 0..core::cmp::min(n, remainder.len()) {
+        remainder[i] = if shift == 0 {
+            u[i]
+        } else {
+            (u[i] >> shift) | (u[i + 1] << (32 - shift))
+        };
+    }
+}
+
+/// `dst = src << shift` for `shift < 32`, returns the bits shifted out of the top limb
+pub fn shl_into(dst: &mut [u32], src: &[u32], shift: u32) -> u32 {
+    if shift == 0 {
+        dst.copy_from_slice(src);
+        return 0;
+    }
+    let mut carry = 0u32;
+    for (d, s) in dst.iter_mut().zip(src.iter()) {
+        *d = (*s << shift) | carry;
+        carry = *s >> (32 - shift);
+    }
+
+    carry
+}
@@ -0,0 +1,305 @@
+use core::marker::PhantomData;
+
+use super::U256;
+
+// Prime fields with moduli below 2^256 and their quadratic extensions, for the curves of the
+// precompiles. Elements are kept in the Montgomery form, so multiplication needs no division,
+// and every operation runs the same instructions whatever the values are: loops have constant
+// bounds, and conditional subtractions are masked instead of branched on. Limbs are 32-bit,
+// the products map onto `mul` and `mulhu` of RV32IM
+
+const LIMBS: usize = 8;
+
+pub trait FieldParameters: Copy + Eq + core::fmt::Debug + 'static {
+    /// Odd prime
+    const MODULUS: U256;
+    /// `-MODULUS^-1 mod 2^32`
+    const INV: u32 = inv32(Self::MODULUS.0[0]);
+    /// `2^256 mod MODULUS`, the Montgomery form of one
+    const R: U256 = pow2_mod(256, &Self::MODULUS);
+    /// `2^512 mod MODULUS`, converts into the Montgomery form
+    const R2: U256 = pow2_mod(512, &Self::MODULUS);
+}
+
+const fn inv32(modulus: u32) -> u32 {
+    // Newton's iteration doubles the correct low bits every step, odd x is its own inverse
+    // modulo 8
+    let mut inverse = modulus;
+    let mut i = 0;
+    while i < 4 {
+        inverse = inverse.wrapping_mul(2u32.wrapping_sub(modulus.wrapping_mul(inverse)));
+        i += 1;
+    }
+
+    inverse.wrapping_neg()
+}
+
+const fn pow2_mod(exponent: u32, modulus: &U256) -> U256 {
+    let mut result = U256::ONE;
+    let mut i = 0;
+    while i < exponent {
+        let (doubled, carry) = result.overflowing_add(&result);
+        let (reduced, borrow) = doubled.overflowing_sub(modulus);
+        result = if carry || !borrow { reduced } else { doubled };
+        i += 1;
+    }
+
+    result
+}
+
+/// `mask` is all ones or all zeroes
+#[inline(always)]
+fn select(mask: u32, if_set: &U256, if_clear: &U256) -> U256 {
+    let mut result = U256::ZERO;
+    for i in 0..LIMBS {
+        result.0[i] = (if_set.0[i] & mask) | (if_clear.0[i] & !mask);
+    }
+
+    result
+}
+
+#[inline(always)]
+const fn mask(condition: bool) -> u32 {
+    (condition as u32).wrapping_neg()
+}
+
+/// Arithmetic shared by the prime fields and their extensions, that the curves are generic over
+pub trait Field: Copy + PartialEq + core::fmt::Debug {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn is_zero(&self) -> bool;
+    /// Picks one of the values without branching on `choice`
+    fn select(choice: bool, if_true: &Self, if_false: &Self) -> Self;
+    fn add(&self, other: &Self) -> Self;
+    fn sub(&self, other: &Self) -> Self;
+    fn mul(&self, other: &Self) -> Self;
+    /// Zero for zero
+    fn inverse(&self) -> Self;
+
+    fn neg(&self) -> Self {
+        Self::ZERO.sub(self)
+    }
+
+    fn double(&self) -> Self {
+        self.add(self)
+    }
+
+    fn square(&self) -> Self {
+        self.mul(self)
+    }
+}
+
+/// Element of the field of `P`, in the Montgomery form
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fp<P: FieldParameters>(U256, PhantomData<P>);
+
+impl<P: FieldParameters> Fp<P> {
+    /// `None` if `value` is not below the modulus
+    #[must_use]
+    pub fn from_u256(value: &U256) -> Option<Self> {
+        if *value >= P::MODULUS {
+            return None;
+        }
+
+        Some(Self(*value, PhantomData).mul(&Self(P::R2, PhantomData)))
+    }
+
+    /// Takes `value` modulo the modulus first
+    #[must_use]
+    pub fn from_u256_reduced(value: &U256) -> Self {
+        let (_, remainder) = value.div_rem(&P::MODULUS);
+
+        Self(remainder, PhantomData).mul(&Self(P::R2, PhantomData))
+    }
+
+    /// All the moduli are well above 2^32, `value` is always below them
+    #[must_use]
+    pub fn from_u32(value: u32) -> Self {
+        Self(U256::from_u32(value), PhantomData).mul(&Self(P::R2, PhantomData))
+    }
+
+    /// Canonical value, below the modulus
+    #[must_use]
+    pub fn to_u256(&self) -> U256 {
+        self.mul(&Self(U256::ONE, PhantomData)).0
+    }
+
+    /// Parity of the canonical value
+    #[must_use]
+    pub fn is_odd(&self) -> bool {
+        self.to_u256().0[0] & 1 != 0
+    }
+
+    /// Goes through all the 256 bits of the exponent, multiplying by one where they are clear
+    #[must_use]
+    pub fn pow(&self, exponent: &U256) -> Self {
+        let mut result = Self::ONE;
+        for i in (0..256).rev() {
+            result = result.square();
+            let product = result.mul(self);
+            result = Self::select(exponent.bit(i), &product, &result);
+        }
+
+        result
+    }
+
+    /// One of the square roots, `None` for non-residues. Only for moduli that are 3 mod 4,
+    /// where it is `self^((p + 1) / 4)`
+    #[must_use]
+    pub fn sqrt(&self) -> Option<Self> {
+        let exponent = P::MODULUS.wrapping_add(&U256::ONE).shr(2);
+        let root = self.pow(&exponent);
+
+        (root.square() == *self).then_some(root)
+    }
+}
+
+impl<P: FieldParameters> Field for Fp<P> {
+    const ZERO: Self = Self(U256::ZERO, PhantomData);
+    const ONE: Self = Self(P::R, PhantomData);
+
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    fn select(choice: bool, if_true: &Self, if_false: &Self) -> Self {
+        Self(select(mask(choice), &if_true.0, &if_false.0), PhantomData)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let (sum, carry) = self.0.overflowing_add(&other.0);
+        let (reduced, borrow) = sum.overflowing_sub(&P::MODULUS);
+
+        Self(select(mask(carry || !borrow), &reduced, &sum), PhantomData)
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        let (difference, borrow) = self.0.overflowing_sub(&other.0);
+        let correction = select(mask(borrow), &P::MODULUS, &U256::ZERO);
+
+        Self(difference.wrapping_add(&correction), PhantomData)
+    }
+
+    /// Montgomery multiplication, coarsely integrated operand scanning. The accumulator gets
+    /// two limbs on top, as moduli may be close to 2^256
+    fn mul(&self, other: &Self) -> Self {
+        let a = &self.0 .0;
+        let b = &other.0 .0;
+        let p = &P::MODULUS.0;
+        let mut t = [0u32; LIMBS + 2];
+        for &limb in b {
+            // t += a * limb
+            let mut carry = 0u64;
+            for j in 0..LIMBS {
+                let product = (a[j] as u64) * (limb as u64) + (t[j] as u64) + carry;
+                t[j] = product as u32;
+                carry = product >> 32;
+            }
+            let sum = (t[LIMBS] as u64) + carry;
+            t[LIMBS] = sum as u32;
+            t[LIMBS + 1] = (sum >> 32) as u32;
+
+            // t = (t + m * p) / 2^32, with m that makes the lowest limb zero
+            let m = t[0].wrapping_mul(P::INV);
+            let mut carry = ((m as u64) * (p[0] as u64) + (t[0] as u64)) >> 32;
+            for j in 1..LIMBS {
+                let product = (m as u64) * (p[j] as u64) + (t[j] as u64) + carry;
+                t[j - 1] = product as u32;
+                carry = product >> 32;
+            }
+            let sum = (t[LIMBS] as u64) + carry;
+            t[LIMBS - 1] = sum as u32;
+            t[LIMBS] = t[LIMBS + 1] + (sum >> 32) as u32;
+        }
+
+        // t < 2p, subtract p once if needed
+        let mut result = U256::ZERO;
+        result.0.copy_from_slice(&t[..LIMBS]);
+        let (reduced, borrow) = result.overflowing_sub(&P::MODULUS);
+
+        Self(
+            select(mask(t[LIMBS] != 0 || !borrow), &reduced, &result),
+            PhantomData,
+        )
+    }
+
+    /// By Fermat's little theorem
+    fn inverse(&self) -> Self {
+        self.pow(&P::MODULUS.wrapping_sub(&U256::from_u32(2)))
+    }
+}
+
+/// Quadratic extension `Fp[u] / (u^2 + 1)`. -1 is not a square for moduli that are 3 mod 4
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fp2<P: FieldParameters> {
+    pub c0: Fp<P>,
+    pub c1: Fp<P>,
+}
+
+impl<P: FieldParameters> Fp2<P> {
+    #[must_use]
+    pub const fn new(c0: Fp<P>, c1: Fp<P>) -> Self {
+        Self { c0, c1 }
+    }
+
+    /// Frobenius map, `self^p`
+    #[must_use]
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.c0, self.c1.neg())
+    }
+
+    #[must_use]
+    pub fn mul_by_fp(&self, other: &Fp<P>) -> Self {
+        Self::new(self.c0.mul(other), self.c1.mul(other))
+    }
+}
+
+impl<P: FieldParameters> Field for Fp2<P> {
+    const ZERO: Self = Self::new(Fp::ZERO, Fp::ZERO);
+    const ONE: Self = Self::new(Fp::ONE, Fp::ZERO);
+
+    fn is_zero(&self) -> bool {
+        self.c0.is_zero() && self.c1.is_zero()
+    }
+
+    fn select(choice: bool, if_true: &Self, if_false: &Self) -> Self {
+        Self::new(
+            Fp::select(choice, &if_true.c0, &if_false.c0),
+            Fp::select(choice, &if_true.c1, &if_false.c1),
+        )
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Self::new(self.c0.add(&other.c0), self.c1.add(&other.c1))
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        Self::new(self.c0.sub(&other.c0), self.c1.sub(&other.c1))
+    }
+
+    /// Karatsuba, three multiplications in the base field
+    fn mul(&self, other: &Self) -> Self {
+        let v0 = self.c0.mul(&other.c0);
+        let v1 = self.c1.mul(&other.c1);
+        let c1 = self
+            .c0
+            .add(&self.c1)
+            .mul(&other.c0.add(&other.c1))
+            .sub(&v0.add(&v1));
+
+        Self::new(v0.sub(&v1), c1)
+    }
+
+    fn square(&self) -> Self {
+        let c0 = self.c0.add(&self.c1).mul(&self.c0.sub(&self.c1));
+
+        Self::new(c0, self.c0.mul(&self.c1).double())
+    }
+
+    fn inverse(&self) -> Self {
+        let norm_inverse = self.c0.square().add(&self.c1.square()).inverse();
+
+        Self::new(self.c0.mul(&norm_inverse), self.c1.neg().mul(&norm_inverse))
+    }
+}
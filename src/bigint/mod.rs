@@ -0,0 +1,6 @@
+pub mod arith;
+pub mod field;
+pub mod modexp;
+pub mod u256;
+
+pub use self::u256::U256;
@@ -0,0 +1,275 @@
+use super::arith;
+
+// Modular exponentiation of arbitrary length numbers, the precompile 0x05 (EIP-198). Input is
+// the lengths of the base, exponent and modulus as 32-byte words, then the three of them
+// big-endian, everything past the end of the input is zeroes. Numbers are reduced after every
+// multiplication with the long division of `arith`, so even moduli work as well. The caller
+// gives the scratch, as it is proportional to the modulus. Lengths have no limit of their own,
+// the gas grows with the square of the longest of the base and modulus, and is charged before
+// the scratch is allocated from whatever memory is left
+
+pub const HEADER_LEN: usize = 96;
+/// Gas of EIP-2565
+pub const MIN_COST: u64 = 200;
+pub const COST_DIVISOR: u64 = 3;
+
+/// Length at `offset` of the header, saturated
+fn read_len(input: &[u8], offset: usize) -> u64 {
+    let mut word = [0u8; 32];
+    for (i, byte) in word.iter_mut().enumerate() {
+        *byte = input.get(offset + i).copied().unwrap_or(0);
+    }
+    if word[..24].iter().any(|byte| *byte != 0) {
+        return u64::MAX;
+    }
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&word[24..]);
+
+    u64::from_be_bytes(bytes)
+}
+
+/// Number of `len` bytes at `offset` of the input, that may be past its end
+#[derive(Clone, Copy)]
+struct Operand<'a> {
+    bytes: &'a [u8],
+    len: usize,
+}
+
+impl<'a> Operand<'a> {
+    fn new(input: &'a [u8], offset: u64, len: usize) -> Self {
+        let start = core::cmp::min(offset, input.len() as u64) as usize;
+        let end = core::cmp::min(offset.saturating_add(len as u64), input.len() as u64) as usize;
+
+        Self {
+            bytes: &input[start..end],
+            len,
+        }
+    }
+
+    fn byte(&self, index: usize) -> u8 {
+        self.bytes.get(index).copied().unwrap_or(0)
+    }
+
+    /// Little-endian limbs, `limbs` must be long enough
+    fn to_limbs(self, limbs: &mut [u32]) {
+        limbs.fill(0);
+        for i in 0..self.len {
+            let position = self.len - 1 - i;
+            limbs[position / 4] |= (self.byte(i) as u32) << (8 * (position % 4));
+        }
+    }
+}
+
+/// Length at `offset` of the header, `None` if it doesn't fit into the address space
+fn checked_len(input: &[u8], offset: usize) -> Option<usize> {
+    usize::try_from(read_len(input, offset)).ok()
+}
+
+/// Operands of the input, `None` if any of them is too long
+fn operands(input: &[u8]) -> Option<(Operand<'_>, Operand<'_>, Operand<'_>)> {
+    let base_len = checked_len(input, 0)?;
+    let exponent_len = checked_len(input, 32)?;
+    let modulus_len = checked_len(input, 64)?;
+    let exponent_offset = (HEADER_LEN as u64).checked_add(base_len as u64)?;
+    let modulus_offset = exponent_offset.checked_add(exponent_len as u64)?;
+
+    Some((
+        Operand::new(input, HEADER_LEN as u64, base_len),
+        Operand::new(input, exponent_offset, exponent_len),
+        Operand::new(input, modulus_offset, modulus_len),
+    ))
+}
+
+/// Length of the output, that is the modulus length. `None` if the input is invalid, because
+/// the lengths don't fit into the address space
+#[must_use]
+pub fn output_len(input: &[u8]) -> Option<usize> {
+    operands(input).map(|(_, _, modulus)| modulus.len)
+}
+
+/// Gas of the input (EIP-2565), saturated
+#[must_use]
+pub fn cost(input: &[u8]) -> u64 {
+    let base_len = read_len(input, 0);
+    let exponent_len = read_len(input, 32);
+    let modulus_len = read_len(input, 64);
+
+    let words = (core::cmp::max(base_len, modulus_len) as u128).div_ceil(8);
+    let complexity = words.saturating_mul(words);
+
+    // bit length of the first 32 bytes of the exponent, and 8 per byte after them
+    let exponent_offset = (HEADER_LEN as u64).saturating_add(base_len);
+    let head = Operand::new(input, exponent_offset, exponent_len.min(32) as usize);
+    let head_bits = (0..head.len).find(|i| head.byte(*i) != 0).map_or(0, |i| {
+        8 * (head.len - i) as u64 - head.byte(i).leading_zeros() as u64
+    });
+    let iterations = (exponent_len.saturating_sub(32) as u128)
+        .saturating_mul(8)
+        .saturating_add(head_bits.saturating_sub(1) as u128);
+    let iterations = core::cmp::max(iterations, 1);
+
+    let cost = complexity.saturating_mul(iterations) / COST_DIVISOR as u128;
+
+    core::cmp::max(u64::try_from(cost).unwrap_or(u64::MAX), MIN_COST)
+}
+
+#[inline(always)]
+const fn limbs(len: usize) -> usize {
+    if len == 0 {
+        1
+    } else {
+        len.div_ceil(4)
+    }
+}
+
+/// Limbs of the scratch that `modexp` needs for a modulus of `modulus_len` bytes, `None` if
+/// they don't fit into the address space
+#[must_use]
+pub const fn scratch_len(modulus_len: usize) -> Option<usize> {
+    match limbs(modulus_len).checked_mul(8) {
+        Some(len) => len.checked_add(2),
+        None => None,
+    }
+}
+
+/// Writes `base^exponent mod modulus` of the input to `output`, that is as long as the modulus.
+/// Zero modulus gives zero. Returns `false`, writing nothing, if the input is invalid
+#[must_use]
+pub fn modexp(input: &[u8], output: &mut [u8], scratch: &mut [u32]) -> bool {
+    let Some((base, exponent, modulus)) = operands(input) else {
+        return false;
+    };
+    output.fill(0);
+
+    let n = limbs(modulus.len);
+    let (m, scratch) = scratch.split_at_mut(n);
+    let (den_scratch, scratch) = scratch.split_at_mut(n);
+    let (result, scratch) = scratch.split_at_mut(n);
+    let (reduced_base, scratch) = scratch.split_at_mut(n);
+    let (wide, div_scratch) = scratch.split_at_mut(2 * n + 1);
+    modulus.to_limbs(m);
+    if arith::significant_limbs(m) == 0 {
+        return true;
+    }
+
+    // base mod m, a byte at a time
+    reduced_base.fill(0);
+    for i in 0..base.len {
+        wide[n] = arith::shl_into(&mut wide[..n], reduced_base, 8);
+        wide[0] |= base.byte(i) as u32;
+        arith::div_rem(
+            &wide[..n + 1],
+            m,
+            &mut [],
+            reduced_base,
+            div_scratch,
+            den_scratch,
+        );
+    }
+
+    // 1 mod m, that is zero for m = 1
+    wide.fill(0);
+    wide[0] = 1;
+    arith::div_rem(&wide[..1], m, &mut [], result, div_scratch, den_scratch);
+
+    // left to right square and multiply, from the first set bit. Bytes past the input are
+    // zeroes, a set bit is in the ones that are there if any
+    let first = exponent.bytes.iter().position(|byte| *byte != 0);
+    for i in first.into_iter().flat_map(|first| first..exponent.len) {
+        let byte = exponent.byte(i);
+        for bit in (0..8).rev() {
+            arith::mul(&mut wide[..2 * n], result, result);
+            arith::div_rem(&wide[..2 * n], m, &mut [], result, div_scratch, den_scratch);
+            if byte & (1 << bit) != 0 {
+                arith::mul(&mut wide[..2 * n], result, reduced_base);
+                arith::div_rem(&wide[..2 * n], m, &mut [], result, div_scratch, den_scratch);
+            }
+        }
+    }
+
+    for (i, byte) in output.iter_mut().rev().enumerate() {
+        *byte = (result[i / 4] >> (8 * (i % 4))) as u8;
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex<const N: usize>(digits: &str) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).unwrap();
+        }
+
+        bytes
+    }
+
+    fn header(base_len: u8, exponent_len: u8, modulus_len: u8) -> [u8; HEADER_LEN] {
+        let mut header = [0u8; HEADER_LEN];
+        header[31] = base_len;
+        header[63] = exponent_len;
+        header[95] = modulus_len;
+
+        header
+    }
+
+    fn run<const N: usize>(input: &[u8]) -> [u8; N] {
+        assert_eq!(output_len(input), Some(N));
+        let mut output = [0xaa; N];
+        let mut scratch = [0xdead_beef; 128];
+        assert!(modexp(
+            input,
+            &mut output,
+            &mut scratch[..scratch_len(N).unwrap()]
+        ));
+
+        output
+    }
+
+    const P: &str = "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+    const P_MINUS_1: &str = "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e";
+
+    /// Examples of EIP-198, with the gas of EIP-2565
+    #[test]
+    fn eip198_examples() {
+        // Fermat's little theorem
+        let mut input = [0u8; HEADER_LEN + 65];
+        input[..HEADER_LEN].copy_from_slice(&header(1, 32, 32));
+        input[HEADER_LEN] = 3;
+        input[HEADER_LEN + 1..HEADER_LEN + 33].copy_from_slice(&hex::<32>(P_MINUS_1));
+        input[HEADER_LEN + 33..].copy_from_slice(&hex::<32>(P));
+        assert_eq!(cost(&input), 1360);
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        assert_eq!(run::<32>(&input), one);
+
+        // empty base is zero
+        let mut input = [0u8; HEADER_LEN + 64];
+        input[..HEADER_LEN].copy_from_slice(&header(0, 32, 32));
+        input[HEADER_LEN..HEADER_LEN + 32].copy_from_slice(&hex::<32>(P_MINUS_1));
+        input[HEADER_LEN + 32..].copy_from_slice(&hex::<32>(P));
+        assert_eq!(cost(&input), 1360);
+        assert_eq!(run::<32>(&input), [0u8; 32]);
+
+        // modulus of 2^256 - 1 bytes costs more than there is gas
+        let mut input = [0u8; HEADER_LEN + 64];
+        input[..HEADER_LEN].copy_from_slice(&header(0, 32, 0));
+        input[64..HEADER_LEN].fill(0xff);
+        input[HEADER_LEN..HEADER_LEN + 32].fill(0xff);
+        input[HEADER_LEN + 32..].copy_from_slice(&hex::<32>(P));
+        assert_eq!(cost(&input), u64::MAX);
+        assert_eq!(output_len(&input).and_then(scratch_len), None);
+
+        // the modulus is 0x80 and 31 zeroes past the end of the input
+        let mut input = [0u8; HEADER_LEN + 4];
+        input[..HEADER_LEN].copy_from_slice(&header(1, 2, 32));
+        input[HEADER_LEN..].copy_from_slice(&[0x03, 0xff, 0xff, 0x80]);
+        assert_eq!(
+            run::<32>(&input),
+            hex("3b01b01ac41f2d6e917c6d6a221ce793802469026d9ab7578fa2e79e4da6aaab")
+        );
+    }
+}
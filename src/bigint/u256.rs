@@ -0,0 +1,491 @@
+use core::cmp::Ordering;
+
+use super::arith;
+
+/// 256-bit unsigned integer stored as little-endian 32-bit limbs, so all arithmetic
+/// maps directly onto RV32IM instructions
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct U256(pub [u32; 8]);
+
+impl U256 {
+    pub const ZERO: Self = Self([0u32; 8]);
+    pub const ONE: Self = Self([1, 0, 0, 0, 0, 0, 0, 0]);
+    pub const MAX: Self = Self([u32::MAX; 8]);
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn from_u32(value: u32) -> Self {
+        let mut result = Self::ZERO;
+        result.0[0] = value;
+
+        result
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn from_u64(value: u64) -> Self {
+        let mut result = Self::ZERO;
+        result.0[0] = value as u32;
+        result.0[1] = (value >> 32) as u32;
+
+        result
+    }
+
+    #[must_use]
+    pub const fn from_be_bytes(bytes: &[u8; 32]) -> Self {
+        let mut result = Self::ZERO;
+        let mut i = 0;
+        while i < 8 {
+            let offset = 32 - 4 * (i + 1);
+            result.0[i] = u32::from_be_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ]);
+            i += 1;
+        }
+
+        result
+    }
+
+    #[must_use]
+    pub const fn to_be_bytes(&self) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        let mut i = 0;
+        while i < 8 {
+            let offset = 32 - 4 * (i + 1);
+            let limb = self.0[i].to_be_bytes();
+            result[offset] = limb[0];
+            result[offset + 1] = limb[1];
+            result[offset + 2] = limb[2];
+            result[offset + 3] = limb[3];
+            i += 1;
+        }
+
+        result
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn is_zero(&self) -> bool {
+        let mut i = 0;
+        while i < 8 {
+            if self.0[i] != 0 {
+                return false;
+            }
+            i += 1;
+        }
+
+        true
+    }
+
+    /// Returns the value as u64 if it fits
+    #[must_use]
+    pub const fn as_u64(&self) -> Option<u64> {
+        let mut i = 2;
+        while i < 8 {
+            if self.0[i] != 0 {
+                return None;
+            }
+            i += 1;
+        }
+
+        Some((self.0[0] as u64) | ((self.0[1] as u64) << 32))
+    }
+
+    #[must_use]
+    pub const fn overflowing_add(&self, other: &Self) -> (Self, bool) {
+        let mut result = Self::ZERO;
+        let mut carry = false;
+        let mut i = 0;
+        while i < 8 {
+            let (t, of0) = self.0[i].overflowing_add(other.0[i]);
+            let (t, of1) = t.overflowing_add(carry as u32);
+            result.0[i] = t;
+            carry = of0 | of1;
+            i += 1;
+        }
+
+        (result, carry)
+    }
+
+    #[must_use]
+    pub const fn overflowing_sub(&self, other: &Self) -> (Self, bool) {
+        let mut result = Self::ZERO;
+        let mut borrow = false;
+        let mut i = 0;
+        while i < 8 {
+            let (t, of0) = self.0[i].overflowing_sub(other.0[i]);
+            let (t, of1) = t.overflowing_sub(borrow as u32);
+            result.0[i] = t;
+            borrow = of0 | of1;
+            i += 1;
+        }
+
+        (result, borrow)
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn wrapping_add(&self, other: &Self) -> Self {
+        self.overflowing_add(other).0
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn wrapping_sub(&self, other: &Self) -> Self {
+        self.overflowing_sub(other).0
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn checked_add(&self, other: &Self) -> Option<Self> {
+        match self.overflowing_add(other) {
+            (result, false) => Some(result),
+            (_, true) => None,
+        }
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn checked_sub(&self, other: &Self) -> Option<Self> {
+        match self.overflowing_sub(other) {
+            (result, false) => Some(result),
+            (_, true) => None,
+        }
+    }
+
+    #[must_use]
+    pub fn wrapping_mul(&self, other: &Self) -> Self {
+        let mut result = Self::ZERO;
+        arith::mul(&mut result.0, &self.0, &other.0);
+
+        result
+    }
+
+    #[must_use]
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let mut wide = [0u32; 16];
+        arith::mul(&mut wide, &self.0, &other.0);
+        if wide[8..].iter().any(|limb| *limb != 0) {
+            return None;
+        }
+        let mut result = Self::ZERO;
+        result.0.copy_from_slice(&wide[..8]);
+
+        Some(result)
+    }
+
+    /// Returns `(self / other, self % other)`, or zeros when dividing by zero
+    #[must_use]
+    pub fn div_rem(&self, other: &Self) -> (Self, Self) {
+        let mut quotient = Self::ZERO;
+        let mut remainder = Self::ZERO;
+        let mut scratch = [0u32; 9];
+        let mut den_scratch = [0u32; 8];
+        arith::div_rem(
+            &self.0,
+            &other.0,
+            &mut quotient.0,
+            &mut remainder.0,
+            &mut scratch,
+            &mut den_scratch,
+        );
+
+        (quotient, remainder)
+    }
+
+    #[must_use]
+    pub fn add_mod(&self, other: &Self, modulus: &Self) -> Self {
+        if modulus.is_zero() {
+            return Self::ZERO;
+        }
+        let (sum, carry) = self.overflowing_add(other);
+        let mut wide = [0u32; 9];
+        wide[..8].copy_from_slice(&sum.0);
+        wide[8] = carry as u32;
+
+        Self::reduce_wide(&wide, modulus)
+    }
+
+    #[must_use]
+    pub fn mul_mod(&self, other: &Self, modulus: &Self) -> Self {
+        if modulus.is_zero() {
+            return Self::ZERO;
+        }
+        let mut wide = [0u32; 16];
+        arith::mul(&mut wide, &self.0, &other.0);
+
+        Self::reduce_wide(&wide, modulus)
+    }
+
+    fn reduce_wide(wide: &[u32], modulus: &Self) -> Self {
+        let mut quotient = [0u32; 0];
+        let mut remainder = Self::ZERO;
+        let mut scratch = [0u32; 17];
+        let mut den_scratch = [0u32; 8];
+        arith::div_rem(
+            wide,
+            &modulus.0,
+            &mut quotient,
+            &mut remainder.0,
+            &mut scratch,
+            &mut den_scratch,
+        );
+
+        remainder
+    }
+
+    #[must_use]
+    pub fn wrapping_pow(&self, exponent: &Self) -> Self {
+        let mut result = Self::ONE;
+        let bits = exponent.bits();
+        for i in (0..bits).rev() {
+            result = result.wrapping_mul(&result);
+            if exponent.bit(i) {
+                result = result.wrapping_mul(self);
+            }
+        }
+
+        result
+    }
+
+    /// Number of significant bits
+    #[must_use]
+    pub const fn bits(&self) -> u32 {
+        let mut i = 8;
+        while i > 0 {
+            i -= 1;
+            if self.0[i] != 0 {
+                return 32 * (i as u32) + 32 - self.0[i].leading_zeros();
+            }
+        }
+
+        0
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn bit(&self, index: u32) -> bool {
+        (self.0[(index / 32) as usize] >> (index % 32)) & 1 == 1
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn is_negative(&self) -> bool {
+        self.0[7] >> 31 == 1
+    }
+
+    #[must_use]
+    pub const fn wrapping_neg(&self) -> Self {
+        Self::ZERO.overflowing_sub(self).0
+    }
+
+    #[must_use]
+    pub const fn not(&self) -> Self {
+        let mut result = *self;
+        let mut i = 0;
+        while i < 8 {
+            result.0[i] = !result.0[i];
+            i += 1;
+        }
+
+        result
+    }
+
+    #[must_use]
+    pub const fn and(&self, other: &Self) -> Self {
+        let mut result = *self;
+        let mut i = 0;
+        while i < 8 {
+            result.0[i] &= other.0[i];
+            i += 1;
+        }
+
+        result
+    }
+
+    #[must_use]
+    pub const fn or(&self, other: &Self) -> Self {
+        let mut result = *self;
+        let mut i = 0;
+        while i < 8 {
+            result.0[i] |= other.0[i];
+            i += 1;
+        }
+
+        result
+    }
+
+    #[must_use]
+    pub const fn xor(&self, other: &Self) -> Self {
+        let mut result = *self;
+        let mut i = 0;
+        while i < 8 {
+            result.0[i] ^= other.0[i];
+            i += 1;
+        }
+
+        result
+    }
+
+    #[must_use]
+    pub const fn shl(&self, shift: u32) -> Self {
+        if shift >= 256 {
+            return Self::ZERO;
+        }
+        let limbs = (shift / 32) as usize;
+        let bits = shift % 32;
+        let mut result = Self::ZERO;
+        let mut i = limbs;
+        while i < 8 {
+            result.0[i] = self.0[i - limbs] << bits;
+            if bits != 0 && i > limbs {
+                result.0[i] |= self.0[i - limbs - 1] >> (32 - bits);
+            }
+            i += 1;
+        }
+
+        result
+    }
+
+    #[must_use]
+    pub const fn shr(&self, shift: u32) -> Self {
+        if shift >= 256 {
+            return Self::ZERO;
+        }
+        let limbs = (shift / 32) as usize;
+        let bits = shift % 32;
+        let mut result = Self::ZERO;
+        let mut i = 0;
+        while i + limbs < 8 {
+            result.0[i] = self.0[i + limbs] >> bits;
+            if bits != 0 && i + limbs + 1 < 8 {
+                result.0[i] |= self.0[i + limbs + 1] << (32 - bits);
+            }
+            i += 1;
+        }
+
+        result
+    }
+
+    /// Arithmetic shift right
+    #[must_use]
+    pub const fn sar(&self, shift: u32) -> Self {
+        if !self.is_negative() {
+            return self.shr(shift);
+        }
+        if shift >= 256 {
+            return Self::MAX;
+        }
+
+        self.not().shr(shift).not()
+    }
+
+    /// Shift amount as used by EVM: anything that doesn't fit into u32 is too large anyway
+    #[must_use]
+    pub const fn as_shift(&self) -> u32 {
+        match self.as_u64() {
+            Some(value) if value < 256 => value as u32,
+            _ => 256,
+        }
+    }
+
+    #[must_use]
+    pub fn signed_cmp(&self, other: &Self) -> Ordering {
+        match (self.is_negative(), other.is_negative()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ => self.cmp(other),
+        }
+    }
+
+    #[must_use]
+    pub fn signed_div(&self, other: &Self) -> Self {
+        let negative = self.is_negative() != other.is_negative();
+        let (quotient, _) = self.abs().div_rem(&other.abs());
+        if negative {
+            quotient.wrapping_neg()
+        } else {
+            quotient
+        }
+    }
+
+    #[must_use]
+    pub fn signed_rem(&self, other: &Self) -> Self {
+        let (_, remainder) = self.abs().div_rem(&other.abs());
+        if self.is_negative() {
+            remainder.wrapping_neg()
+        } else {
+            remainder
+        }
+    }
+
+    #[must_use]
+    pub const fn abs(&self) -> Self {
+        if self.is_negative() {
+            self.wrapping_neg()
+        } else {
+            *self
+        }
+    }
+
+    /// Extends the sign of the lowest `byte_index + 1` bytes
+    #[must_use]
+    pub const fn sign_extend(&self, byte_index: &Self) -> Self {
+        let byte_index = match byte_index.as_u64() {
+            Some(value) if value < 31 => value as u32,
+            _ => return *self,
+        };
+        let sign_bit = byte_index * 8 + 7;
+        let mask = Self::ONE.shl(sign_bit + 1).overflowing_sub(&Self::ONE).0;
+        if self.bit(sign_bit) {
+            self.or(&mask.not())
+        } else {
+            self.and(&mask)
+        }
+    }
+
+    /// Byte of the big-endian representation
+    #[must_use]
+    pub const fn byte(&self, index: &Self) -> Self {
+        match index.as_u64() {
+            Some(value) if value < 32 => {
+                let bytes = self.to_be_bytes();
+                Self::from_u32(bytes[value as usize] as u32)
+            }
+            _ => Self::ZERO,
+        }
+    }
+
+    /// Returns the value as usize if it fits, saturating otherwise
+    #[must_use]
+    pub const fn as_usize_saturated(&self) -> usize {
+        match self.as_u64() {
+            Some(value) if value <= usize::MAX as u64 => value as usize,
+            _ => usize::MAX,
+        }
+    }
+}
+
+
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for i in (0..8).rev() {
+            match self.0[i].cmp(&other.0[i]) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            }
+        }
+
+        Ordering::Equal
+    }
+}
@@ -0,0 +1,81 @@
+use crate::cpu::Registers;
+use crate::pmp::{self, PmpConfig};
+use crate::timer;
+use crate::user_mode::{self, UserContext, UserExit};
+
+use riscv::register::{minstret, satp};
+
+// Execution contexts of the contracts that run in U-mode. IPC is blocking and serial: a contract
+// that calls another one is suspended in its syscall for the whole call, while the callee gets
+// the hart. The context of the caller stays in its call frame on the kernel stack: the registers
+// and pc, that the trap entry saves on every exit, and PMP and satp, that the callee reprograms.
+// Resuming a context switches the hart to it, and back to whatever the kernel had before once
+// the contract exits, so nested executions never see each other's mappings. Resources are split
+// by the caller before the call and reclaimed after it, as for the interpreters. A context runs
+// with the timer armed at its budget, a nested one rearms it for itself, and the caller's is
+// armed again when the caller resumes.
+
+/// satp of every context, there is no translation
+pub const SATP_BARE: usize = 0;
+
+/// Suspended contract execution: registers and pc, and the memory it may access
+pub struct Context {
+    pub user: UserContext,
+    pub pmp: PmpConfig,
+    pub satp: usize,
+}
+
+/// State of the hart that the kernel gets back after a context exits
+struct Saved {
+    pmp: PmpConfig,
+    satp: usize,
+}
+
+impl Context {
+    /// Starts at `pc` with nothing mapped
+    #[must_use]
+    pub const fn new(pc: u32) -> Self {
+        Self {
+            user: UserContext::new(pc),
+            pmp: PmpConfig::EMPTY,
+            satp: SATP_BARE,
+        }
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn register(&self, register: Registers) -> u32 {
+        self.user.register(register)
+    }
+
+    #[inline(always)]
+    pub fn set_register(&mut self, register: Registers, value: u32) {
+        self.user.set_register(register, value);
+    }
+
+    /// Runs the contract until its next exit, or until it retires about `budget` instructions,
+    /// returns the exit along with the instructions retired
+    pub fn resume(&mut self, budget: u64) -> (UserExit, u64) {
+        let saved = Saved {
+            pmp: pmp::current(),
+            satp: satp::read().bits(),
+        };
+        unsafe {
+            pmp::apply(&self.pmp);
+        }
+        satp::write(self.satp);
+
+        let start = minstret::read64();
+        timer::arm(budget);
+        let exit = user_mode::run(&mut self.user);
+        timer::disarm();
+        let retired = minstret::read64().wrapping_sub(start);
+
+        unsafe {
+            pmp::apply(&saved.pmp);
+        }
+        satp::write(saved.satp);
+
+        (exit, retired)
+    }
+}
@@ -1,3 +1,6 @@
+use crate::helper_reg_utils::*;
+use crate::utils::sign_extend;
+
 #[repr(usize)]
 pub enum Registers {
     Zero = 0,
@@ -39,42 +42,536 @@ pub const fn gp(r: Registers) -> usize {
     r as usize
 }
 
-#[repr(C)]
-#[derive(Clone, Copy)]
-pub struct TrapFrame {
-    pub regs: [u32; 32], // 0..128
-                         // pub satp:   u32,       // 128..132
-                         // pub pc:     u32,       // 132..136
-                         // pub qm:     u32,       // 136..140
-                         // pub pid:    u32,       // 140..144
-                         // pub mode:   u32,       // 144..148
-}
-
-impl TrapFrame {
-    pub const fn new() -> Self {
-        TrapFrame {
-            regs: [0; 32],
-            // satp:   0,
-            // pc:     0,
-            // qm:     1,
-            // pid:    0,
-            // mode:   0,
+pub const OPCODE_LOAD: u32 = 0b0000011;
+pub const OPCODE_MISC_MEM: u32 = 0b0001111;
+pub const OPCODE_OP_IMM: u32 = 0b0010011;
+pub const OPCODE_AUIPC: u32 = 0b0010111;
+pub const OPCODE_STORE: u32 = 0b0100011;
+pub const OPCODE_OP: u32 = 0b0110011;
+pub const OPCODE_LUI: u32 = 0b0110111;
+pub const OPCODE_BRANCH: u32 = 0b1100011;
+pub const OPCODE_JALR: u32 = 0b1100111;
+pub const OPCODE_JAL: u32 = 0b1101111;
+pub const OPCODE_SYSTEM: u32 = 0b1110011;
+
+const FUNCT7_ALT: u32 = 0b0100000;
+const FUNCT7_MULDIV: u32 = 0b0000001;
+const FUNCT7_SFENCE_VMA: u32 = 0b0001001;
+const IMM_ECALL: u32 = 0x000;
+const IMM_EBREAK: u32 = 0x001;
+const IMM_SRET: u32 = 0x102;
+const IMM_WFI: u32 = 0x105;
+const IMM_MRET: u32 = 0x302;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BranchCondition {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+    Ltu,
+    Geu,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadWidth {
+    Byte,
+    Half,
+    Word,
+    ByteUnsigned,
+    HalfUnsigned,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoreWidth {
+    Byte,
+    Half,
+    Word,
+}
+
+/// Register-register operations, the immediate forms use the ones of RV32I but `Sub`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AluOp {
+    Add,
+    Sub,
+    Sll,
+    Slt,
+    Sltu,
+    Xor,
+    Srl,
+    Sra,
+    Or,
+    And,
+    Mul,
+    Mulh,
+    Mulhsu,
+    Mulhu,
+    Div,
+    Divu,
+    Rem,
+    Remu,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsrOp {
+    ReadWrite,
+    ReadSet,
+    ReadClear,
+}
+
+/// Decoded RV32IM instruction, with the privileged ones of the SYSTEM opcode. Registers are
+/// numbers, and immediates are sign-extended, except for the upper ones of `Lui` and `Auipc`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    Lui {
+        rd: u8,
+        imm: u32,
+    },
+    Auipc {
+        rd: u8,
+        imm: u32,
+    },
+    Jal {
+        rd: u8,
+        offset: i32,
+    },
+    Jalr {
+        rd: u8,
+        rs1: u8,
+        offset: i32,
+    },
+    Branch {
+        condition: BranchCondition,
+        rs1: u8,
+        rs2: u8,
+        offset: i32,
+    },
+    Load {
+        width: LoadWidth,
+        rd: u8,
+        rs1: u8,
+        offset: i32,
+    },
+    Store {
+        width: StoreWidth,
+        rs1: u8,
+        rs2: u8,
+        offset: i32,
+    },
+    /// Shifts have the amount in `imm`
+    OpImm {
+        op: AluOp,
+        rd: u8,
+        rs1: u8,
+        imm: i32,
+    },
+    Op {
+        op: AluOp,
+        rd: u8,
+        rs1: u8,
+        rs2: u8,
+    },
+    Fence {
+        predecessor: u8,
+        successor: u8,
+    },
+    FenceI,
+    Ecall,
+    Ebreak,
+    /// `immediate` forms take `rs1` as a 5-bit value
+    Csr {
+        op: CsrOp,
+        immediate: bool,
+        rd: u8,
+        rs1: u8,
+        csr: u16,
+    },
+    Mret,
+    Sret,
+    Wfi,
+    SfenceVma {
+        rs1: u8,
+        rs2: u8,
+    },
+}
+
+impl Instruction {
+    /// `None` for the invalid encodings, and anything outside of RV32IM, compressed ones included
+    #[must_use]
+    pub const fn decode(src: u32) -> Option<Self> {
+        if src & 0b11 != 0b11 {
+            return None;
         }
+        let rd = get_rd(src) as u8;
+        let rs1 = ITypeOpcode::rs1(src) as u8;
+        let rs2 = RTypeOpcode::rs2(src) as u8;
+        let funct3 = ITypeOpcode::funct3(src);
+        let funct7 = RTypeOpcode::funct7(src);
+        let i_imm = sign_extended(ITypeOpcode::imm(src), 12);
+
+        let instruction = match get_opcode(src) {
+            OPCODE_LUI => Self::Lui {
+                rd,
+                imm: UTypeOpcode::imm(src),
+            },
+            OPCODE_AUIPC => Self::Auipc {
+                rd,
+                imm: UTypeOpcode::imm(src),
+            },
+            OPCODE_JAL => Self::Jal {
+                rd,
+                offset: sign_extended(JTypeOpcode::imm(src), 21),
+            },
+            OPCODE_JALR if funct3 == 0 => Self::Jalr {
+                rd,
+                rs1,
+                offset: i_imm,
+            },
+            OPCODE_BRANCH => {
+                let condition = match funct3 {
+                    0 => BranchCondition::Eq,
+                    1 => BranchCondition::Ne,
+                    4 => BranchCondition::Lt,
+                    5 => BranchCondition::Ge,
+                    6 => BranchCondition::Ltu,
+                    7 => BranchCondition::Geu,
+                    _ => return None,
+                };
+                Self::Branch {
+                    condition,
+                    rs1,
+                    rs2,
+                    offset: sign_extended(BTypeOpcode::imm(src), 13),
+                }
+            }
+            OPCODE_LOAD => {
+                let width = match funct3 {
+                    0 => LoadWidth::Byte,
+                    1 => LoadWidth::Half,
+                    2 => LoadWidth::Word,
+                    4 => LoadWidth::ByteUnsigned,
+                    5 => LoadWidth::HalfUnsigned,
+                    _ => return None,
+                };
+                Self::Load {
+                    width,
+                    rd,
+                    rs1,
+                    offset: i_imm,
+                }
+            }
+            OPCODE_STORE => {
+                let width = match funct3 {
+                    0 => StoreWidth::Byte,
+                    1 => StoreWidth::Half,
+                    2 => StoreWidth::Word,
+                    _ => return None,
+                };
+                Self::Store {
+                    width,
+                    rs1,
+                    rs2,
+                    offset: sign_extended(STypeOpcode::imm(src), 12),
+                }
+            }
+            OPCODE_OP_IMM => {
+                let (op, imm) = match (funct3, funct7) {
+                    (0, _) => (AluOp::Add, i_imm),
+                    (1, 0) => (AluOp::Sll, rs2 as i32),
+                    (2, _) => (AluOp::Slt, i_imm),
+                    (3, _) => (AluOp::Sltu, i_imm),
+                    (4, _) => (AluOp::Xor, i_imm),
+                    (5, 0) => (AluOp::Srl, rs2 as i32),
+                    (5, FUNCT7_ALT) => (AluOp::Sra, rs2 as i32),
+                    (6, _) => (AluOp::Or, i_imm),
+                    (7, _) => (AluOp::And, i_imm),
+                    _ => return None,
+                };
+                Self::OpImm { op, rd, rs1, imm }
+            }
+            OPCODE_OP => {
+                let op = match (funct7, funct3) {
+                    (0, 0) => AluOp::Add,
+                    (FUNCT7_ALT, 0) => AluOp::Sub,
+                    (0, 1) => AluOp::Sll,
+                    (0, 2) => AluOp::Slt,
+                    (0, 3) => AluOp::Sltu,
+                    (0, 4) => AluOp::Xor,
+                    (0, 5) => AluOp::Srl,
+                    (FUNCT7_ALT, 5) => AluOp::Sra,
+                    (0, 6) => AluOp::Or,
+                    (0, 7) => AluOp::And,
+                    (FUNCT7_MULDIV, 0) => AluOp::Mul,
+                    (FUNCT7_MULDIV, 1) => AluOp::Mulh,
+                    (FUNCT7_MULDIV, 2) => AluOp::Mulhsu,
+                    (FUNCT7_MULDIV, 3) => AluOp::Mulhu,
+                    (FUNCT7_MULDIV, 4) => AluOp::Div,
+                    (FUNCT7_MULDIV, 5) => AluOp::Divu,
+                    (FUNCT7_MULDIV, 6) => AluOp::Rem,
+                    (FUNCT7_MULDIV, 7) => AluOp::Remu,
+                    _ => return None,
+                };
+                Self::Op { op, rd, rs1, rs2 }
+            }
+            OPCODE_MISC_MEM => match funct3 {
+                0 => Self::Fence {
+                    predecessor: ((src >> 24) & 0xf) as u8,
+                    successor: ((src >> 20) & 0xf) as u8,
+                },
+                1 => Self::FenceI,
+                _ => return None,
+            },
+            OPCODE_SYSTEM => match funct3 {
+                0 if rd != 0 => return None,
+                0 if funct7 == FUNCT7_SFENCE_VMA => Self::SfenceVma { rs1, rs2 },
+                0 if rs1 != 0 => return None,
+                0 => match ITypeOpcode::imm(src) {
+                    IMM_ECALL => Self::Ecall,
+                    IMM_EBREAK => Self::Ebreak,
+                    IMM_MRET => Self::Mret,
+                    IMM_SRET => Self::Sret,
+                    IMM_WFI => Self::Wfi,
+                    _ => return None,
+                },
+                4 => return None,
+                _ => {
+                    let op = match funct3 & 3 {
+                        1 => CsrOp::ReadWrite,
+                        2 => CsrOp::ReadSet,
+                        _ => CsrOp::ReadClear,
+                    };
+                    Self::Csr {
+                        op,
+                        immediate: funct3 & 4 != 0,
+                        rd,
+                        rs1,
+                        csr: ITypeOpcode::imm(src) as u16,
+                    }
+                }
+            },
+            _ => return None,
+        };
+
+        Some(instruction)
+    }
+
+    /// Touches the privileged state, or may stall the hart
+    #[must_use]
+    pub const fn is_privileged(&self) -> bool {
+        matches!(
+            self,
+            Self::Csr { .. } | Self::Mret | Self::Sret | Self::Wfi | Self::SfenceVma { .. }
+        )
     }
 }
 
-/// Dumps the registers of a given trap frame. This is NOT the
-/// current CPU registers!
-pub fn dump_registers(frame: *const TrapFrame) {
-    use crate::{print, println};
+#[inline(always)]
+const fn sign_extended(mut value: u32, bits: u32) -> i32 {
+    sign_extend(&mut value, bits);
+
+    value as i32
+}
+
+/// ABI names, by register number
+pub const REGISTER_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+#[inline(always)]
+fn name(register: u8) -> &'static str {
+    REGISTER_NAMES[register as usize & 31]
+}
+
+impl BranchCondition {
+    #[must_use]
+    pub const fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Eq => "beq",
+            Self::Ne => "bne",
+            Self::Lt => "blt",
+            Self::Ge => "bge",
+            Self::Ltu => "bltu",
+            Self::Geu => "bgeu",
+        }
+    }
+}
+
+impl LoadWidth {
+    #[must_use]
+    pub const fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Byte => "lb",
+            Self::Half => "lh",
+            Self::Word => "lw",
+            Self::ByteUnsigned => "lbu",
+            Self::HalfUnsigned => "lhu",
+        }
+    }
+}
+
+impl StoreWidth {
+    #[must_use]
+    pub const fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Byte => "sb",
+            Self::Half => "sh",
+            Self::Word => "sw",
+        }
+    }
+}
+
+impl AluOp {
+    #[must_use]
+    pub const fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Add => "add",
+            Self::Sub => "sub",
+            Self::Sll => "sll",
+            Self::Slt => "slt",
+            Self::Sltu => "sltu",
+            Self::Xor => "xor",
+            Self::Srl => "srl",
+            Self::Sra => "sra",
+            Self::Or => "or",
+            Self::And => "and",
+            Self::Mul => "mul",
+            Self::Mulh => "mulh",
+            Self::Mulhsu => "mulhsu",
+            Self::Mulhu => "mulhu",
+            Self::Div => "div",
+            Self::Divu => "divu",
+            Self::Rem => "rem",
+            Self::Remu => "remu",
+        }
+    }
+}
+
+impl CsrOp {
+    #[must_use]
+    pub const fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::ReadWrite => "csrrw",
+            Self::ReadSet => "csrrs",
+            Self::ReadClear => "csrrc",
+        }
+    }
+}
+
+/// Fence sets as the assembler takes them, e.g. `iorw`
+fn write_fence_set(f: &mut core::fmt::Formatter<'_>, set: u8) -> core::fmt::Result {
+    for (bit, access) in ["w", "r", "o", "i"].iter().enumerate().rev() {
+        if set & (1 << bit) != 0 {
+            f.write_str(access)?;
+        }
+    }
+
+    Ok(())
+}
 
-    print!("   ");
-    for i in 1..32 {
-        if i % 4 == 0 {
-            println!();
-            print!("   ");
+/// Canonical forms, without pseudo-instructions. Jump and branch offsets are relative to the pc
+impl core::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            Self::Lui { rd, imm } => write!(f, "lui {}, {:#x}", name(rd), imm >> 12),
+            Self::Auipc { rd, imm } => write!(f, "auipc {}, {:#x}", name(rd), imm >> 12),
+            Self::Jal { rd, offset } => write!(f, "jal {}, {}", name(rd), offset),
+            Self::Jalr { rd, rs1, offset } => {
+                write!(f, "jalr {}, {}({})", name(rd), offset, name(rs1))
+            }
+            Self::Branch {
+                condition,
+                rs1,
+                rs2,
+                offset,
+            } => write!(
+                f,
+                "{} {}, {}, {}",
+                condition.mnemonic(),
+                name(rs1),
+                name(rs2),
+                offset
+            ),
+            Self::Load {
+                width,
+                rd,
+                rs1,
+                offset,
+            } => write!(
+                f,
+                "{} {}, {}({})",
+                width.mnemonic(),
+                name(rd),
+                offset,
+                name(rs1)
+            ),
+            Self::Store {
+                width,
+                rs1,
+                rs2,
+                offset,
+            } => write!(
+                f,
+                "{} {}, {}({})",
+                width.mnemonic(),
+                name(rs2),
+                offset,
+                name(rs1)
+            ),
+            Self::OpImm { op, rd, rs1, imm } => {
+                // the only immediate form that isn't the register one with an `i`
+                if op == AluOp::Sltu {
+                    f.write_str("sltiu")?;
+                } else {
+                    write!(f, "{}i", op.mnemonic())?;
+                }
+                write!(f, " {}, {}, {}", name(rd), name(rs1), imm)
+            }
+            Self::Op { op, rd, rs1, rs2 } => write!(
+                f,
+                "{} {}, {}, {}",
+                op.mnemonic(),
+                name(rd),
+                name(rs1),
+                name(rs2)
+            ),
+            Self::Fence {
+                predecessor,
+                successor,
+            } => {
+                f.write_str("fence ")?;
+                write_fence_set(f, predecessor)?;
+                f.write_str(", ")?;
+                write_fence_set(f, successor)
+            }
+            Self::FenceI => f.write_str("fence.i"),
+            Self::Ecall => f.write_str("ecall"),
+            Self::Ebreak => f.write_str("ebreak"),
+            Self::Csr {
+                op,
+                immediate,
+                rd,
+                rs1,
+                csr,
+            } => {
+                if immediate {
+                    write!(f, "{}i {}, {:#x}, {}", op.mnemonic(), name(rd), csr, rs1)
+                } else {
+                    write!(
+                        f,
+                        "{} {}, {:#x}, {}",
+                        op.mnemonic(),
+                        name(rd),
+                        csr,
+                        name(rs1)
+                    )
+                }
+            }
+            Self::Mret => f.write_str("mret"),
+            Self::Sret => f.write_str("sret"),
+            Self::Wfi => f.write_str("wfi"),
+            Self::SfenceVma { rs1, rs2 } => write!(f, "sfence.vma {}, {}", name(rs1), name(rs2)),
         }
-        print!("x{:2}:{:08x}   ", i, unsafe { (*frame).regs[i] });
     }
-    println!();
 }
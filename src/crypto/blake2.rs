@@ -0,0 +1,184 @@
+// BLAKE2b compression function F with a given number of rounds, the precompile 0x09 (EIP-152).
+// Input is the rounds as a big-endian u32, then the state h, the message block m and the
+// offset counter t as little-endian u64 words, and the final block flag f as a byte
+
+pub const INPUT_LEN: usize = 213;
+pub const OUTPUT_LEN: usize = 64;
+
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// Message word permutations, round `i` uses `SIGMA[i % 10]`
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+#[inline(always)]
+fn mix(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// Compresses the block `m` into the state `h`
+pub fn compress(rounds: u32, h: &mut [u64; 8], m: &[u64; 16], t: [u64; 2], last: bool) {
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= t[0];
+    v[13] ^= t[1];
+    if last {
+        v[14] = !v[14];
+    }
+
+    for round in 0..rounds as usize {
+        let s = &SIGMA[round % 10];
+        mix(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        mix(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        mix(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        mix(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        mix(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        mix(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        mix(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        mix(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+/// Rounds of the input, that its cost is proportional to
+#[must_use]
+pub fn rounds(input: &[u8; INPUT_LEN]) -> u32 {
+    u32::from_be_bytes([input[0], input[1], input[2], input[3]])
+}
+
+fn read_words<const N: usize>(bytes: &[u8]) -> [u64; N] {
+    let mut words = [0u64; N];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(8)) {
+        let mut le = [0u8; 8];
+        le.copy_from_slice(chunk);
+        *word = u64::from_le_bytes(le);
+    }
+
+    words
+}
+
+/// Precompile 0x09, the new state. `None` if the final block flag is not 0 or 1
+#[must_use]
+pub fn blake2f(input: &[u8; INPUT_LEN]) -> Option<[u8; OUTPUT_LEN]> {
+    let last = match input[INPUT_LEN - 1] {
+        0 => false,
+        1 => true,
+        _ => return None,
+    };
+    let mut h = read_words::<8>(&input[4..68]);
+    let m = read_words::<16>(&input[68..196]);
+    let t = read_words::<2>(&input[196..212]);
+    compress(rounds(input), &mut h, &m, t, last);
+
+    let mut output = [0u8; OUTPUT_LEN];
+    for (chunk, word) in output.chunks_exact_mut(8).zip(h) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex<const N: usize>(digits: &str) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).unwrap();
+        }
+
+        bytes
+    }
+
+    /// Input of the EIP-152 test vectors 3 to 8: the first and last block of BLAKE2b-512 of "abc"
+    fn input(rounds: u32, f: u8) -> [u8; INPUT_LEN] {
+        let mut input = [0u8; INPUT_LEN];
+        input[..4].copy_from_slice(&rounds.to_be_bytes());
+        input[4..68].copy_from_slice(&hex::<64>(
+            "48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+             d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b",
+        ));
+        input[68..71].copy_from_slice(b"abc");
+        input[196] = 3;
+        input[INPUT_LEN - 1] = f;
+
+        input
+    }
+
+    #[test]
+    fn eip152_vectors() {
+        assert_eq!(blake2f(&input(12, 2)), None);
+        assert_eq!(
+            blake2f(&input(0, 1)),
+            Some(hex(
+                "08c9bcf367e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+                 d282e6ad7f520e511f6c3e2b8c68059b9442be0454267ce079217e1319cde05b"
+            ))
+        );
+        assert_eq!(
+            blake2f(&input(12, 1)),
+            Some(hex(
+                "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+                 7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+            ))
+        );
+        assert_eq!(
+            blake2f(&input(12, 0)),
+            Some(hex(
+                "75ab69d3190a562c51aef8d88f1c2775876944407270c42c9844252c26d28752\
+                 98743e7f6d5ea2f2d3e8d226039cd31b4e426ac4f2d3d666a610c2116fde4735"
+            ))
+        );
+        assert_eq!(
+            blake2f(&input(1, 1)),
+            Some(hex(
+                "b63a380cb2897d521994a85234ee2c181b5f844d2c624c002677e9703449d2fb\
+                 a551b3a8333bcdf5f2f7e08993d53923de3d64fcc68c034e717b9293fed7a421"
+            ))
+        );
+    }
+
+    /// Vector 8 takes minutes, run it with `--ignored`
+    #[test]
+    #[ignore]
+    fn eip152_max_rounds() {
+        assert_eq!(
+            blake2f(&input(u32::MAX, 1)),
+            Some(hex(
+                "fc59093aafa9ab43daae0e914c57635c5402d8e3d2130eb9b3cc181de7f0ecf9\
+                 b22bf99a7815ce16419e200e01846e6b5df8cc7703041bbceb571de6631d2615"
+            ))
+        );
+    }
+}
@@ -0,0 +1,401 @@
+pub mod pairing;
+pub mod tower;
+
+use super::curve::{CurveParameters, Point};
+use crate::bigint::field::{Field, FieldParameters, Fp, Fp2};
+use crate::bigint::U256;
+
+// BN254 (alt_bn128) of the precompiles 0x06, 0x07 and 0x08 (EIP-196, EIP-197). G1 is the curve
+// `y^2 = x^3 + 3` over Fq, G2 is the subgroup of order r of its twist `y^2 = x^3 + 3 / xi`
+// over Fq2. Coordinates are 32-byte big-endian words, Fq2 ones have the imaginary part first,
+// and the identity is encoded as all zeroes. Inputs are of the exact length here, the callers
+// pad or truncate them
+
+pub const G1_ENCODED_LEN: usize = 64;
+pub const G2_ENCODED_LEN: usize = 128;
+pub const PAIR_ENCODED_LEN: usize = G1_ENCODED_LEN + G2_ENCODED_LEN;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BaseField;
+
+impl FieldParameters for BaseField {
+    const MODULUS: U256 = U256([
+        0xd87cfd47, 0x3c208c16, 0x6871ca8d, 0x97816a91, 0x8181585d, 0xb85045b6, 0xe131a029,
+        0x30644e72,
+    ]);
+}
+
+pub type Fq = Fp<BaseField>;
+pub type Fq2 = Fp2<BaseField>;
+
+/// Order of G1 and G2
+pub const ORDER: U256 = U256([
+    0xf0000001, 0x43e1f593, 0x79b97091, 0x2833e848, 0x8181585d, 0xb85045b6, 0xe131a029, 0x30644e72,
+]);
+
+/// `3 / xi`, the b of the twist
+const TWIST_B: [U256; 2] = [
+    U256([
+        0x24a138e5, 0x3267e6dc, 0x59dbefa3, 0xb5b4c5e5, 0x1be06ac3, 0x81be1899, 0xceb8aaae,
+        0x2b149d40,
+    ]),
+    U256([
+        0x85c315d2, 0xe4a2bd06, 0xe52d1852, 0xa74fa084, 0xeed8fdf4, 0xcd2cafad, 0x3af0fed4,
+        0x009713b0,
+    ]),
+];
+
+/// Constants are canonical, real part first
+fn fq2(value: &[U256; 2]) -> Fq2 {
+    Fq2::new(
+        Fq::from_u256(&value[0]).unwrap_or(Fq::ZERO),
+        Fq::from_u256(&value[1]).unwrap_or(Fq::ZERO),
+    )
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct G1Parameters;
+
+impl CurveParameters for G1Parameters {
+    type Field = Fq;
+
+    fn b() -> Fq {
+        Fq::from_u32(3)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct G2Parameters;
+
+impl CurveParameters for G2Parameters {
+    type Field = Fq2;
+
+    fn b() -> Fq2 {
+        fq2(&TWIST_B)
+    }
+}
+
+pub type G1 = Point<G1Parameters>;
+pub type G2 = Point<G2Parameters>;
+
+fn read_word(bytes: &[u8]) -> U256 {
+    let mut word = [0u8; 32];
+    word.copy_from_slice(&bytes[..32]);
+
+    U256::from_be_bytes(&word)
+}
+
+/// `None` if the coordinate is not below the modulus
+fn read_fq(bytes: &[u8]) -> Option<Fq> {
+    Fq::from_u256(&read_word(bytes))
+}
+
+/// `None` if the point is not on the curve. G1 is the whole curve, there is no subgroup check
+pub fn read_g1(bytes: &[u8; G1_ENCODED_LEN]) -> Option<G1> {
+    let x = read_fq(&bytes[..32])?;
+    let y = read_fq(&bytes[32..])?;
+    if x.is_zero() && y.is_zero() {
+        return Some(G1::IDENTITY);
+    }
+
+    G1::contains(&x, &y).then(|| G1::from_affine(x, y))
+}
+
+/// `None` if the point is not on the twist, or not of order r
+pub fn read_g2(bytes: &[u8; G2_ENCODED_LEN]) -> Option<G2> {
+    let x = Fq2::new(read_fq(&bytes[32..64])?, read_fq(&bytes[..32])?);
+    let y = Fq2::new(read_fq(&bytes[96..])?, read_fq(&bytes[64..96])?);
+    if x.is_zero() && y.is_zero() {
+        return Some(G2::IDENTITY);
+    }
+    if !G2::contains(&x, &y) {
+        return None;
+    }
+    let point = G2::from_affine(x, y);
+
+    point.mul(&ORDER).is_identity().then_some(point)
+}
+
+#[must_use]
+pub fn write_g1(point: G1) -> [u8; G1_ENCODED_LEN] {
+    let mut bytes = [0u8; G1_ENCODED_LEN];
+    if let Some((x, y)) = point.to_affine() {
+        bytes[..32].copy_from_slice(&x.to_u256().to_be_bytes());
+        bytes[32..].copy_from_slice(&y.to_u256().to_be_bytes());
+    }
+
+    bytes
+}
+
+/// Precompile 0x06, sum of two G1 points
+#[must_use]
+pub fn ecadd(input: &[u8; 2 * G1_ENCODED_LEN]) -> Option<[u8; G1_ENCODED_LEN]> {
+    let mut a = [0u8; G1_ENCODED_LEN];
+    let mut b = [0u8; G1_ENCODED_LEN];
+    a.copy_from_slice(&input[..G1_ENCODED_LEN]);
+    b.copy_from_slice(&input[G1_ENCODED_LEN..]);
+    let sum = read_g1(&a)?.add(&read_g1(&b)?);
+
+    Some(write_g1(sum))
+}
+
+/// Precompile 0x07, G1 point times a 256-bit scalar
+#[must_use]
+pub fn ecmul(input: &[u8; G1_ENCODED_LEN + 32]) -> Option<[u8; G1_ENCODED_LEN]> {
+    let mut point = [0u8; G1_ENCODED_LEN];
+    point.copy_from_slice(&input[..G1_ENCODED_LEN]);
+    let product = read_g1(&point)?.mul(&read_word(&input[G1_ENCODED_LEN..]));
+
+    Some(write_g1(product))
+}
+
+/// Precompile 0x08, if the product of the pairings of the (G1, G2) pairs is one. `None` if the
+/// input is not a whole number of pairs or any point is invalid. No pairs is the empty product
+#[must_use]
+pub fn ecpairing(input: &[u8]) -> Option<bool> {
+    if input.len() % PAIR_ENCODED_LEN != 0 {
+        return None;
+    }
+
+    let mut product = tower::Fq12::ONE;
+    for pair in input.chunks_exact(PAIR_ENCODED_LEN) {
+        let mut g1 = [0u8; G1_ENCODED_LEN];
+        let mut g2 = [0u8; G2_ENCODED_LEN];
+        g1.copy_from_slice(&pair[..G1_ENCODED_LEN]);
+        g2.copy_from_slice(&pair[G1_ENCODED_LEN..]);
+        let (g1, g2) = (read_g1(&g1)?, read_g2(&g2)?);
+
+        // pairings with the identity are one
+        if let (Some(p), Some(q)) = (g1.to_affine(), g2.to_affine()) {
+            product = product.mul(&pairing::miller_loop(&p, &q));
+        }
+    }
+
+    Some(pairing::final_exponentiation(&product) == tower::Fq12::ONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex<const N: usize>(digits: &str) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).unwrap();
+        }
+
+        bytes
+    }
+
+    /// Points and scalars back to back
+    fn join<const N: usize>(parts: &[&str]) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        let mut offset = 0;
+        for part in parts {
+            for i in 0..part.len() / 2 {
+                bytes[offset + i] = u8::from_str_radix(&part[2 * i..2 * i + 2], 16).unwrap();
+            }
+            offset += part.len() / 2;
+        }
+        assert_eq!(offset, N);
+
+        bytes
+    }
+
+    const G1_GENERATOR: &str = concat!(
+        "0000000000000000000000000000000000000000000000000000000000000001",
+        "0000000000000000000000000000000000000000000000000000000000000002",
+    );
+    /// The generator negated, y is `p - 2`
+    const G1_NEGATED: &str = concat!(
+        "0000000000000000000000000000000000000000000000000000000000000001",
+        "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45",
+    );
+    /// (1, 3) is not on the curve
+    const G1_INVALID: &str = concat!(
+        "0000000000000000000000000000000000000000000000000000000000000001",
+        "0000000000000000000000000000000000000000000000000000000000000003",
+    );
+    const G2_GENERATOR: &str = concat!(
+        "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+        "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+        "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+        "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+    );
+    /// (1, y) on the twist, but not of order r
+    const G2_OUTSIDE_SUBGROUP: &str = concat!(
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+        "0d1271953ed9ea0836846e70a1934187998c7f790cb4d7511b7f8da82de048a4",
+        "2869111d5381f072f8e2728fdb825a51aadd70e52c9830e9ab4b871c0531f1bb",
+    );
+
+    /// go-ethereum's bn256Add vectors (chfast1, chfast2, cdetrio13, cdetrio14)
+    #[test]
+    fn add_vectors() {
+        let vectors = [
+            (
+                concat!(
+                    "18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9",
+                    "063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266",
+                    "07c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed",
+                    "06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7",
+                ),
+                concat!(
+                    "2243525c5efd4b9c3d3c45ac0ca3fe4dd85e830a4ce6b65fa1eeaee202839703",
+                    "301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915",
+                ),
+            ),
+            (
+                concat!(
+                    "2243525c5efd4b9c3d3c45ac0ca3fe4dd85e830a4ce6b65fa1eeaee202839703",
+                    "301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915",
+                    "18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9",
+                    "063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266",
+                ),
+                concat!(
+                    "2bd3e6d0f3b142924f5ca7b49ce5b9d54c4703d7ae5648e61d02268b1a0a9fb7",
+                    "21611ce0a6af85915e2f1d70300909ce2e49dfad4a4619c8390cae66cefdb204",
+                ),
+            ),
+            (
+                concat!(
+                    "17c139df0efee0f766bc0204762b774362e4ded88953a39ce849a8a7fa163fa9",
+                    "01e0559bacb160664764a357af8a9fe70baa9258e0b959273ffc5718c6d4cc7c",
+                    "039730ea8dff1254c0fee9c0ea777d29a9c710b7e616683f194f18c43b43b869",
+                    "073a5ffcc6fc7a28c30723d6e58ce577356982d65b833a5a5c15bf9024b43d98",
+                ),
+                concat!(
+                    "15bf2bb17880144b5d1cd2b1f46eff9d617bffd1ca57c37fb5a49bd84e53cf66",
+                    "049c797f9ce0d17083deb32b5e36f2ea2a212ee036598dd7624c168993d1355f",
+                ),
+            ),
+            (
+                concat!(
+                    "17c139df0efee0f766bc0204762b774362e4ded88953a39ce849a8a7fa163fa9",
+                    "01e0559bacb160664764a357af8a9fe70baa9258e0b959273ffc5718c6d4cc7c",
+                    "17c139df0efee0f766bc0204762b774362e4ded88953a39ce849a8a7fa163fa9",
+                    "2e83f8d734803fc370eba25ed1f6b8768bd6d83887b87165fc2434fe11a830cb",
+                ),
+                concat!(
+                    "0000000000000000000000000000000000000000000000000000000000000000",
+                    "0000000000000000000000000000000000000000000000000000000000000000",
+                ),
+            ),
+        ];
+        for (input, output) in vectors {
+            assert_eq!(ecadd(&hex(input)), Some(hex(output)));
+        }
+    }
+
+    /// go-ethereum's bn256ScalarMul vectors (chfast1, chfast2, chfast3, cdetrio1)
+    #[test]
+    fn mul_vectors() {
+        let vectors = [
+            (
+                concat!(
+                    "2bd3e6d0f3b142924f5ca7b49ce5b9d54c4703d7ae5648e61d02268b1a0a9fb7",
+                    "21611ce0a6af85915e2f1d70300909ce2e49dfad4a4619c8390cae66cefdb204",
+                    "00000000000000000000000000000000000000000000000011138ce750fa15c2",
+                ),
+                concat!(
+                    "070a8d6a982153cae4be29d434e8faef8a47b274a053f5a4ee2a6c9c13c31e5c",
+                    "031b8ce914eba3a9ffb989f9cdd5b0f01943074bf4f0f315690ec3cec6981afc",
+                ),
+            ),
+            (
+                concat!(
+                    "070a8d6a982153cae4be29d434e8faef8a47b274a053f5a4ee2a6c9c13c31e5c",
+                    "031b8ce914eba3a9ffb989f9cdd5b0f01943074bf4f0f315690ec3cec6981afc",
+                    "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd46",
+                ),
+                concat!(
+                    "025a6f4181d2b4ea8b724290ffb40156eb0adb514c688556eb79cdea0752c2bb",
+                    "2eff3f31dea215f1eb86023a133a996eb6300b44da664d64251d05381bb8a02e",
+                ),
+            ),
+            (
+                concat!(
+                    "025a6f4181d2b4ea8b724290ffb40156eb0adb514c688556eb79cdea0752c2bb",
+                    "2eff3f31dea215f1eb86023a133a996eb6300b44da664d64251d05381bb8a02e",
+                    "183227397098d014dc2822db40c0ac2ecbc0b548b438e5469e10460b6c3e7ea3",
+                ),
+                concat!(
+                    "14789d0d4a730b354403b5fac948113739e276c23e0258d8596ee72f9cd9d323",
+                    "0af18a63153e0ec25ff9f2951dd3fa90ed0197bfef6e2a1a62b5095b9d2b4a27",
+                ),
+            ),
+            (
+                concat!(
+                    "1a87b0584ce92f4593d161480614f2989035225609f08058ccfa3d0f940febe3",
+                    "1a2f3c951f6dadcc7ee9007dff81504b0fcd6d7cf59996efdc33d92bf7f9f8f6",
+                    "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+                ),
+                concat!(
+                    "2cde5879ba6f13c0b5aa4ef627f159a3347df9722efce88a9afbb20b763b4c41",
+                    "1aa7e43076f6aee272755a7f9b84832e71559ba0d2e0b17d5f9f01755e5b0d11",
+                ),
+            ),
+        ];
+        for (input, output) in vectors {
+            assert_eq!(ecmul(&hex(input)), Some(hex(output)));
+        }
+    }
+
+    /// go-ethereum's bn256Pairing vectors (jeff1, empty_data, one_point, two_point_match_2)
+    #[test]
+    fn pairing_vectors() {
+        let jeff1: [u8; 2 * PAIR_ENCODED_LEN] = hex(concat!(
+            "1c76476f4def4bb94541d57ebba1193381ffa7aa76ada664dd31c16024c43f59",
+            "3034dd2920f673e204fee2811c678745fc819b55d3e9d294e45c9b03a76aef41",
+            "209dd15ebff5d46c4bd888e51a93cf99a7329636c63514396b4a452003a35bf7",
+            "04bf11ca01483bfa8b34b43561848d28905960114c8ac04049af4b6315a41678",
+            "2bb8324af6cfc93537a2ad1a445cfd0ca2a71acd7ac41fadbf933c2a51be344d",
+            "120a2a4cf30c1bf9845f20c6fe39e07ea2cce61f0c9bb048165fe5e4de877550",
+            "111e129f1cf1097710d41c4ac70fcdfa5ba2023c6ff1cbeac322de49d1b6df7c",
+            "2032c61a830e3c17286de9462bf242fca2883585b93870a73853face6a6bf411",
+            "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+            "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+            "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+            "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+        ));
+        assert_eq!(ecpairing(&jeff1), Some(true));
+        assert_eq!(ecpairing(&[]), Some(true));
+
+        let one_point: [u8; PAIR_ENCODED_LEN] = join(&[G1_GENERATOR, G2_GENERATOR]);
+        assert_eq!(ecpairing(&one_point), Some(false));
+
+        let two_point: [u8; 2 * PAIR_ENCODED_LEN] =
+            join(&[G1_GENERATOR, G2_GENERATOR, G1_NEGATED, G2_GENERATOR]);
+        assert_eq!(ecpairing(&two_point), Some(true));
+    }
+
+    #[test]
+    fn invalid_points() {
+        let add: [u8; 2 * G1_ENCODED_LEN] = join(&[G1_GENERATOR, G1_INVALID]);
+        assert_eq!(ecadd(&add), None);
+
+        // a coordinate of p is not reduced, even if it is y = 0 mod p
+        let unreduced = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47";
+        let add: [u8; 2 * G1_ENCODED_LEN] = join(&[G1_GENERATOR, unreduced, unreduced]);
+        assert_eq!(ecadd(&add), None);
+
+        let scalar = "0000000000000000000000000000000000000000000000000000000000000002";
+        let mul: [u8; G1_ENCODED_LEN + 32] = join(&[G1_INVALID, scalar]);
+        assert_eq!(ecmul(&mul), None);
+
+        let pair: [u8; PAIR_ENCODED_LEN] = join(&[G1_INVALID, G2_GENERATOR]);
+        assert_eq!(ecpairing(&pair), None);
+
+        // the generator with the last byte of y changed is off the twist
+        let mut off_twist: [u8; PAIR_ENCODED_LEN] = join(&[G1_GENERATOR, G2_GENERATOR]);
+        off_twist[PAIR_ENCODED_LEN - 1] ^= 1;
+        assert_eq!(ecpairing(&off_twist), None);
+
+        let pair: [u8; PAIR_ENCODED_LEN] = join(&[G1_GENERATOR, G2_OUTSIDE_SUBGROUP]);
+        assert_eq!(ecpairing(&pair), None);
+
+        let one_point: [u8; PAIR_ENCODED_LEN] = join(&[G1_GENERATOR, G2_GENERATOR]);
+        assert_eq!(ecpairing(&one_point[..PAIR_ENCODED_LEN - 1]), None);
+    }
+}
@@ -0,0 +1,167 @@
+use super::tower::{Fq12, Fq6};
+use super::{fq2, Fq, Fq2, G2Parameters};
+use crate::bigint::field::Field;
+use crate::bigint::U256;
+use crate::crypto::curve::CurveParameters;
+
+// Optimal ate pairing. The Miller loop keeps the multiple of Q in homogeneous projective
+// coordinates, and evaluates the lines at P as they come out of the doubling and addition steps
+// (formulas 3 and 4 of "The Realm of the Pairings", for a D-type twist). The hard part of
+// the final exponentiation is that of Fuentes-Castañeda, Knapp and Rodríguez-Henríquez, it
+// gives a fixed power of the reduced pairing that is coprime to r, which is as good to check
+// that the product is one
+
+/// `6x + 2`, the top bit is at 64
+const ATE_LOOP_COUNT: u128 = 29793968203157093288;
+const ATE_LOOP_COUNT_BITS: u32 = 65;
+/// Parameter of the curve
+const X: u64 = 4965661367192848881;
+
+/// `xi^((p - 1) / 3)` and `xi^((p - 1) / 2)`, that the Frobenius map on the twist multiplies
+/// x and y by
+const TWIST_FROBENIUS_X: [U256; 2] = [
+    U256([
+        0x176f553d, 0x99e39557, 0xc2c3330c, 0xb78cc310, 0xf559b143, 0x4c0bec3c, 0x4f7911f7,
+        0x2fb34798,
+    ]),
+    U256([
+        0x640fcba2, 0x1665d51c, 0x0b7c9dce, 0x32ae2a1d, 0xd75a0794, 0x4ba4cc8b, 0x61ebae20,
+        0x16c9e550,
+    ]),
+];
+const TWIST_FROBENIUS_Y: [U256; 2] = [
+    U256([
+        0x71a0135a, 0xdc540146, 0xa9c95998, 0xdbaae0ed, 0xb6e2f9b9, 0xdc5ec698, 0x489af5dc,
+        0x063cf305,
+    ]),
+    U256([
+        0x2623b0e3, 0x82d37f63, 0x8fa25bd2, 0x21807dc9, 0xec796f2b, 0x0704b5a7, 0xac41049a,
+        0x07c03cbc,
+    ]),
+];
+
+/// Coefficients of 1, w and v w of the line, before the evaluation at P
+type Line = (Fq2, Fq2, Fq2);
+
+struct G2Projective {
+    x: Fq2,
+    y: Fq2,
+    z: Fq2,
+}
+
+impl G2Projective {
+    fn double_step(&mut self, two_inverse: &Fq) -> Line {
+        let a = self.x.mul(&self.y).mul_by_fp(two_inverse);
+        let b = self.y.square();
+        let c = self.z.square();
+        let e = G2Parameters::b().mul(&c.double().add(&c));
+        let f = e.double().add(&e);
+        let g = b.add(&f).mul_by_fp(two_inverse);
+        let h = self.y.add(&self.z).square().sub(&b.add(&c));
+        let i = e.sub(&b);
+        let j = self.x.square();
+        let e_square = e.square();
+
+        self.x = a.mul(&b.sub(&f));
+        self.y = g.square().sub(&e_square.double().add(&e_square));
+        self.z = b.mul(&h);
+
+        (h.neg(), j.double().add(&j), i)
+    }
+
+    fn add_step(&mut self, q: &(Fq2, Fq2)) -> Line {
+        let (qx, qy) = q;
+        let theta = self.y.sub(&qy.mul(&self.z));
+        let lambda = self.x.sub(&qx.mul(&self.z));
+        let c = theta.square();
+        let d = lambda.square();
+        let e = lambda.mul(&d);
+        let f = self.z.mul(&c);
+        let g = self.x.mul(&d);
+        let h = e.add(&f).sub(&g.double());
+
+        self.x = lambda.mul(&h);
+        self.y = theta.mul(&g.sub(&h)).sub(&e.mul(&self.y));
+        self.z = self.z.mul(&e);
+
+        (lambda, theta.neg(), theta.mul(qx).sub(&lambda.mul(qy)))
+    }
+}
+
+/// `f` times the line evaluated at `p`
+fn evaluate(f: &Fq12, line: Line, p: &(Fq, Fq)) -> Fq12 {
+    let (c0, c1, c2) = line;
+    let line = Fq12::new(
+        Fq6::new(c0.mul_by_fp(&p.1), Fq2::ZERO, Fq2::ZERO),
+        Fq6::new(c1.mul_by_fp(&p.0), c2, Fq2::ZERO),
+    );
+
+    f.mul(&line)
+}
+
+/// Frobenius map on the twist, `(x^p * xi^((p - 1) / 3), y^p * xi^((p - 1) / 2))`
+fn twist_frobenius(q: &(Fq2, Fq2)) -> (Fq2, Fq2) {
+    (
+        q.0.conjugate().mul(&fq2(&TWIST_FROBENIUS_X)),
+        q.1.conjugate().mul(&fq2(&TWIST_FROBENIUS_Y)),
+    )
+}
+
+/// Miller loop of the affine points, neither of them is the identity
+#[must_use]
+pub fn miller_loop(p: &(Fq, Fq), q: &(Fq2, Fq2)) -> Fq12 {
+    let two_inverse = Fq::from_u32(2).inverse();
+    let mut t = G2Projective {
+        x: q.0,
+        y: q.1,
+        z: Fq2::ONE,
+    };
+    let mut f = Fq12::ONE;
+    for i in (0..ATE_LOOP_COUNT_BITS - 1).rev() {
+        f = f.square();
+        f = evaluate(&f, t.double_step(&two_inverse), p);
+        if (ATE_LOOP_COUNT >> i) & 1 != 0 {
+            f = evaluate(&f, t.add_step(q), p);
+        }
+    }
+
+    let q1 = twist_frobenius(q);
+    let (x, y) = twist_frobenius(&q1);
+    f = evaluate(&f, t.add_step(&q1), p);
+
+    evaluate(&f, t.add_step(&(x, y.neg())), p)
+}
+
+/// `f^-x`, `f` is in the cyclotomic subgroup
+fn exp_by_neg_x(f: &Fq12) -> Fq12 {
+    f.pow(X).conjugate()
+}
+
+#[must_use]
+pub fn final_exponentiation(f: &Fq12) -> Fq12 {
+    // easy part, f^((p^6 - 1) (p^2 + 1))
+    let r = f.conjugate().mul(&f.inverse());
+    let r = r.frobenius(2).mul(&r);
+
+    // hard part, a multiple of (p^4 - p^2 + 1) / r
+    let y0 = exp_by_neg_x(&r);
+    let y1 = y0.square();
+    let y2 = y1.square();
+    let y3 = y2.mul(&y1);
+    let y4 = exp_by_neg_x(&y3);
+    let y5 = y4.square();
+    let y6 = exp_by_neg_x(&y5);
+    let y3 = y3.conjugate();
+    let y6 = y6.conjugate();
+    let y7 = y6.mul(&y4);
+    let y8 = y7.mul(&y3);
+    let y9 = y8.mul(&y1);
+    let y10 = y8.mul(&y4);
+    let y11 = y10.mul(&r);
+    let y12 = y9.frobenius(1);
+    let y13 = y12.mul(&y11);
+    let y14 = y8.frobenius(2).mul(&y13);
+    let y15 = r.conjugate().mul(&y9).frobenius(3);
+
+    y15.mul(&y14)
+}
@@ -0,0 +1,313 @@
+use super::{fq2, Fq2};
+use crate::bigint::field::Field;
+use crate::bigint::U256;
+
+// Tower of the extensions that the pairing maps into: `Fq6 = Fq2[v] / (v^3 - xi)` with
+// `xi = 9 + u`, and `Fq12 = Fq6[w] / (w^2 - v)`. Frobenius maps use the precomputed powers of
+// xi, only for the first three powers of p, that the final exponentiation needs
+
+/// `xi^((p^i - 1) / 3)` for i = 1, 2, 3
+const FROBENIUS_FP6_C1: [[U256; 2]; 3] = [
+    [
+        U256([
+            0x176f553d, 0x99e39557, 0xc2c3330c, 0xb78cc310, 0xf559b143, 0x4c0bec3c, 0x4f7911f7,
+            0x2fb34798,
+        ]),
+        U256([
+            0x640fcba2, 0x1665d51c, 0x0b7c9dce, 0x32ae2a1d, 0xd75a0794, 0x4ba4cc8b, 0x61ebae20,
+            0x16c9e550,
+        ]),
+    ],
+    [
+        U256([
+            0x607cfd48, 0xe4bd44e5, 0xbb966e3d, 0xc28f069f, 0xe0acccb0, 0x5e6dd9e7, 0xe131a029,
+            0x30644e72,
+        ]),
+        U256([
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000,
+        ]),
+    ],
+    [
+        U256([
+            0x7bdcfb6d, 0x7b746ee8, 0x5d6942d3, 0x805ffd3d, 0x959f25ac, 0xbaff1c77, 0xb755ef0a,
+            0x0856e078,
+        ]),
+        U256([
+            0xaaa586de, 0x380cab2b, 0x98ff2631, 0x0fdf31bf, 0xec26094f, 0xa9f30e6d, 0xb3d1766f,
+            0x04f1de41,
+        ]),
+    ],
+];
+
+/// `xi^((2 p^i - 2) / 3)` for i = 1, 2, 3
+const FROBENIUS_FP6_C2: [[U256; 2]; 3] = [
+    [
+        U256([
+            0x921ea762, 0x848a1f55, 0xbe94ec72, 0xd33365f7, 0x5a181e84, 0x80f3c0b7, 0x64eea801,
+            0x05b54f5e,
+        ]),
+        U256([
+            0xcd2b8126, 0xc13b4711, 0x1bdec763, 0x3685d2ea, 0x3b0b1c92, 0x9f3a80b0, 0xe7fd8aee,
+            0x2c145edb,
+        ]),
+    ],
+    [
+        U256([
+            0x77fffffe, 0x57634731, 0xacdb5c4f, 0xd4f263f1, 0xa0d48bac, 0x59e26bce, 0x00000000,
+            0x00000000,
+        ]),
+        U256([
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000,
+        ]),
+    ],
+    [
+        U256([
+            0x3ccbf066, 0x0e1a92bc, 0x75b06bcb, 0xe6330945, 0xb5b2444e, 0x19bee0f7, 0x11c08dab,
+            0x0bc58c66,
+        ]),
+        U256([
+            0x730c239f, 0x5fe3ed9d, 0x737f96e5, 0xa44a9e08, 0x0cd21d04, 0xfeb0f6ef, 0xe1910a12,
+            0x23d5e999,
+        ]),
+    ],
+];
+
+/// `xi^((p^i - 1) / 6)` for i = 1, 2, 3
+const FROBENIUS_FP12_C1: [[U256; 2]; 3] = [
+    [
+        U256([
+            0xdcc9e470, 0xd60b35da, 0x292f2176, 0x5c521e08, 0x76e68b60, 0xe8b99fdd, 0x2865a7df,
+            0x1284b71c,
+        ]),
+        U256([
+            0x80f362ac, 0xca5cf05f, 0x8eeec7e5, 0x74799277, 0x12150b8e, 0xa6327cfe, 0xb4fae7e6,
+            0x246996f3,
+        ]),
+    ],
+    [
+        U256([
+            0x607cfd49, 0xe4bd44e5, 0xbb966e3d, 0xc28f069f, 0xe0acccb0, 0x5e6dd9e7, 0xe131a029,
+            0x30644e72,
+        ]),
+        U256([
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000,
+        ]),
+    ],
+    [
+        U256([
+            0x1ed4a67f, 0xe86f7d39, 0xbe55d24a, 0x894cb38d, 0xd0acaa90, 0xefe9608c, 0xcc82e4bb,
+            0x19dc81cf,
+        ]),
+        U256([
+            0xf4c0c101, 0x7694aa2b, 0x97d439ec, 0x7f03a5e3, 0x3576139d, 0x06cbeee3, 0x0be77d73,
+            0x00abf8b6,
+        ]),
+    ],
+];
+
+/// `self * xi`
+fn mul_by_nonresidue(value: &Fq2) -> Fq2 {
+    let nine = value.double().double().double().add(value);
+
+    Fq2::new(nine.c0.sub(&value.c1), nine.c1.add(&value.c0))
+}
+
+/// `self^(p^power)` of an element of Fq2
+fn frobenius_fq2(value: &Fq2, power: usize) -> Fq2 {
+    if power % 2 == 1 {
+        value.conjugate()
+    } else {
+        *value
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fq6 {
+    pub c0: Fq2,
+    pub c1: Fq2,
+    pub c2: Fq2,
+}
+
+impl Fq6 {
+    #[must_use]
+    pub const fn new(c0: Fq2, c1: Fq2, c2: Fq2) -> Self {
+        Self { c0, c1, c2 }
+    }
+
+    /// `self * v`
+    #[must_use]
+    pub fn mul_by_nonresidue(&self) -> Self {
+        Self::new(mul_by_nonresidue(&self.c2), self.c0, self.c1)
+    }
+
+    #[must_use]
+    pub fn mul_by_fq2(&self, other: &Fq2) -> Self {
+        Self::new(self.c0.mul(other), self.c1.mul(other), self.c2.mul(other))
+    }
+
+    /// `self^(p^power)`, `power` is 1, 2 or 3
+    #[must_use]
+    pub fn frobenius(&self, power: usize) -> Self {
+        Self::new(
+            frobenius_fq2(&self.c0, power),
+            frobenius_fq2(&self.c1, power).mul(&fq2(&FROBENIUS_FP6_C1[power - 1])),
+            frobenius_fq2(&self.c2, power).mul(&fq2(&FROBENIUS_FP6_C2[power - 1])),
+        )
+    }
+}
+
+impl Field for Fq6 {
+    const ZERO: Self = Self::new(Fq2::ZERO, Fq2::ZERO, Fq2::ZERO);
+    const ONE: Self = Self::new(Fq2::ONE, Fq2::ZERO, Fq2::ZERO);
+
+    fn is_zero(&self) -> bool {
+        self.c0.is_zero() && self.c1.is_zero() && self.c2.is_zero()
+    }
+
+    fn select(choice: bool, if_true: &Self, if_false: &Self) -> Self {
+        Self::new(
+            Fq2::select(choice, &if_true.c0, &if_false.c0),
+            Fq2::select(choice, &if_true.c1, &if_false.c1),
+            Fq2::select(choice, &if_true.c2, &if_false.c2),
+        )
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Self::new(
+            self.c0.add(&other.c0),
+            self.c1.add(&other.c1),
+            self.c2.add(&other.c2),
+        )
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        Self::new(
+            self.c0.sub(&other.c0),
+            self.c1.sub(&other.c1),
+            self.c2.sub(&other.c2),
+        )
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        let (a, b) = (self, other);
+        let c0 =
+            a.c0.mul(&b.c0)
+                .add(&mul_by_nonresidue(&a.c1.mul(&b.c2).add(&a.c2.mul(&b.c1))));
+        let c1 =
+            a.c0.mul(&b.c1)
+                .add(&a.c1.mul(&b.c0))
+                .add(&mul_by_nonresidue(&a.c2.mul(&b.c2)));
+        let c2 = a.c0.mul(&b.c2).add(&a.c1.mul(&b.c1)).add(&a.c2.mul(&b.c0));
+
+        Self::new(c0, c1, c2)
+    }
+
+    fn inverse(&self) -> Self {
+        let t0 = self
+            .c0
+            .square()
+            .sub(&mul_by_nonresidue(&self.c1.mul(&self.c2)));
+        let t1 = mul_by_nonresidue(&self.c2.square()).sub(&self.c0.mul(&self.c1));
+        let t2 = self.c1.square().sub(&self.c0.mul(&self.c2));
+        let norm = self
+            .c0
+            .mul(&t0)
+            .add(&mul_by_nonresidue(&self.c2.mul(&t1).add(&self.c1.mul(&t2))));
+
+        Self::new(t0, t1, t2).mul_by_fq2(&norm.inverse())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fq12 {
+    pub c0: Fq6,
+    pub c1: Fq6,
+}
+
+impl Fq12 {
+    #[must_use]
+    pub const fn new(c0: Fq6, c1: Fq6) -> Self {
+        Self { c0, c1 }
+    }
+
+    /// `self^(p^6)`, the inverse for the elements of the cyclotomic subgroup
+    #[must_use]
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.c0, self.c1.neg())
+    }
+
+    /// `self^(p^power)`, `power` is 1, 2 or 3
+    #[must_use]
+    pub fn frobenius(&self, power: usize) -> Self {
+        Self::new(
+            self.c0.frobenius(power),
+            self.c1
+                .frobenius(power)
+                .mul_by_fq2(&fq2(&FROBENIUS_FP12_C1[power - 1])),
+        )
+    }
+
+    /// `self^exponent`, the exponent is public
+    #[must_use]
+    pub fn pow(&self, exponent: u64) -> Self {
+        let mut result = Self::ONE;
+        for i in (0..u64::BITS - exponent.leading_zeros()).rev() {
+            result = result.square();
+            if exponent & (1 << i) != 0 {
+                result = result.mul(self);
+            }
+        }
+
+        result
+    }
+}
+
+impl Field for Fq12 {
+    const ZERO: Self = Self::new(Fq6::ZERO, Fq6::ZERO);
+    const ONE: Self = Self::new(Fq6::ONE, Fq6::ZERO);
+
+    fn is_zero(&self) -> bool {
+        self.c0.is_zero() && self.c1.is_zero()
+    }
+
+    fn select(choice: bool, if_true: &Self, if_false: &Self) -> Self {
+        Self::new(
+            Fq6::select(choice, &if_true.c0, &if_false.c0),
+            Fq6::select(choice, &if_true.c1, &if_false.c1),
+        )
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Self::new(self.c0.add(&other.c0), self.c1.add(&other.c1))
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        Self::new(self.c0.sub(&other.c0), self.c1.sub(&other.c1))
+    }
+
+    /// Karatsuba over Fq6
+    fn mul(&self, other: &Self) -> Self {
+        let v0 = self.c0.mul(&other.c0);
+        let v1 = self.c1.mul(&other.c1);
+        let c1 = self
+            .c0
+            .add(&self.c1)
+            .mul(&other.c0.add(&other.c1))
+            .sub(&v0.add(&v1));
+
+        Self::new(v0.add(&v1.mul_by_nonresidue()), c1)
+    }
+
+    fn inverse(&self) -> Self {
+        let norm = self
+            .c0
+            .square()
+            .sub(&self.c1.square().mul_by_nonresidue())
+            .inverse();
+
+        Self::new(self.c0.mul(&norm), self.c1.mul(&norm).neg())
+    }
+}
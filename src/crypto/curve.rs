@@ -0,0 +1,158 @@
+use crate::bigint::field::Field;
+use crate::bigint::U256;
+
+// Short Weierstrass curves `y^2 = x^3 + b`, the a = 0 ones of secp256k1 and BN254. Points are
+// in projective coordinates with the complete formulas of Renes, Costello and Batina
+// (algorithms 7 and 9), so that the identity and doubling need no branches, and scalar
+// multiplications go through all the 256 bits
+
+pub trait CurveParameters: Copy + core::fmt::Debug + 'static {
+    type Field: Field;
+
+    fn b() -> Self::Field;
+}
+
+/// `(X : Y : Z)` is `(X / Z, Y / Z)`, the identity is `(0 : 1 : 0)`
+#[derive(Clone, Copy, Debug)]
+pub struct Point<C: CurveParameters> {
+    pub x: C::Field,
+    pub y: C::Field,
+    pub z: C::Field,
+}
+
+impl<C: CurveParameters> Point<C> {
+    pub const IDENTITY: Self = Self {
+        x: C::Field::ZERO,
+        y: C::Field::ONE,
+        z: C::Field::ZERO,
+    };
+
+    #[must_use]
+    pub fn from_affine(x: C::Field, y: C::Field) -> Self {
+        Self {
+            x,
+            y,
+            z: C::Field::ONE,
+        }
+    }
+
+    /// If `(x, y)` is on the curve
+    #[must_use]
+    pub fn contains(x: &C::Field, y: &C::Field) -> bool {
+        y.square() == x.square().mul(x).add(&C::b())
+    }
+
+    #[must_use]
+    pub fn is_identity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    /// `None` for the identity
+    #[must_use]
+    pub fn to_affine(self) -> Option<(C::Field, C::Field)> {
+        if self.is_identity() {
+            return None;
+        }
+        let z_inverse = self.z.inverse();
+
+        Some((self.x.mul(&z_inverse), self.y.mul(&z_inverse)))
+    }
+
+    #[must_use]
+    pub fn select(choice: bool, if_true: &Self, if_false: &Self) -> Self {
+        Self {
+            x: C::Field::select(choice, &if_true.x, &if_false.x),
+            y: C::Field::select(choice, &if_true.y, &if_false.y),
+            z: C::Field::select(choice, &if_true.z, &if_false.z),
+        }
+    }
+
+    #[must_use]
+    pub fn neg(&self) -> Self {
+        Self {
+            x: self.x,
+            y: self.y.neg(),
+            z: self.z,
+        }
+    }
+
+    #[must_use]
+    pub fn add(&self, other: &Self) -> Self {
+        let b3 = C::b().double().add(&C::b());
+        let t0 = self.x.mul(&other.x);
+        let t1 = self.y.mul(&other.y);
+        let t2 = self.z.mul(&other.z);
+        let t3 = self.x.add(&self.y).mul(&other.x.add(&other.y));
+        let t3 = t3.sub(&t0.add(&t1));
+        let t4 = self.y.add(&self.z).mul(&other.y.add(&other.z));
+        let t4 = t4.sub(&t1.add(&t2));
+        let y3 = self.x.add(&self.z).mul(&other.x.add(&other.z));
+        let y3 = y3.sub(&t0.add(&t2));
+        let t0 = t0.double().add(&t0);
+        let t2 = b3.mul(&t2);
+        let z3 = t1.add(&t2);
+        let t1 = t1.sub(&t2);
+        let y3 = b3.mul(&y3);
+        let x3 = t3.mul(&t1).sub(&t4.mul(&y3));
+        let y3 = t1.mul(&z3).add(&y3.mul(&t0));
+        let z3 = z3.mul(&t4).add(&t0.mul(&t3));
+
+        Self {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    #[must_use]
+    pub fn double(&self) -> Self {
+        let b3 = C::b().double().add(&C::b());
+        let t0 = self.y.square();
+        let z3 = t0.double().double().double();
+        let t1 = self.y.mul(&self.z);
+        let t2 = b3.mul(&self.z.square());
+        let x3 = t2.mul(&z3);
+        let y3 = t0.add(&t2);
+        let z3 = t1.mul(&z3);
+        let t2 = t2.double().add(&t2);
+        let t0 = t0.sub(&t2);
+        let y3 = x3.add(&t0.mul(&y3));
+        let x3 = t0.mul(&self.x.mul(&self.y)).double();
+
+        Self {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    #[must_use]
+    pub fn mul(&self, scalar: &U256) -> Self {
+        let mut result = Self::IDENTITY;
+        for i in (0..256).rev() {
+            result = result.double();
+            let sum = result.add(self);
+            result = Self::select(scalar.bit(i), &sum, &result);
+        }
+
+        result
+    }
+
+    /// `a * self + b * other`, both multiplications at once (Shamir's trick)
+    #[must_use]
+    pub fn double_mul(&self, a: &U256, other: &Self, b: &U256) -> Self {
+        let both = self.add(other);
+        let mut result = Self::IDENTITY;
+        for i in (0..256).rev() {
+            result = result.double();
+            let addend = Self::select(
+                a.bit(i),
+                &Self::select(b.bit(i), &both, self),
+                &Self::select(b.bit(i), other, &Self::IDENTITY),
+            );
+            result = result.add(&addend);
+        }
+
+        result
+    }
+}
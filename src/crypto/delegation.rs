@@ -0,0 +1,33 @@
+// Delegation of primitives to circuits of the simulator, through a reserved MMIO window at the
+// start of IORAM. The kernel writes the input words into the window and the id of the primitive
+// into the control word right after them. The simulator performs the primitive as part of that
+// store, and leaves the output in place of the input. The simulator has no such circuits yet,
+// so nothing routes here unless the kernel is built with the feature of the primitive
+
+/// Base of the window
+pub const DELEGATION_ADDRESS: usize = 0x0080_0000;
+/// Input and output words, enough for the Keccak state
+pub const DELEGATION_WORDS: usize = 50;
+
+/// Control word value that runs Keccak-f[1600] on the 25 lanes, low words first
+pub const DELEGATION_KECCAK_F1600: u32 = 1;
+
+#[inline(always)]
+fn word(index: usize) -> *mut u32 {
+    core::ptr::from_exposed_addr_mut::<u32>(DELEGATION_ADDRESS + index * 4)
+}
+
+pub fn keccak_f1600(state: &mut [u64; 25]) {
+    unsafe {
+        for (index, lane) in state.iter().enumerate() {
+            word(2 * index).write_volatile(*lane as u32);
+            word(2 * index + 1).write_volatile((*lane >> 32) as u32);
+        }
+        word(DELEGATION_WORDS).write_volatile(DELEGATION_KECCAK_F1600);
+        for (index, lane) in state.iter_mut().enumerate() {
+            let low = word(2 * index).read_volatile() as u64;
+            let high = word(2 * index + 1).read_volatile() as u64;
+            *lane = low | high << 32;
+        }
+    }
+}
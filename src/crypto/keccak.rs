@@ -0,0 +1,158 @@
+use super::delegation;
+
+// Plain Keccak-256 (original padding, as used by Ethereum, not SHA3-256)
+
+const RATE: usize = 136;
+const ROUNDS: usize = 24;
+
+const ROUND_CONSTANTS: [u64; ROUNDS] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// Rotation of every lane in rho, indexed by lane
+const RHO: [u32; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+/// Where pi moves every lane
+const PI: [usize; 25] = [
+    0, 10, 20, 5, 15, 16, 1, 11, 21, 6, 7, 17, 2, 12, 22, 23, 8, 18, 3, 13, 14, 24, 9, 19, 4,
+];
+
+/// Keccak-f[1600], delegated to the circuit of the simulator when the kernel is built with
+/// the `keccak_delegation` feature
+#[inline(always)]
+pub fn keccak_f1600(state: &mut [u64; 25]) {
+    if cfg!(feature = "keccak_delegation") {
+        delegation::keccak_f1600(state);
+    } else {
+        permute(state);
+    }
+}
+
+/// Software permutation. All the loops have constant bounds and indices, so they unroll into
+/// straight-line code, and every lane stays in registers as a pair of words on RV32. Theta is
+/// applied on the way into rho and pi, that write into a copy, so no lane is read twice
+#[inline(never)]
+pub fn permute(state: &mut [u64; 25]) {
+    for round_constant in ROUND_CONSTANTS {
+        // theta
+        let mut c = [0u64; 5];
+        for x in 0..5 {
+            c[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        let d = [
+            c[4] ^ c[1].rotate_left(1),
+            c[0] ^ c[2].rotate_left(1),
+            c[1] ^ c[3].rotate_left(1),
+            c[2] ^ c[4].rotate_left(1),
+            c[3] ^ c[0].rotate_left(1),
+        ];
+
+        // rho and pi
+        let mut lanes = [0u64; 25];
+        for lane in 0..25 {
+            lanes[PI[lane]] = (state[lane] ^ d[lane % 5]).rotate_left(RHO[lane]);
+        }
+
+        // chi
+        for y in (0..25).step_by(5) {
+            state[y] = lanes[y] ^ (!lanes[y + 1] & lanes[y + 2]);
+            state[y + 1] = lanes[y + 1] ^ (!lanes[y + 2] & lanes[y + 3]);
+            state[y + 2] = lanes[y + 2] ^ (!lanes[y + 3] & lanes[y + 4]);
+            state[y + 3] = lanes[y + 3] ^ (!lanes[y + 4] & lanes[y]);
+            state[y + 4] = lanes[y + 4] ^ (!lanes[y] & lanes[y + 1]);
+        }
+
+        // iota
+        state[0] ^= round_constant;
+    }
+}
+
+#[derive(Clone)]
+pub struct Keccak256 {
+    state: [u64; 25],
+    buffer: [u8; RATE],
+    buffer_len: usize,
+}
+
+impl Keccak256 {
+    pub const fn new() -> Self {
+        Self {
+            state: [0u64; 25],
+            buffer: [0u8; RATE],
+            buffer_len: 0,
+        }
+    }
+
+    fn absorb_buffer(&mut self) {
+        for (lane, chunk) in self.state.iter_mut().zip(self.buffer.chunks_exact(8)) {
+            let mut le_bytes = [0u8; 8];
+            le_bytes.copy_from_slice(chunk);
+            *lane ^= u64::from_le_bytes(le_bytes);
+        }
+        keccak_f1600(&mut self.state);
+        self.buffer_len = 0;
+    }
+
+    pub fn update(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            let to_take = core::cmp::min(RATE - self.buffer_len, input.len());
+            self.buffer[self.buffer_len..self.buffer_len + to_take]
+                .copy_from_slice(&input[..to_take]);
+            self.buffer_len += to_take;
+            input = &input[to_take..];
+            if self.buffer_len == RATE {
+                self.absorb_buffer();
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn finalize(mut self) -> [u8; 32] {
+        for dst in self.buffer[self.buffer_len..].iter_mut() {
+            *dst = 0;
+        }
+        self.buffer[self.buffer_len] ^= 0x01;
+        self.buffer[RATE - 1] ^= 0x80;
+        self.absorb_buffer();
+
+        let mut result = [0u8; 32];
+        for (dst, lane) in result.chunks_exact_mut(8).zip(self.state.iter()) {
+            dst.copy_from_slice(&lane.to_le_bytes());
+        }
+
+        result
+    }
+}
+
+#[must_use]
+pub fn keccak256(input: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(input);
+
+    hasher.finalize()
+}
@@ -0,0 +1,14 @@
+pub mod blake2;
+pub mod bn254;
+pub mod curve;
+pub mod delegation;
+pub mod keccak;
+pub mod ripemd160;
+pub mod secp256k1;
+pub mod sha256;
+
+pub use self::blake2::blake2f;
+pub use self::keccak::{keccak256, Keccak256};
+pub use self::ripemd160::{ripemd160, Ripemd160};
+pub use self::secp256k1::ecrecover;
+pub use self::sha256::{sha256, Sha256};
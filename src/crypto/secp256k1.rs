@@ -0,0 +1,198 @@
+use super::curve::{self, CurveParameters};
+use super::keccak256;
+use crate::bigint::field::{Field, FieldParameters, Fp};
+use crate::bigint::U256;
+
+// secp256k1 public key recovery, for the precompile 0x01 and the signatures of transactions
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BaseField;
+
+impl FieldParameters for BaseField {
+    const MODULUS: U256 = U256([
+        0xfffffc2f, 0xfffffffe, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff,
+        0xffffffff,
+    ]);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScalarField;
+
+impl FieldParameters for ScalarField {
+    const MODULUS: U256 = U256([
+        0xd0364141, 0xbfd25e8c, 0xaf48a03b, 0xbaaedce6, 0xfffffffe, 0xffffffff, 0xffffffff,
+        0xffffffff,
+    ]);
+}
+
+pub type Fq = Fp<BaseField>;
+pub type Fr = Fp<ScalarField>;
+
+/// Half of the order of the group, signatures with `s` above it are malleable (EIP-2)
+pub const HALF_ORDER: U256 = U256([
+    0x681b20a0, 0xdfe92f46, 0x57a4501d, 0x5d576e73, 0xffffffff, 0xffffffff, 0xffffffff, 0x7fffffff,
+]);
+
+const GENERATOR_X: U256 = U256([
+    0x16f81798, 0x59f2815b, 0x2dce28d9, 0x029bfcdb, 0xce870b07, 0x55a06295, 0xf9dcbbac, 0x79be667e,
+]);
+const GENERATOR_Y: U256 = U256([
+    0xfb10d4b8, 0x9c47d08f, 0xa6855419, 0xfd17b448, 0x0e1108a8, 0x5da4fbfc, 0x26a3c465, 0x483ada77,
+]);
+
+#[derive(Clone, Copy, Debug)]
+pub struct Secp256k1;
+
+impl CurveParameters for Secp256k1 {
+    type Field = Fq;
+
+    fn b() -> Fq {
+        Fq::from_u32(7)
+    }
+}
+
+type Point = curve::Point<Secp256k1>;
+
+fn generator() -> Point {
+    Point::from_affine(
+        Fq::from_u256(&GENERATOR_X).unwrap_or(Fq::ZERO),
+        Fq::from_u256(&GENERATOR_Y).unwrap_or(Fq::ZERO),
+    )
+}
+
+/// Address of the key that signed `hash`, `None` if the signature is invalid. `r` is the x of
+/// the point that the signer used, and `y_parity` is the parity of its y
+#[must_use]
+pub fn recover(hash: &[u8; 32], y_parity: bool, r: &U256, s: &U256) -> Option<[u8; 20]> {
+    let r_scalar = Fr::from_u256(r).filter(|r| !r.is_zero())?;
+    let s_scalar = Fr::from_u256(s).filter(|s| !s.is_zero())?;
+
+    // r < n < p, there is no need to try r + n
+    let x = Fq::from_u256(r)?;
+    let y = x.square().mul(&x).add(&Secp256k1::b()).sqrt()?;
+    let y = Fq::select(y.is_odd() != y_parity, &y.neg(), &y);
+
+    // signer key is r^-1 (s R - z G)
+    let z = Fr::from_u256_reduced(&U256::from_be_bytes(hash));
+    let r_inverse = r_scalar.inverse();
+    let u1 = z.neg().mul(&r_inverse).to_u256();
+    let u2 = s_scalar.mul(&r_inverse).to_u256();
+    let key = generator().double_mul(&u1, &Point::from_affine(x, y), &u2);
+    let (key_x, key_y) = key.to_affine()?;
+
+    let mut encoding = [0u8; 64];
+    encoding[..32].copy_from_slice(&key_x.to_u256().to_be_bytes());
+    encoding[32..].copy_from_slice(&key_y.to_u256().to_be_bytes());
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&keccak256(&encoding)[12..]);
+
+    Some(address)
+}
+
+/// Input of the precompile: hash, v, r and s, 32 bytes each. `v` is 27 or 28, as in the
+/// signatures of the legacy transactions
+#[must_use]
+pub fn ecrecover(input: &[u8; 128]) -> Option<[u8; 20]> {
+    let word = |index: usize| {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&input[32 * index..32 * (index + 1)]);
+        bytes
+    };
+    let v = U256::from_be_bytes(&word(1));
+    let y_parity = match v.as_u64() {
+        Some(27) => false,
+        Some(28) => true,
+        _ => return None,
+    };
+
+    recover(
+        &word(0),
+        y_parity,
+        &U256::from_be_bytes(&word(2)),
+        &U256::from_be_bytes(&word(3)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex<const N: usize>(digits: &str) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).unwrap();
+        }
+
+        bytes
+    }
+
+    /// Precompile input from the hash, v, r and s
+    fn input(hash: &str, v: u8, r: &str, s: &str) -> [u8; 128] {
+        let mut input = [0u8; 128];
+        input[..32].copy_from_slice(&hex::<32>(hash));
+        input[63] = v;
+        input[64..96].copy_from_slice(&hex::<32>(r));
+        input[96..].copy_from_slice(&hex::<32>(s));
+
+        input
+    }
+
+    /// ethereum/tests, "ValidKey" of the ecrecover precompile
+    #[test]
+    fn valid_key() {
+        let input = input(
+            "18c547e4f7b0f325ad1e56f57e26c745b09a3e503d86e00e5255ff7f715d3d1c",
+            28,
+            "73b1693892219d736caba55bdb67216e485557ea6b6af75f37096c9aa6a5a75f",
+            "eeb940b1d03b21e36b0e47e79769f095fe2ab855bd91e3a38756b7d75a9c4549",
+        );
+        assert_eq!(
+            ecrecover(&input),
+            Some(hex("a94f5374fce5edbc8e2a8697c15331677e6ebf0b"))
+        );
+
+        let mut wrong_v = input;
+        wrong_v[63] = 29;
+        assert_eq!(ecrecover(&wrong_v), None);
+        let mut long_v = input;
+        long_v[62] = 1;
+        assert_eq!(ecrecover(&long_v), None);
+    }
+
+    /// ethereum/tests, "CallEcrecover0"
+    #[test]
+    fn call_ecrecover() {
+        let input = input(
+            "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e",
+            27,
+            "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e",
+            "789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02",
+        );
+        assert_eq!(
+            ecrecover(&input),
+            Some(hex("ceaccac640adf55b2028469bd36ba501f28b699d"))
+        );
+    }
+
+    /// go-ethereum's crypto/signature_test.go
+    #[test]
+    fn geth_signature() {
+        let hash = hex("ce0677bb30baa8cf067c88db9811f4333d131bf8bcf12fe7065d211dce971008");
+        let r = U256::from_be_bytes(&hex(
+            "90f27b8b488db00b00606796d2987f6a5f59ae62ea05effe84fef5b8b0e54998",
+        ));
+        let s = U256::from_be_bytes(&hex(
+            "4a691139ad57a3f0b906637673aa2f63d1f55cb1a69199d4009eea23ceaddc93",
+        ));
+        assert_eq!(
+            recover(&hash, true, &r, &s),
+            Some(hex("a19d069d48d2e9392ec2bb41ecab0a72119d633b"))
+        );
+        assert_ne!(
+            recover(&hash, false, &r, &s),
+            Some(hex("a19d069d48d2e9392ec2bb41ecab0a72119d633b"))
+        );
+        assert_eq!(recover(&hash, true, &U256::ZERO, &s), None);
+        assert_eq!(recover(&hash, true, &r, &U256::ZERO), None);
+    }
+}
@@ -0,0 +1,16 @@
+use super::opcodes::{JUMPDEST, PUSH1, PUSH32};
+
+/// Marks every `JUMPDEST` (one bit per byte of code) that is an instruction, and not a part of `PUSH` immediate
+pub fn fill_jumpdest_bitmap(code: &[u8], bitmap: &mut [u8]) {
+    bitmap.fill(0);
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        if opcode == JUMPDEST {
+            bitmap[pc / 8] |= 1 << (pc % 8);
+        } else if (PUSH1..=PUSH32).contains(&opcode) {
+            pc += (opcode - PUSH1 + 1) as usize;
+        }
+        pc += 1;
+    }
+}
@@ -0,0 +1,57 @@
+// Gas schedule as of Cancun
+
+pub const ZERO: u64 = 0;
+pub const BASE: u64 = 2;
+pub const VERY_LOW: u64 = 3;
+pub const LOW: u64 = 5;
+pub const MID: u64 = 8;
+pub const HIGH: u64 = 10;
+pub const JUMPDEST: u64 = 1;
+
+pub const EXP: u64 = 10;
+pub const EXP_BYTE: u64 = 50;
+pub const KECCAK256: u64 = 30;
+pub const KECCAK256_WORD: u64 = 6;
+pub const COPY_WORD: u64 = 3;
+pub const BLOCKHASH: u64 = 20;
+
+pub const WARM_ACCESS: u64 = 100;
+pub const COLD_ACCOUNT_ACCESS: u64 = 2600;
+pub const COLD_SLOAD: u64 = 2100;
+pub const SSTORE_STIPEND: u64 = 2300;
+
+pub const LOG: u64 = 375;
+pub const LOG_TOPIC: u64 = 375;
+pub const LOG_DATA_BYTE: u64 = 8;
+
+pub const CREATE: u64 = 32000;
+pub const INIT_CODE_WORD: u64 = 2;
+pub const CODE_DEPOSIT_BYTE: u64 = 200;
+
+pub const CALL_VALUE: u64 = 9000;
+pub const CALL_STIPEND: u64 = 2300;
+pub const NEW_ACCOUNT: u64 = 25000;
+
+pub const SELFDESTRUCT: u64 = 5000;
+
+pub const MEMORY_WORD: u64 = 3;
+pub const MEMORY_QUADRATIC_DENOMINATOR: u64 = 512;
+
+#[must_use]
+#[inline(always)]
+pub const fn words(bytes: u64) -> u64 {
+    bytes.div_ceil(32)
+}
+
+#[must_use]
+#[inline(always)]
+pub const fn memory_cost(words: u64) -> u64 {
+    MEMORY_WORD * words + words * words / MEMORY_QUADRATIC_DENOMINATOR
+}
+
+/// EIP-150: callee can get at most all but one 64th of the remaining gas
+#[must_use]
+#[inline(always)]
+pub const fn all_but_one_64th(gas: u64) -> u64 {
+    gas - gas / 64
+}
@@ -0,0 +1,727 @@
+use super::ir::{self, Program, BEGIN_BLOCK, LARGE_PUSH_WORDS, MAX_INLINE_PUSH_LEN};
+use super::memory::{memory_range, Memory};
+use super::opcodes::*;
+use super::stack::Stack;
+use super::{address_from_word, gas, word_from_address, EvmError, MAX_INIT_CODE_SIZE};
+use crate::bigint::U256;
+use crate::crypto::keccak256;
+use crate::system::account::CodeType;
+use crate::system::call::CallRequest;
+use crate::system::costs;
+use crate::system::deployment::{DeploymentRequest, DeploymentScheme};
+use crate::system::events::MAX_TOPICS;
+use crate::system::interpreter::{ExecutionFrame, ExecutionStatus};
+use crate::system::resources::Resources;
+use crate::system::types::{Address, Bytes32};
+use crate::system::{System, SystemError};
+
+pub enum ExitReason {
+    Stop,
+    Return(Option<(usize, usize)>),
+    Revert(Option<(usize, usize)>),
+}
+
+pub struct Interpreter<'a, 'b> {
+    pub frame: &'a ExecutionFrame<'b>,
+    pub resources: &'a mut Resources,
+    pub stack: Stack,
+    pub memory: Memory,
+    pub program: Program,
+    /// Position of the next instruction in the stream of the program
+    pub position: usize,
+}
+
+impl<'a, 'b> Interpreter<'a, 'b> {
+    #[inline(always)]
+    fn charge(&mut self, ticks: u64) -> Result<(), EvmError> {
+        self.resources.charge(ticks)?;
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn push_bool(&mut self, value: bool) {
+        self.stack.push(if value { U256::ONE } else { U256::ZERO })
+    }
+
+    #[inline(always)]
+    fn expand_memory(
+        &mut self,
+        system: &mut System,
+        range: Option<(usize, usize)>,
+    ) -> Result<(), EvmError> {
+        self.memory
+            .expand(&mut system.memory, self.resources, range)
+    }
+
+    #[inline(always)]
+    fn check_not_static(&self) -> Result<(), EvmError> {
+        if self.frame.is_static {
+            return Err(EvmError::StaticStateChange);
+        }
+
+        Ok(())
+    }
+
+    fn jump(&mut self, destination: &U256) -> Result<(), EvmError> {
+        self.position = self
+            .program
+            .jump_target(destination.as_usize_saturated())
+            .ok_or(EvmError::InvalidJump)?;
+
+        Ok(())
+    }
+
+    /// Charges for the account access (EIP-2929) and marks it warm
+    fn access_account(&mut self, system: &mut System, address: &Address) -> Result<(), EvmError> {
+        let was_warm = system.accounts.touch(address)?;
+        self.charge(if was_warm {
+            gas::WARM_ACCESS
+        } else {
+            gas::COLD_ACCOUNT_ACCESS
+        })
+    }
+
+    fn copy_to_memory(
+        &mut self,
+        system: &mut System,
+        source: &[u8],
+        memory_offset: &U256,
+        source_offset: &U256,
+        len: &U256,
+    ) -> Result<(), EvmError> {
+        let range = memory_range(memory_offset, len)?;
+        if let Some((_, len)) = range {
+            self.charge(gas::COPY_WORD * gas::words(len as u64))?;
+        }
+        self.expand_memory(system, range)?;
+        if let Some((offset, len)) = range {
+            let source_offset = core::cmp::min(source_offset.as_usize_saturated(), source.len());
+            self.memory
+                .copy_padded(offset, len, &source[source_offset..]);
+        }
+
+        Ok(())
+    }
+
+    pub fn run(&mut self, system: &mut System) -> Result<ExitReason, EvmError> {
+        let code = self.frame.code;
+        loop {
+            let word = self.program.word(self.position);
+            self.position += 1;
+            let opcode = ir::opcode(word);
+            match opcode {
+                BEGIN_BLOCK => {
+                    let static_gas = self.program.word(self.position);
+                    let (required, growth) =
+                        ir::unpack_stack_bounds(self.program.word(self.position + 1));
+                    self.position += ir::BLOCK_IMMEDIATE_WORDS;
+                    self.charge(static_gas as u64)?;
+                    self.stack.check(required, growth)?;
+                }
+                STOP => return Ok(ExitReason::Stop),
+                ADD => {
+                    let [a, b] = self.stack.pop_n();
+                    self.stack.push(a.wrapping_add(&b));
+                }
+                MUL => {
+                    let [a, b] = self.stack.pop_n();
+                    self.stack.push(a.wrapping_mul(&b));
+                }
+                SUB => {
+                    let [a, b] = self.stack.pop_n();
+                    self.stack.push(a.wrapping_sub(&b));
+                }
+                DIV => {
+                    let [a, b] = self.stack.pop_n();
+                    self.stack.push(a.div_rem(&b).0);
+                }
+                SDIV => {
+                    let [a, b] = self.stack.pop_n();
+                    self.stack.push(a.signed_div(&b));
+                }
+                MOD => {
+                    let [a, b] = self.stack.pop_n();
+                    self.stack.push(a.div_rem(&b).1);
+                }
+                SMOD => {
+                    let [a, b] = self.stack.pop_n();
+                    self.stack.push(a.signed_rem(&b));
+                }
+                ADDMOD => {
+                    let [a, b, n] = self.stack.pop_n();
+                    self.stack.push(a.add_mod(&b, &n));
+                }
+                MULMOD => {
+                    let [a, b, n] = self.stack.pop_n();
+                    self.stack.push(a.mul_mod(&b, &n));
+                }
+                EXP => {
+                    let [base, exponent] = self.stack.pop_n();
+                    let exponent_bytes = (exponent.bits() as u64).div_ceil(8);
+                    self.charge(gas::EXP_BYTE * exponent_bytes)?;
+                    self.stack.push(base.wrapping_pow(&exponent));
+                }
+                SIGNEXTEND => {
+                    let [byte_index, value] = self.stack.pop_n();
+                    self.stack.push(value.sign_extend(&byte_index));
+                }
+                LT => {
+                    let [a, b] = self.stack.pop_n();
+                    self.push_bool(a < b);
+                }
+                GT => {
+                    let [a, b] = self.stack.pop_n();
+                    self.push_bool(a > b);
+                }
+                SLT => {
+                    let [a, b] = self.stack.pop_n();
+                    self.push_bool(a.signed_cmp(&b).is_lt());
+                }
+                SGT => {
+                    let [a, b] = self.stack.pop_n();
+                    self.push_bool(a.signed_cmp(&b).is_gt());
+                }
+                EQ => {
+                    let [a, b] = self.stack.pop_n();
+                    self.push_bool(a == b);
+                }
+                ISZERO => {
+                    let a = self.stack.pop();
+                    self.push_bool(a.is_zero());
+                }
+                AND => {
+                    let [a, b] = self.stack.pop_n();
+                    self.stack.push(a.and(&b));
+                }
+                OR => {
+                    let [a, b] = self.stack.pop_n();
+                    self.stack.push(a.or(&b));
+                }
+                XOR => {
+                    let [a, b] = self.stack.pop_n();
+                    self.stack.push(a.xor(&b));
+                }
+                NOT => {
+                    let a = self.stack.pop();
+                    self.stack.push(a.not());
+                }
+                BYTE => {
+                    let [index, value] = self.stack.pop_n();
+                    self.stack.push(value.byte(&index));
+                }
+                SHL => {
+                    let [shift, value] = self.stack.pop_n();
+                    self.stack.push(value.shl(shift.as_shift()));
+                }
+                SHR => {
+                    let [shift, value] = self.stack.pop_n();
+                    self.stack.push(value.shr(shift.as_shift()));
+                }
+                SAR => {
+                    let [shift, value] = self.stack.pop_n();
+                    self.stack.push(value.sar(shift.as_shift()));
+                }
+                KECCAK256 => {
+                    let [offset, len] = self.stack.pop_n();
+                    let range = memory_range(&offset, &len)?;
+                    let words = range.map_or(0, |(_, len)| gas::words(len as u64));
+                    self.charge(gas::KECCAK256_WORD * words)?;
+                    self.expand_memory(system, range)?;
+                    let hash = keccak256(self.memory.range(range));
+                    self.stack.push(U256::from_be_bytes(&hash));
+                }
+                ADDRESS => {
+                    self.stack.push(word_from_address(&self.frame.address));
+                }
+                BALANCE => {
+                    let address = address_from_word(&self.stack.pop());
+                    self.access_account(system, &address)?;
+                    let balance = system.accounts.get(&address)?.balance;
+                    self.stack.push(balance);
+                }
+                ORIGIN => {
+                    self.stack
+                        .push(word_from_address(&system.transaction.origin));
+                }
+                CALLER => {
+                    self.stack.push(word_from_address(&self.frame.caller));
+                }
+                CALLVALUE => {
+                    self.stack.push(self.frame.value);
+                }
+                CALLDATALOAD => {
+                    let offset = self.stack.pop().as_usize_saturated();
+                    let calldata = self.frame.calldata;
+                    let mut word = [0u8; 32];
+                    if offset < calldata.len() {
+                        let len = core::cmp::min(32, calldata.len() - offset);
+                        word[..len].copy_from_slice(&calldata[offset..offset + len]);
+                    }
+                    self.stack.push(U256::from_be_bytes(&word));
+                }
+                CALLDATASIZE => {
+                    self.stack
+                        .push(U256::from_u64(self.frame.calldata.len() as u64));
+                }
+                CALLDATACOPY => {
+                    let [memory_offset, offset, len] = self.stack.pop_n();
+                    let calldata = self.frame.calldata;
+                    self.copy_to_memory(system, calldata, &memory_offset, &offset, &len)?;
+                }
+                CODESIZE => {
+                    self.stack.push(U256::from_u64(code.len() as u64));
+                }
+                CODECOPY => {
+                    let [memory_offset, offset, len] = self.stack.pop_n();
+                    self.copy_to_memory(system, code, &memory_offset, &offset, &len)?;
+                }
+                GASPRICE => {
+                    self.stack.push(system.transaction.gas_price);
+                }
+                EXTCODESIZE => {
+                    let address = address_from_word(&self.stack.pop());
+                    self.access_account(system, &address)?;
+                    let len = external_code(system, &address)?.len();
+                    self.stack.push(U256::from_u64(len as u64));
+                }
+                EXTCODECOPY => {
+                    let [address, memory_offset, offset, len] = self.stack.pop_n();
+                    let address = address_from_word(&address);
+                    self.access_account(system, &address)?;
+                    let external_code = external_code(system, &address)?;
+                    self.copy_to_memory(system, external_code, &memory_offset, &offset, &len)?;
+                }
+                RETURNDATASIZE => {
+                    self.stack
+                        .push(U256::from_u64(system.returndata.as_slice().len() as u64));
+                }
+                RETURNDATACOPY => {
+                    let [memory_offset, offset, len] = self.stack.pop_n();
+                    // unlike other copies, reading out of bounds is an error
+                    let end = offset
+                        .checked_add(&len)
+                        .ok_or(EvmError::ReturnDataOutOfBounds)?;
+                    if end > U256::from_u64(system.returndata.as_slice().len() as u64) {
+                        return Err(EvmError::ReturnDataOutOfBounds);
+                    }
+                    let range = memory_range(&memory_offset, &len)?;
+                    if let Some((_, len)) = range {
+                        self.charge(gas::COPY_WORD * gas::words(len as u64))?;
+                    }
+                    self.expand_memory(system, range)?;
+                    if let Some((memory_offset, len)) = range {
+                        let offset = offset.as_usize_saturated();
+                        self.memory
+                            .slice_mut(memory_offset, len)
+                            .copy_from_slice(&system.returndata.as_slice()[offset..offset + len]);
+                    }
+                }
+                EXTCODEHASH => {
+                    let address = address_from_word(&self.stack.pop());
+                    self.access_account(system, &address)?;
+                    let account = *system.accounts.get(&address)?;
+                    let hash = if account.is_empty() {
+                        U256::ZERO
+                    } else if account.code_type == CodeType::Empty {
+                        U256::from_be_bytes(&keccak256(&[]))
+                    } else {
+                        U256::from_be_bytes(&account.code_hash.0)
+                    };
+                    self.stack.push(hash);
+                }
+                BLOCKHASH => {
+                    let number = self.stack.pop();
+                    let hash = match number.as_u64() {
+                        Some(number) => system.block.block_hash(number),
+                        None => Bytes32::ZERO,
+                    };
+                    self.stack.push(U256::from_be_bytes(&hash.0));
+                }
+                COINBASE => {
+                    self.stack.push(word_from_address(&system.block.coinbase));
+                }
+                TIMESTAMP => {
+                    self.stack.push(U256::from_u64(system.block.timestamp));
+                }
+                NUMBER => {
+                    self.stack.push(U256::from_u64(system.block.number));
+                }
+                PREVRANDAO => {
+                    self.stack
+                        .push(U256::from_be_bytes(&system.block.prevrandao.0));
+                }
+                GASLIMIT => {
+                    self.stack.push(U256::from_u64(system.block.gas_limit));
+                }
+                CHAINID => {
+                    self.stack.push(U256::from_u64(system.block.chain_id));
+                }
+                SELFBALANCE => {
+                    let balance = system.accounts.get(&self.frame.address)?.balance;
+                    self.stack.push(balance);
+                }
+                BASEFEE => {
+                    self.stack.push(system.block.base_fee);
+                }
+                BLOBHASH => {
+                    // blob transactions are not supported, so there are no versioned hashes
+                    let _ = self.stack.pop();
+                    self.stack.push(U256::ZERO);
+                }
+                BLOBBASEFEE => {
+                    self.stack.push(system.block.blob_base_fee);
+                }
+                POP => {
+                    let _ = self.stack.pop();
+                }
+                MLOAD => {
+                    let offset = self.stack.pop();
+                    let range = memory_range(&offset, &U256::from_u32(32))?;
+                    self.expand_memory(system, range)?;
+                    let mut word = [0u8; 32];
+                    word.copy_from_slice(self.memory.range(range));
+                    self.stack.push(U256::from_be_bytes(&word));
+                }
+                MSTORE => {
+                    let [offset, value] = self.stack.pop_n();
+                    let range = memory_range(&offset, &U256::from_u32(32))?;
+                    self.expand_memory(system, range)?;
+                    if let Some((offset, len)) = range {
+                        self.memory
+                            .slice_mut(offset, len)
+                            .copy_from_slice(&value.to_be_bytes());
+                    }
+                }
+                MSTORE8 => {
+                    let [offset, value] = self.stack.pop_n();
+                    let range = memory_range(&offset, &U256::ONE)?;
+                    self.expand_memory(system, range)?;
+                    if let Some((offset, len)) = range {
+                        self.memory.slice_mut(offset, len)[0] = value.0[0] as u8;
+                    }
+                }
+                SLOAD => {
+                    let key = Bytes32(self.stack.pop().to_be_bytes());
+                    let access = system.storage.read(&self.frame.address, &key)?;
+                    self.charge(if access.was_warm {
+                        gas::WARM_ACCESS
+                    } else {
+                        gas::COLD_SLOAD
+                    })?;
+                    self.stack.push(U256::from_be_bytes(&access.value.0));
+                }
+                SSTORE => {
+                    self.check_not_static()?;
+                    if self.resources.remaining() <= gas::SSTORE_STIPEND {
+                        return Err(EvmError::OutOfGas);
+                    }
+                    let [key, value] = self.stack.pop_n();
+                    let key = Bytes32(key.to_be_bytes());
+                    let value = Bytes32(value.to_be_bytes());
+                    let access = system.storage.write(&self.frame.address, &key, &value)?;
+                    let (cost, refund) =
+                        costs::storage_write_cost(&access.original, &access.value, &value);
+                    let cold_cost = if access.was_warm { 0 } else { gas::COLD_SLOAD };
+                    self.charge(cost + cold_cost)?;
+                    system.refund += refund;
+                }
+                JUMP => {
+                    let destination = self.stack.pop();
+                    self.jump(&destination)?;
+                }
+                JUMPI => {
+                    let [destination, condition] = self.stack.pop_n();
+                    if !condition.is_zero() {
+                        self.jump(&destination)?;
+                    }
+                }
+                PC => {
+                    self.stack.push(U256::from_u32(ir::argument(word)));
+                }
+                MSIZE => {
+                    self.stack.push(U256::from_u64(self.memory.len() as u64));
+                }
+                GAS => {
+                    self.stack.push(U256::from_u64(self.resources.remaining()));
+                }
+                TLOAD => {
+                    let key = Bytes32(self.stack.pop().to_be_bytes());
+                    let access = system.transient_storage.read(&self.frame.address, &key)?;
+                    self.stack.push(U256::from_be_bytes(&access.value.0));
+                }
+                TSTORE => {
+                    self.check_not_static()?;
+                    let [key, value] = self.stack.pop_n();
+                    let key = Bytes32(key.to_be_bytes());
+                    let value = Bytes32(value.to_be_bytes());
+                    let _ = system
+                        .transient_storage
+                        .write(&self.frame.address, &key, &value)?;
+                }
+                MCOPY => {
+                    let [destination, source, len] = self.stack.pop_n();
+                    let destination_range = memory_range(&destination, &len)?;
+                    let source_range = memory_range(&source, &len)?;
+                    if let Some((_, len)) = destination_range {
+                        self.charge(gas::COPY_WORD * gas::words(len as u64))?;
+                    }
+                    // single expansion that covers both ranges
+                    let furthest = match (destination_range, source_range) {
+                        (Some(a), Some(b)) if a.0 > b.0 => Some(a),
+                        (_, b) => b,
+                    };
+                    self.expand_memory(system, furthest)?;
+                    if let (Some((destination, len)), Some((source, _))) =
+                        (destination_range, source_range)
+                    {
+                        self.memory
+                            .slice_mut(0, self.memory.len())
+                            .copy_within(source..source + len, destination);
+                    }
+                }
+                PUSH0 => {
+                    self.stack.push(U256::ZERO);
+                }
+                PUSH1..=PUSH32 => {
+                    if opcode - PUSH1 < MAX_INLINE_PUSH_LEN {
+                        self.stack.push(U256::from_u32(ir::argument(word)));
+                    } else {
+                        self.stack.push(self.program.push_value(self.position));
+                        self.position += LARGE_PUSH_WORDS;
+                    }
+                }
+                DUP1..=DUP16 => {
+                    self.stack.dup((opcode - DUP1 + 1) as usize);
+                }
+                SWAP1..=SWAP16 => {
+                    self.stack.swap((opcode - SWAP1 + 1) as usize);
+                }
+                LOG0..=LOG4 => {
+                    self.check_not_static()?;
+                    let num_topics = (opcode - LOG0) as usize;
+                    let [offset, len] = self.stack.pop_n();
+                    let mut topics = [Bytes32::ZERO; MAX_TOPICS];
+                    for topic in topics[..num_topics].iter_mut() {
+                        *topic = Bytes32(self.stack.pop().to_be_bytes());
+                    }
+                    let range = memory_range(&offset, &len)?;
+                    let data_len = range.map_or(0, |(_, len)| len as u64);
+                    self.charge(gas::LOG_DATA_BYTE * data_len)?;
+                    self.expand_memory(system, range)?;
+                    system.events.emit(
+                        &self.frame.address,
+                        &topics[..num_topics],
+                        self.memory.range(range),
+                    )?;
+                }
+                CREATE | CREATE2 => self.create(system, opcode == CREATE2)?,
+                CALL | CALLCODE | DELEGATECALL | STATICCALL => self.call(system, opcode)?,
+                RETURN | REVERT => {
+                    let [offset, len] = self.stack.pop_n();
+                    let range = memory_range(&offset, &len)?;
+                    self.expand_memory(system, range)?;
+                    return Ok(if opcode == RETURN {
+                        ExitReason::Return(range)
+                    } else {
+                        ExitReason::Revert(range)
+                    });
+                }
+                SELFDESTRUCT => {
+                    self.check_not_static()?;
+                    let beneficiary = address_from_word(&self.stack.pop());
+                    if !system.accounts.touch(&beneficiary)? {
+                        self.charge(gas::COLD_ACCOUNT_ACCESS)?;
+                    }
+                    let balance = system.accounts.get(&self.frame.address)?.balance;
+                    if !balance.is_zero() && system.accounts.get(&beneficiary)?.is_empty() {
+                        self.charge(gas::NEW_ACCOUNT)?;
+                    }
+                    system
+                        .accounts
+                        .transfer(&self.frame.address, &beneficiary, &balance)?;
+                    // EIP-6780: only contracts created in the same transaction are deleted
+                    if system
+                        .accounts
+                        .is_created_in_transaction(&self.frame.address)?
+                    {
+                        system.accounts.mark_destructed(&self.frame.address)?;
+                    }
+                    return Ok(ExitReason::Stop);
+                }
+                _ => return Err(EvmError::InvalidOpcode),
+            }
+        }
+    }
+
+    fn create(&mut self, system: &mut System, is_create2: bool) -> Result<(), EvmError> {
+        self.check_not_static()?;
+        let [value, offset, len] = self.stack.pop_n();
+        let salt = if is_create2 {
+            Some(Bytes32(self.stack.pop().to_be_bytes()))
+        } else {
+            None
+        };
+
+        let range = memory_range(&offset, &len)?;
+        let init_code_len = range.map_or(0, |(_, len)| len);
+        if init_code_len > MAX_INIT_CODE_SIZE {
+            return Err(EvmError::InitCodeTooLarge);
+        }
+        let words = gas::words(init_code_len as u64);
+        let hashing_cost = if is_create2 {
+            gas::KECCAK256_WORD * words
+        } else {
+            0
+        };
+        self.charge(gas::INIT_CODE_WORD * words + hashing_cost)?;
+        self.expand_memory(system, range)?;
+
+        system.returndata.clear();
+        let account = *system.accounts.get(&self.frame.address)?;
+        if account.balance < value || account.nonce == u64::MAX {
+            self.stack.push(U256::ZERO);
+            return Ok(());
+        }
+
+        let request = DeploymentRequest {
+            deployer: self.frame.address,
+            code_type: CodeType::Evm,
+            code: self.memory.range(range),
+            constants: &[],
+            constructor_calldata: &[],
+            value,
+            scheme: match salt {
+                Some(salt) => DeploymentScheme::Create2 { salt },
+                None => DeploymentScheme::Create,
+            },
+        };
+        let mut callee_resources = self
+            .resources
+            .take(gas::all_but_one_64th(self.resources.remaining()));
+        let result = match system.deploy(&request, &mut callee_resources) {
+            Ok(result) => result,
+            Err(SystemError::AddressCollision) => {
+                self.stack.push(U256::ZERO);
+                return Ok(());
+            }
+            Err(error) => return Err(error.into()),
+        };
+        self.resources.reclaim(callee_resources);
+
+        if result.status == ExecutionStatus::Success {
+            self.stack.push(word_from_address(&result.address));
+        } else {
+            self.stack.push(U256::ZERO);
+        }
+
+        Ok(())
+    }
+
+    fn call(&mut self, system: &mut System, opcode: u8) -> Result<(), EvmError> {
+        let requested_gas = self.stack.pop();
+        let address = address_from_word(&self.stack.pop());
+        let value = if opcode == CALL || opcode == CALLCODE {
+            self.stack.pop()
+        } else {
+            U256::ZERO
+        };
+        let [args_offset, args_len, ret_offset, ret_len] = self.stack.pop_n();
+        if opcode == CALL && !value.is_zero() {
+            self.check_not_static()?;
+        }
+
+        let args_range = memory_range(&args_offset, &args_len)?;
+        let ret_range = memory_range(&ret_offset, &ret_len)?;
+        self.expand_memory(system, args_range)?;
+        self.expand_memory(system, ret_range)?;
+
+        self.access_account(system, &address)?;
+        if !value.is_zero() {
+            self.charge(gas::CALL_VALUE)?;
+            if opcode == CALL && system.accounts.get(&address)?.is_empty() {
+                self.charge(gas::NEW_ACCOUNT)?;
+            }
+        }
+
+        let available = gas::all_but_one_64th(self.resources.remaining());
+        let callee_gas = match requested_gas.as_u64() {
+            Some(requested) if requested < available => requested,
+            _ => available,
+        };
+        let mut callee_resources = self.resources.take(callee_gas);
+        if !value.is_zero() {
+            callee_resources.reclaim(Resources::new(gas::CALL_STIPEND));
+        }
+
+        system.returndata.clear();
+        let balance = system.accounts.get(&self.frame.address)?.balance;
+        if balance < value {
+            self.resources.reclaim(callee_resources);
+            self.stack.push(U256::ZERO);
+            return Ok(());
+        }
+
+        let request = match opcode {
+            CALL => CallRequest {
+                is_static: self.frame.is_static,
+                ..CallRequest::new(
+                    self.frame.address,
+                    address,
+                    value,
+                    self.memory.range(args_range),
+                )
+            },
+            CALLCODE => CallRequest {
+                caller: self.frame.address,
+                callee: self.frame.address,
+                code_address: address,
+                value,
+                transfer_value: true,
+                calldata: self.memory.range(args_range),
+                is_static: self.frame.is_static,
+            },
+            DELEGATECALL => CallRequest {
+                caller: self.frame.caller,
+                callee: self.frame.address,
+                code_address: address,
+                value: self.frame.value,
+                transfer_value: false,
+                calldata: self.memory.range(args_range),
+                is_static: self.frame.is_static,
+            },
+            _ => CallRequest {
+                is_static: true,
+                ..CallRequest::new(
+                    self.frame.address,
+                    address,
+                    U256::ZERO,
+                    self.memory.range(args_range),
+                )
+            },
+        };
+        let status = system.call(&request, &mut callee_resources)?;
+        self.resources.reclaim(callee_resources);
+
+        if let Some((offset, len)) = ret_range {
+            let returndata = system.returndata.as_slice();
+            let to_copy = core::cmp::min(len, returndata.len());
+            self.memory
+                .slice_mut(offset, to_copy)
+                .copy_from_slice(&returndata[..to_copy]);
+        }
+
+        self.push_bool(status == ExecutionStatus::Success);
+
+        Ok(())
+    }
+}
+
+fn external_code(system: &mut System, address: &Address) -> Result<&'static [u8], SystemError> {
+    let account = *system.accounts.get(address)?;
+    if account.code_type == CodeType::Empty {
+        return Ok(&[]);
+    }
+
+    system.code.get(&account.code_hash)
+}
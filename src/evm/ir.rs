@@ -0,0 +1,393 @@
+use super::analysis::fill_jumpdest_bitmap;
+use super::gas;
+use super::opcodes::*;
+use super::stack::STACK_LIMIT;
+use crate::bigint::U256;
+use crate::system::SystemError;
+
+// Deployment-time translation of EVM bytecode into a pre-decoded stream of words, that is
+// stored as the preprocessing artifact of the code.
+//
+// Artifact layout (all little-endian u32 words):
+//  - header: `[bitmap_words, num_jumpdests, stream_len]`
+//  - jumpdest bitmap, one bit per byte of the code
+//  - rank of every bitmap word (number of jumpdests before it), so that a jump finds its
+//    target in O(1)
+//  - position in the stream of the block started by every jumpdest
+//  - instruction stream
+//
+// Every instruction is a word `opcode | argument << 8`, followed by the immediate
+// for some opcodes:
+//  - `BEGIN_BLOCK` is followed by the static gas of the block and by the packed stack
+//    requirements (items the block pops below its starting height, and the most it grows)
+//  - `PUSH1`..`PUSH3` keep the value in the argument, longer pushes are followed by the 8 words
+//    of the value
+//  - `PC` keeps the original program counter in the argument
+//
+// `JUMPDEST` is not emitted at all, as it only starts a new block. Undefined opcodes are
+// translated into `INVALID`, and stream is terminated with `STOP`, so the interpreter
+// never runs out of it.
+
+/// Unassigned in EVM, so never clashes with a real opcode
+pub const BEGIN_BLOCK: u8 = 0x0c;
+
+pub const HEADER_WORDS: usize = 3;
+pub const BLOCK_IMMEDIATE_WORDS: usize = 2;
+pub const LARGE_PUSH_WORDS: usize = 8;
+/// Pushes of up to this many bytes keep the value in the argument of the instruction
+pub const MAX_INLINE_PUSH_LEN: u8 = 3;
+
+#[derive(Clone, Copy)]
+struct OpcodeInfo {
+    static_gas: u64,
+    inputs: u32,
+    outputs: u32,
+    /// Next instruction starts a new block. Besides control flow, this is set for instructions
+    /// that look at the remaining gas, so that it's never charged ahead for them
+    ends_block: bool,
+}
+
+const fn info(static_gas: u64, inputs: u32, outputs: u32) -> Option<OpcodeInfo> {
+    Some(OpcodeInfo {
+        static_gas,
+        inputs,
+        outputs,
+        ends_block: false,
+    })
+}
+
+const fn terminal(static_gas: u64, inputs: u32, outputs: u32) -> Option<OpcodeInfo> {
+    Some(OpcodeInfo {
+        static_gas,
+        inputs,
+        outputs,
+        ends_block: true,
+    })
+}
+
+/// Static part of the gas and stack effect, `None` for undefined opcodes. Dynamic
+/// gas (memory expansion, account access, etc.) is charged by the instruction itself
+const fn opcode_info(opcode: u8) -> Option<OpcodeInfo> {
+    match opcode {
+        STOP => terminal(gas::ZERO, 0, 0),
+        ADD | SUB | LT | GT | SLT | SGT | EQ | AND | OR | XOR | BYTE | SHL | SHR | SAR => {
+            info(gas::VERY_LOW, 2, 1)
+        }
+        MUL | DIV | SDIV | MOD | SMOD | SIGNEXTEND => info(gas::LOW, 2, 1),
+        ADDMOD | MULMOD => info(gas::MID, 3, 1),
+        EXP => info(gas::EXP, 2, 1),
+        ISZERO | NOT => info(gas::VERY_LOW, 1, 1),
+        KECCAK256 => info(gas::KECCAK256, 2, 1),
+        ADDRESS | ORIGIN | CALLER | CALLVALUE | CALLDATASIZE | CODESIZE | GASPRICE
+        | RETURNDATASIZE | COINBASE | TIMESTAMP | NUMBER | PREVRANDAO | GASLIMIT | CHAINID
+        | BASEFEE | BLOBBASEFEE | PC | MSIZE | PUSH0 => info(gas::BASE, 0, 1),
+        BALANCE | EXTCODESIZE | EXTCODEHASH => info(gas::ZERO, 1, 1),
+        CALLDATALOAD | BLOBHASH | MLOAD => info(gas::VERY_LOW, 1, 1),
+        CALLDATACOPY | CODECOPY | RETURNDATACOPY | MCOPY => info(gas::VERY_LOW, 3, 0),
+        EXTCODECOPY => info(gas::ZERO, 4, 0),
+        BLOCKHASH => info(gas::BLOCKHASH, 1, 1),
+        SELFBALANCE => info(gas::LOW, 0, 1),
+        POP => info(gas::BASE, 1, 0),
+        MSTORE | MSTORE8 => info(gas::VERY_LOW, 2, 0),
+        SLOAD => info(gas::ZERO, 1, 1),
+        SSTORE => terminal(gas::ZERO, 2, 0),
+        JUMP => terminal(gas::MID, 1, 0),
+        JUMPI => terminal(gas::HIGH, 2, 0),
+        GAS => terminal(gas::BASE, 0, 1),
+        JUMPDEST => info(gas::JUMPDEST, 0, 0),
+        TLOAD => info(gas::WARM_ACCESS, 1, 1),
+        TSTORE => info(gas::WARM_ACCESS, 2, 0),
+        PUSH1..=PUSH32 => info(gas::VERY_LOW, 0, 1),
+        DUP1..=DUP16 => {
+            let depth = (opcode - DUP1 + 1) as u32;
+            info(gas::VERY_LOW, depth, depth + 1)
+        }
+        SWAP1..=SWAP16 => {
+            let depth = (opcode - SWAP1 + 1) as u32;
+            info(gas::VERY_LOW, depth + 1, depth + 1)
+        }
+        LOG0..=LOG4 => {
+            let num_topics = (opcode - LOG0) as u32;
+            info(
+                gas::LOG + gas::LOG_TOPIC * num_topics as u64,
+                2 + num_topics,
+                0,
+            )
+        }
+        CREATE => terminal(gas::CREATE, 3, 1),
+        CREATE2 => terminal(gas::CREATE, 4, 1),
+        CALL | CALLCODE => terminal(gas::ZERO, 7, 1),
+        DELEGATECALL | STATICCALL => terminal(gas::ZERO, 6, 1),
+        RETURN | REVERT => terminal(gas::ZERO, 2, 0),
+        INVALID => terminal(gas::ZERO, 0, 0),
+        SELFDESTRUCT => terminal(gas::SELFDESTRUCT, 1, 0),
+        _ => None,
+    }
+}
+
+#[must_use]
+#[inline(always)]
+pub const fn encode(opcode: u8, argument: u32) -> u32 {
+    opcode as u32 | (argument << 8)
+}
+
+#[must_use]
+#[inline(always)]
+pub const fn opcode(word: u32) -> u8 {
+    word as u8
+}
+
+#[must_use]
+#[inline(always)]
+pub const fn argument(word: u32) -> u32 {
+    word >> 8
+}
+
+#[must_use]
+#[inline(always)]
+pub const fn pack_stack_bounds(required: u32, growth: u32) -> u32 {
+    required | (growth << 16)
+}
+
+#[must_use]
+#[inline(always)]
+pub const fn unpack_stack_bounds(word: u32) -> (usize, usize) {
+    ((word & 0xffff) as usize, (word >> 16) as usize)
+}
+
+/// Writes words into the artifact buffer
+struct Writer<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Writer<'a> {
+    fn push(&mut self, word: u32) -> Result<(), SystemError> {
+        let end = self.len + 4;
+        if end > self.buffer.len() {
+            return Err(SystemError::BlobStorageFull);
+        }
+        self.buffer[self.len..end].copy_from_slice(&word.to_le_bytes());
+        self.len = end;
+
+        Ok(())
+    }
+
+    fn set(&mut self, position: usize, word: u32) {
+        self.buffer[4 * position..4 * position + 4].copy_from_slice(&word.to_le_bytes());
+    }
+
+    const fn position(&self) -> usize {
+        self.len / 4
+    }
+}
+
+/// Static gas and stack bounds of the block that is being translated
+struct Block {
+    position: usize,
+    static_gas: u64,
+    height: i32,
+    lowest: i32,
+    highest: i32,
+}
+
+impl Block {
+    fn new(writer: &mut Writer) -> Result<Self, SystemError> {
+        let position = writer.position();
+        writer.push(encode(BEGIN_BLOCK, 0))?;
+        for _ in 0..BLOCK_IMMEDIATE_WORDS {
+            writer.push(0)?;
+        }
+
+        Ok(Self {
+            position,
+            static_gas: 0,
+            height: 0,
+            lowest: 0,
+            highest: 0,
+        })
+    }
+
+    fn add(&mut self, info: &OpcodeInfo) {
+        self.static_gas += info.static_gas;
+        self.height -= info.inputs as i32;
+        self.lowest = core::cmp::min(self.lowest, self.height);
+        self.height += info.outputs as i32;
+        self.highest = core::cmp::max(self.highest, self.height);
+    }
+
+    fn finish(self, writer: &mut Writer) {
+        // code is at most 48 KB, so neither of the values can overflow
+        writer.set(self.position + 1, self.static_gas as u32);
+        // blocks that can never pass the check are kept as is and fail at runtime
+        let required = core::cmp::min(-self.lowest as u32, STACK_LIMIT as u32 + 1);
+        let growth = core::cmp::min(self.highest as u32, STACK_LIMIT as u32 + 1);
+        writer.set(self.position + 2, pack_stack_bounds(required, growth));
+    }
+}
+
+/// Preprocessing function of the EVM interpreter
+pub fn translate(code: &[u8], artifact: &mut [u8]) -> Result<usize, SystemError> {
+    let bitmap_words = code.len().div_ceil(32);
+    let bitmap_len = 4 * bitmap_words;
+    if HEADER_WORDS * 4 + 2 * bitmap_len > artifact.len() {
+        return Err(SystemError::BlobStorageFull);
+    }
+
+    let (header, tables) = artifact.split_at_mut(HEADER_WORDS * 4);
+    let (bitmap, rest) = tables.split_at_mut(bitmap_len);
+    fill_jumpdest_bitmap(code, bitmap);
+    let mut num_jumpdests = 0u32;
+    for (word, rank) in bitmap.chunks(4).zip(rest.chunks_mut(4)) {
+        rank.copy_from_slice(&num_jumpdests.to_le_bytes());
+        num_jumpdests += u32::from_le_bytes([word[0], word[1], word[2], word[3]]).count_ones();
+    }
+    header[..4].copy_from_slice(&(bitmap_words as u32).to_le_bytes());
+    header[4..8].copy_from_slice(&num_jumpdests.to_le_bytes());
+
+    let targets_position = HEADER_WORDS + 2 * bitmap_words;
+    let stream_position = targets_position + num_jumpdests as usize;
+    let mut writer = Writer {
+        buffer: artifact,
+        len: 4 * targets_position,
+    };
+    for _ in 0..num_jumpdests {
+        writer.push(0)?;
+    }
+
+    let mut block: Option<Block> = None;
+    let mut jumpdest_index = 0;
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        let info = match opcode_info(opcode) {
+            Some(info) => info,
+            None => opcode_info(INVALID).unwrap(),
+        };
+
+        if opcode == JUMPDEST {
+            if let Some(block) = block.take() {
+                block.finish(&mut writer);
+            }
+            let position = writer.position() - stream_position;
+            writer.set(targets_position + jumpdest_index, position as u32);
+            jumpdest_index += 1;
+        }
+        let current = match block.as_mut() {
+            Some(current) => current,
+            None => block.insert(Block::new(&mut writer)?),
+        };
+        current.add(&info);
+
+        match opcode {
+            JUMPDEST => {}
+            PUSH1..=PUSH32 => {
+                let len = (opcode - PUSH1 + 1) as usize;
+                let mut value = [0u8; 32];
+                let available = core::cmp::min(len, code.len() - pc - 1);
+                value[32 - len..32 - len + available]
+                    .copy_from_slice(&code[pc + 1..pc + 1 + available]);
+                let value = U256::from_be_bytes(&value);
+                if len as u8 <= MAX_INLINE_PUSH_LEN {
+                    writer.push(encode(opcode, value.0[0]))?;
+                } else {
+                    writer.push(encode(opcode, 0))?;
+                    for limb in value.0 {
+                        writer.push(limb)?;
+                    }
+                }
+                pc += len;
+            }
+            PC => writer.push(encode(PC, pc as u32))?,
+            _ if opcode_info(opcode).is_none() => writer.push(encode(INVALID, 0))?,
+            _ => writer.push(encode(opcode, 0))?,
+        }
+
+        if info.ends_block {
+            if let Some(block) = block.take() {
+                block.finish(&mut writer);
+            }
+        }
+        pc += 1;
+    }
+    if let Some(block) = block.take() {
+        block.finish(&mut writer);
+    }
+    writer.push(encode(STOP, 0))?;
+
+    let stream_len = writer.position() - stream_position;
+    writer.set(2, stream_len as u32);
+
+    Ok(writer.len)
+}
+
+/// Translated code, as seen by the interpreter
+#[derive(Clone, Copy)]
+pub struct Program {
+    bitmap: &'static [u32],
+    ranks: &'static [u32],
+    targets: &'static [u32],
+    pub stream: &'static [u32],
+}
+
+impl Program {
+    /// Artifacts are word aligned and we've produced them ourselves (the oracle can only
+    /// give us the artifact with the hash we've committed to), so only the sizes are checked
+    pub fn from_artifact(artifact: &'static [u8]) -> Result<Self, SystemError> {
+        if artifact.len() & 3 != 0 || artifact.len() < HEADER_WORDS * 4 {
+            return Err(SystemError::InvalidCode);
+        }
+        let words: &'static [u32] = unsafe {
+            core::slice::from_raw_parts(artifact.as_ptr().cast::<u32>(), artifact.len() / 4)
+        };
+        let bitmap_words = words[0] as usize;
+        let num_jumpdests = words[1] as usize;
+        let stream_len = words[2] as usize;
+        if HEADER_WORDS + 2 * bitmap_words + num_jumpdests + stream_len != words.len() {
+            return Err(SystemError::InvalidCode);
+        }
+        let (bitmap, rest) = words[HEADER_WORDS..].split_at(bitmap_words);
+        let (ranks, rest) = rest.split_at(bitmap_words);
+        let (targets, stream) = rest.split_at(num_jumpdests);
+
+        Ok(Self {
+            bitmap,
+            ranks,
+            targets,
+            stream,
+        })
+    }
+
+    /// Position in the stream of the block that starts at `destination` if it's a valid jumpdest
+    #[must_use]
+    #[inline(always)]
+    pub fn jump_target(&self, destination: usize) -> Option<usize> {
+        let word_index = destination / 32;
+        let bit = destination % 32;
+        let word = *self.bitmap.get(word_index)?;
+        if word & (1 << bit) == 0 {
+            return None;
+        }
+        let rank = self.ranks[word_index] + (word & ((1 << bit) - 1)).count_ones();
+
+        Some(self.targets[rank as usize] as usize)
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn word(&self, position: usize) -> u32 {
+        self.stream[position]
+    }
+
+    /// Reads the value of a long push that follows the instruction at `position`
+    #[must_use]
+    #[inline(always)]
+    pub fn push_value(&self, position: usize) -> U256 {
+        let mut value = U256::ZERO;
+        value
+            .0
+            .copy_from_slice(&self.stream[position..position + LARGE_PUSH_WORDS]);
+
+        value
+    }
+}
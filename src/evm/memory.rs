@@ -0,0 +1,111 @@
+use super::{gas, EvmError};
+use crate::bigint::U256;
+use crate::system::memory::{MemoryArena, MemoryRegion};
+use crate::system::resources::Resources;
+
+// Memory can not be larger than this, as gas for it would exceed any block gas limit anyway.
+// It is well below `usize::MAX` of RV32, so ends of the ranges never overflow
+pub const MAX_MEMORY_SIZE: u64 = 1 << 26;
+
+/// Byte addressable memory of the frame, grows in 32 byte words. Must be the last allocation
+/// of the frame in the arena, so that it can grow
+pub struct Memory {
+    region: MemoryRegion,
+}
+
+/// Converts `(offset, len)` operands into a memory range. Zero length ranges do not touch
+/// the memory at all, so their offset can be arbitrary
+pub fn memory_range(offset: &U256, len: &U256) -> Result<Option<(usize, usize)>, EvmError> {
+    if len.is_zero() {
+        return Ok(None);
+    }
+    let (Some(offset), Some(len)) = (offset.as_u64(), len.as_u64()) else {
+        return Err(EvmError::OutOfGas);
+    };
+    match offset.checked_add(len) {
+        Some(end) if end <= MAX_MEMORY_SIZE => {}
+        _ => return Err(EvmError::OutOfGas),
+    }
+    let (Ok(offset), Ok(len)) = (usize::try_from(offset), usize::try_from(len)) else {
+        return Err(EvmError::OutOfGas);
+    };
+
+    Ok(Some((offset, len)))
+}
+
+impl Memory {
+    pub fn new(arena: &mut MemoryArena) -> Result<Self, EvmError> {
+        let region = arena.allocate(0)?;
+
+        Ok(Self { region })
+    }
+
+    #[must_use]
+    pub const fn region(&self) -> MemoryRegion {
+        self.region
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn len(&self) -> usize {
+        self.region.len()
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.region.is_empty()
+    }
+
+    /// Expands memory to cover the range, charging for the expansion
+    pub fn expand(
+        &mut self,
+        arena: &mut MemoryArena,
+        resources: &mut Resources,
+        range: Option<(usize, usize)>,
+    ) -> Result<(), EvmError> {
+        let Some((offset, len)) = range else {
+            return Ok(());
+        };
+        let end = offset as u64 + len as u64;
+        if end <= self.len() as u64 {
+            return Ok(());
+        }
+
+        let current_words = gas::words(self.len() as u64);
+        let new_words = gas::words(end);
+        resources.charge(gas::memory_cost(new_words) - gas::memory_cost(current_words))?;
+        arena.grow(&mut self.region, (new_words * 32) as usize)?;
+
+        Ok(())
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn slice(&self, offset: usize, len: usize) -> &'static [u8] {
+        &self.region.as_slice()[offset..offset + len]
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn slice_mut(&mut self, offset: usize, len: usize) -> &'static mut [u8] {
+        &mut self.region.as_mut_slice()[offset..offset + len]
+    }
+
+    /// Slice for the range, that must already be expanded
+    #[must_use]
+    pub fn range(&self, range: Option<(usize, usize)>) -> &'static [u8] {
+        match range {
+            Some((offset, len)) => self.slice(offset, len),
+            None => &[],
+        }
+    }
+
+    /// Copies `src` into memory, padding with zeroes up to `len`
+    pub fn copy_padded(&mut self, offset: usize, len: usize, src: &[u8]) {
+        let dst = self.slice_mut(offset, len);
+        let to_copy = core::cmp::min(len, src.len());
+        dst[..to_copy].copy_from_slice(&src[..to_copy]);
+        dst[to_copy..].fill(0);
+    }
+}
@@ -0,0 +1,145 @@
+pub mod analysis;
+pub mod gas;
+pub mod interpreter;
+pub mod ir;
+pub mod memory;
+pub mod opcodes;
+pub mod stack;
+
+use self::interpreter::ExitReason;
+use self::ir::Program;
+use self::memory::Memory;
+use self::stack::Stack;
+use crate::bigint::U256;
+use crate::system::interpreter::{ExecutionFrame, ExecutionStatus, Interpreter};
+use crate::system::resources::Resources;
+use crate::system::types::Address;
+use crate::system::{System, SystemError};
+
+/// EIP-170
+pub const MAX_CODE_SIZE: usize = 24576;
+/// EIP-3860
+pub const MAX_INIT_CODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+/// EIP-3541: prefix reserved for EOF
+pub const RESERVED_CODE_PREFIX: u8 = 0xef;
+
+/// Exceptional halts of the frame. All of them, except `System`, consume the gas of
+/// the frame and fail it, while `System` means that the kernel itself can not continue
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvmError {
+    StackOverflow,
+    StackUnderflow,
+    OutOfGas,
+    InvalidJump,
+    InvalidOpcode,
+    StaticStateChange,
+    ReturnDataOutOfBounds,
+    InitCodeTooLarge,
+    System(SystemError),
+}
+
+impl From<SystemError> for EvmError {
+    fn from(error: SystemError) -> Self {
+        match error {
+            SystemError::OutOfResources | SystemError::OutOfMemory => Self::OutOfGas,
+            error => Self::System(error),
+        }
+    }
+}
+
+pub const INTERPRETER: Interpreter = Interpreter {
+    validate,
+    preprocess: Some(ir::translate),
+    execute,
+    constructor_returns_code: true,
+};
+
+fn validate(code: &[u8]) -> bool {
+    code.len() <= MAX_CODE_SIZE && code.first() != Some(&RESERVED_CODE_PREFIX)
+}
+
+/// Addresses are kept on the stack as 20 byte big-endian numbers
+#[must_use]
+pub fn word_from_address(address: &Address) -> U256 {
+    U256::from_be_bytes(&address.0)
+}
+
+#[must_use]
+pub fn address_from_word(word: &U256) -> Address {
+    let bytes = word.to_be_bytes();
+    let mut evm_address = [0u8; 20];
+    evm_address.copy_from_slice(&bytes[12..]);
+
+    Address::from_evm_address(&evm_address)
+}
+
+fn execute(
+    system: &mut System,
+    frame: &ExecutionFrame,
+    resources: &mut Resources,
+) -> Result<ExecutionStatus, SystemError> {
+    // everything the frame allocates is released at once by resetting the arena to this point
+    let frame_start = system.memory.allocate(0)?;
+    let result = execute_in_arena(system, frame, resources);
+    system.memory.release(frame_start);
+
+    result
+}
+
+fn execute_in_arena(
+    system: &mut System,
+    frame: &ExecutionFrame,
+    resources: &mut Resources,
+) -> Result<ExecutionStatus, SystemError> {
+    // code is translated once at deployment
+    let program = Program::from_artifact(frame.artifact)?;
+    let stack = Stack::new(&mut system.memory)?;
+    // memory goes last, as it's the only allocation that grows
+    let memory = match Memory::new(&mut system.memory) {
+        Ok(memory) => memory,
+        Err(EvmError::System(error)) => return Err(error),
+        Err(_) => return Err(SystemError::OutOfMemory),
+    };
+
+    let mut interpreter = interpreter::Interpreter {
+        frame,
+        resources,
+        stack,
+        memory,
+        program,
+        position: 0,
+    };
+    let exit = interpreter.run(system);
+    let exit = match exit {
+        Ok(ExitReason::Return(range)) if frame.is_constructor => {
+            let len = range.map_or(0, |(_, len)| len as u64);
+            interpreter
+                .resources
+                .charge(gas::CODE_DEPOSIT_BYTE * len)
+                .map(|_| ExitReason::Return(range))
+                .map_err(EvmError::from)
+        }
+        exit => exit,
+    };
+
+    match exit {
+        Ok(ExitReason::Stop) => {
+            system.returndata.clear();
+            Ok(ExecutionStatus::Success)
+        }
+        Ok(ExitReason::Return(range)) => {
+            system.returndata.set(interpreter.memory.range(range))?;
+            Ok(ExecutionStatus::Success)
+        }
+        Ok(ExitReason::Revert(range)) => {
+            system.returndata.set(interpreter.memory.range(range))?;
+            Ok(ExecutionStatus::Revert)
+        }
+        Err(EvmError::System(error)) => Err(error),
+        Err(_) => {
+            interpreter.resources.burn();
+            system.returndata.clear();
+            Ok(ExecutionStatus::Failure)
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use super::EvmError;
+use crate::bigint::U256;
+use crate::system::memory::{MemoryArena, MemoryRegion};
+use crate::system::SystemError;
+
+pub const STACK_LIMIT: usize = 1024;
+
+/// Operand stack of the frame, placed into the memory arena, as 32 KB per frame
+/// are too much for the kernel stack
+pub struct Stack {
+    region: MemoryRegion,
+    slots: *mut U256,
+    len: usize,
+}
+
+impl Stack {
+    pub fn new(arena: &mut MemoryArena) -> Result<Self, SystemError> {
+        let mut region = arena.allocate(STACK_LIMIT * core::mem::size_of::<U256>())?;
+        // arena regions are word aligned
+        let slots = region.as_mut_slice().as_mut_ptr().cast::<U256>();
+
+        Ok(Self {
+            region,
+            slots,
+            len: 0,
+        })
+    }
+
+    #[must_use]
+    pub const fn region(&self) -> MemoryRegion {
+        self.region
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Checks that the block that is about to run neither underflows nor overflows the
+    /// stack, after that instructions of the block work with the stack unchecked
+    #[inline(always)]
+    pub fn check(&self, required: usize, growth: usize) -> Result<(), EvmError> {
+        if self.len < required {
+            return Err(EvmError::StackUnderflow);
+        }
+        if self.len + growth > STACK_LIMIT {
+            return Err(EvmError::StackOverflow);
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn push(&mut self, value: U256) {
+        debug_assert!(self.len < STACK_LIMIT);
+        unsafe { self.slots.add(self.len).write(value) };
+        self.len += 1;
+    }
+
+    #[inline(always)]
+    pub fn pop(&mut self) -> U256 {
+        debug_assert!(self.len > 0);
+        self.len -= 1;
+
+        unsafe { self.slots.add(self.len).read() }
+    }
+
+    #[inline(always)]
+    pub fn pop_n<const N: usize>(&mut self) -> [U256; N] {
+        debug_assert!(self.len >= N);
+        let mut result = [U256::ZERO; N];
+        for dst in result.iter_mut() {
+            self.len -= 1;
+            *dst = unsafe { self.slots.add(self.len).read() };
+        }
+
+        result
+    }
+
+    /// `DUPn`, where `depth` is 1-based
+    #[inline(always)]
+    pub fn dup(&mut self, depth: usize) {
+        debug_assert!(self.len >= depth);
+        let value = unsafe { self.slots.add(self.len - depth).read() };
+
+        self.push(value)
+    }
+
+    /// `SWAPn`, where `depth` is 1-based
+    #[inline(always)]
+    pub fn swap(&mut self, depth: usize) {
+        debug_assert!(self.len > depth);
+        unsafe {
+            core::ptr::swap(
+                self.slots.add(self.len - 1),
+                self.slots.add(self.len - 1 - depth),
+            )
+        };
+    }
+}
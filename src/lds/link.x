@@ -1,8 +1,16 @@
 PROVIDE(_stext = ORIGIN(REGION_TEXT));
 PROVIDE(_stack_start = ORIGIN(REGION_STACK) + LENGTH(REGION_STACK));
 PROVIDE(_max_hart_id = 0);
-PROVIDE(_hart_stack_size = 512K);
+/* nested calls of interpreters (up to 1024 frames deep) run on the kernel stack. Interpreter
+   stacks and memories are in the arena, but every nested call still takes 2-3K of the stack
+   for the call path through the system layer, so 1024 of them need about 3M, and the rest is
+   headroom for native and WASM frames */
+PROVIDE(_hart_stack_size = 8M);
 PROVIDE(_heap_size = 64M);
+/* trap handlers run on a stack of their own, that mscratch points to */
+PROVIDE(_exception_stack_size = 64K);
+/* the trap entry writes the whole frame before it checks for overflow, so the guard must fit one */
+PROVIDE(_exception_stack_guard_size = 4K);
 
 PROVIDE(UserSoft = DefaultHandler);
 PROVIDE(SupervisorSoft = DefaultHandler);
@@ -69,6 +77,10 @@ SECTIONS
     *(.trap.rust);
 
     *(.text .text.*);
+
+    /* End of the code, for the disassembly of fatal traps */
+    . = ALIGN(4);
+    _etext = .;
   } > REGION_TEXT AT > REGION_TEXT :text
 
   .rodata : ALIGN(4)
@@ -102,6 +114,18 @@ SECTIONS
     _ebss = .;
   } > REGION_BSS AT > REGION_BSS :bss
 
+  /* stack of the trap handlers, with the guard right below it. Nothing else is ever
+     placed in the guard, so a frame that overflows into it doesn't corrupt anything */
+  .exception_stack (NOLOAD) : ALIGN(4096)
+  {
+    _sexception_stack_guard = .;
+    . += _exception_stack_guard_size;
+    _sexception_stack = .;
+    . += _exception_stack_size;
+    . = ALIGN(16);
+    _eexception_stack = .;
+  } > REGION_BSS
+
   /* fictitious region that represents the memory available for the heap */
   .heap (NOLOAD) : ALIGN(4096)
   {
@@ -163,6 +187,9 @@ BUG(riscv-rt): the LMA of .data is not 4-byte aligned");
 ASSERT(_sbss % 4 == 0 && _ebss % 4 == 0, "
 BUG(riscv-rt): .bss is not 4-byte aligned");
 
+ASSERT(_exception_stack_guard_size >= 320 && _sexception_stack % 16 == 0, "
+BUG: exception stack guard must fit a trap frame of RV64 and keep the stack 16-byte aligned");
+
 ASSERT(_sheap % 4 == 0, "
 BUG(riscv-rt): start of .heap is not 4-byte aligned");
 
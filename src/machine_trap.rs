@@ -1,89 +1,48 @@
-use core::hint::unreachable_unchecked;
-
+use crate::cpu::{gp, Instruction, Registers};
 use crate::helper_reg_utils::*;
-use crate::trap_frame::MachineTrapFrame;
+use crate::println;
+use crate::trap_frame::{dump_registers, TrapFrame, XLEN_BYTES};
+use crate::traps;
+use crate::user_mode::{self, UserExit, ENTER_USER_MODE, EXIT_CALL_BIT};
 use crate::utils::*;
 
 use riscv::register::{mstatus::MPP, satp::Mode};
 
 #[inline(never)]
 fn machine_mode_handle_unaligned_load(
-    trap_frame: &mut MachineTrapFrame,
+    trap_frame: &mut TrapFrame,
     instr: u32,
     epc: usize,
+    user: bool,
 ) -> (usize, bool) {
     // Simulator and circuit disable unaligned loads (but still can load individual u8/u16/u32 without crossing memory boundary),
     // so we should only expect cases when we cross the boudnary
 
-    let rd = get_rd(instr);
-    if rd == 0 {
-        unsafe { riscv::asm::wfi() };
-    }
-
-    // now depending on how many bytes we need to load we proceed
-    let mut imm = ITypeOpcode::imm(instr);
-    sign_extend(&mut imm, 12);
-    let rs1 = ITypeOpcode::rs1(instr);
-    let rs1: u32 = trap_frame.registers[rs1 as usize];
-    let physical_address = rs1.wrapping_add(imm);
-
-    let aligned_address = physical_address & !3;
-    let unalignment = physical_address & 3;
-
+    let rd = get_rd(instr) as usize;
     let funct3 = ITypeOpcode::funct3(instr);
-
-    let bytes_to_read = match funct3 {
-        0 | 4 => 1,
-        1 | 5 => 2,
-        2 => 4,
-        _ => return (0, true), // invalid instruction
+    let Some(bytes_to_read) = access_width(funct3, true) else {
+        return (0, true); // invalid instruction
     };
+    let address = effective_address(trap_frame, ITypeOpcode::rs1(instr), ITypeOpcode::imm(instr));
 
-    // now just match over everything
-
-    // no translation here
-    let value: u32 = match (unalignment, bytes_to_read) {
-        (1, 2) => {
-            // single write
-            let value_low =
-                unsafe { core::ptr::from_exposed_addr::<u32>(aligned_address as usize).read() };
-
-            value_low >> 8
-        }
-        (1, 4) | (2, 4) | (3, 4) | (3, 2) => {
-            let (next_address, overflow) = aligned_address.overflowing_add(4);
-            if overflow {
-                unsafe { riscv::asm::wfi() };
-            }
-
-            let value_low =
-                unsafe { core::ptr::from_exposed_addr::<u32>(aligned_address as usize).read() };
-            let value_high =
-                unsafe { core::ptr::from_exposed_addr::<u32>(next_address as usize).read() };
-            // properly shift to get value. Sign/zero extend below takes care of cleaning up top bytes if needed
-            let shift = unalignment * 8;
-
-            (value_low >> shift) | (value_high << (32 - shift))
-        }
-        _ => unsafe {
-            riscv::asm::wfi();
-
-            unreachable_unchecked()
-        },
+    // we load with kernel permissions, so user code can only get what PMP gives it
+    if user && !crate::pmp::is_accessible(address, bytes_to_read, false) {
+        return (0, true);
+    }
+    let Some(value) = read_bytes(address, bytes_to_read) else {
+        return (0, true);
     };
 
-    let ret_val = match funct3 {
-        1 => sign_extend_16(value),
-        2 => value,
-        5 => zero_extend_16(value),
-        _ => unsafe {
-            riscv::asm::wfi();
-
-            unreachable_unchecked()
-        },
+    // LBU, LHU and LWU zero extend, the rest sign extend to the register
+    let shift = 64 - bytes_to_read as u32 * 8;
+    let value = if funct3 & 4 == 0 {
+        ((value << shift) as i64 >> shift) as u64
+    } else {
+        value
     };
-
-    trap_frame.registers[rd as usize] = ret_val;
+    if rd != 0 {
+        trap_frame.registers[rd] = value as usize;
+    }
 
     // return to mepc + 4
     (epc.wrapping_add(4), false)
@@ -91,143 +50,238 @@ fn machine_mode_handle_unaligned_load(
 
 #[inline(never)]
 fn machine_mode_handle_unaligned_store(
-    trap_frame: &mut MachineTrapFrame,
+    trap_frame: &mut TrapFrame,
     instr: u32,
     epc: usize,
+    user: bool,
 ) -> (usize, bool) {
-    // Same - we only handle u16/u32 unaligned stores
+    // Same - stores that cross the boundary
+
+    let funct3 = STypeOpcode::funct3(instr);
+    let Some(bytes_to_write) = access_width(funct3, false) else {
+        return (0, true); // invalid instruction
+    };
+    let address = effective_address(trap_frame, STypeOpcode::rs1(instr), STypeOpcode::imm(instr));
+    if user && !crate::pmp::is_accessible(address, bytes_to_write, true) {
+        return (0, true);
+    }
+
+    let rs2 = STypeOpcode::rs2(instr);
+    let value_to_write = trap_frame.registers[rs2 as usize] as u64;
+    if write_bytes(address, bytes_to_write, value_to_write).is_none() {
+        return (0, true);
+    }
+
+    // return to mepc + 4
+    (epc.wrapping_add(4), false)
+}
+
+/// Bytes accessed by the load or store with `funct3`. LD, LWU and SD are only there on RV64
+const fn access_width(funct3: u32, load: bool) -> Option<usize> {
+    let rv64 = XLEN_BYTES == 8;
+    match (funct3, load) {
+        (0, _) | (4, true) => Some(1),
+        (1, _) | (5, true) => Some(2),
+        (2, _) => Some(4),
+        (6, true) if rv64 => Some(4),
+        (3, _) if rv64 => Some(8),
+        _ => None,
+    }
+}
 
-    let mut imm = STypeOpcode::imm(instr);
+/// rs1 plus the 12-bit immediate of a load or store
+fn effective_address(trap_frame: &TrapFrame, rs1: u32, mut imm: u32) -> usize {
     sign_extend(&mut imm, 12);
 
-    let rs1 = STypeOpcode::rs1(instr);
-    let rs1: u32 = trap_frame.registers[rs1 as usize];
-    let physical_address = rs1.wrapping_add(imm);
+    trap_frame.registers[rs1 as usize].wrapping_add(imm as i32 as isize as usize)
+}
 
-    let funct3 = STypeOpcode::funct3(instr);
-    let bytes_to_write = match funct3 {
-        a @ 0 | a @ 1 | a @ 2 => 1 << a,
-        _ => return (0, true), // invalid instruction
-    };
+/// Little endian value of `len` bytes at `address`. Bytes are read one by one, as only
+/// the accesses that don't cross the boundary work, `None` if the range wraps around
+fn read_bytes(address: usize, len: usize) -> Option<u64> {
+    address.checked_add(len)?;
+    let mut value = 0u64;
+    for i in 0..len {
+        // no translation here
+        let byte = unsafe { core::ptr::from_exposed_addr::<u8>(address + i).read() };
+        value |= (byte as u64) << (i * 8);
+    }
 
-    let aligned_address = physical_address & !3;
-    let unalignment = physical_address & 3;
+    Some(value)
+}
 
-    let rs2 = STypeOpcode::rs2(instr);
-    let rs2: u32 = trap_frame.registers[rs2 as usize];
-    let value_to_write = rs2;
-
-    match (unalignment, bytes_to_write) {
-        (1, 2) => {
-            // single read and write
-            let existing_value_low =
-                unsafe { core::ptr::from_exposed_addr::<u32>(aligned_address as usize).read() };
-            let new_value = existing_value_low & 0xff0000ff & ((value_to_write & 0x0000ffff) << 8);
-            unsafe {
-                core::ptr::from_exposed_addr_mut::<u32>(aligned_address as usize).write(new_value)
-            };
-        }
-        (1, 4) | (2, 4) | (3, 4) | (3, 2) => {
-            let (next_address, overflow) = aligned_address.overflowing_add(4);
-            if overflow {
-                unsafe { riscv::asm::wfi() };
-            }
+/// Writes the lowest `len` bytes of `value` to `address`, little endian and one by one
+fn write_bytes(address: usize, len: usize, value: u64) -> Option<()> {
+    address.checked_add(len)?;
+    for i in 0..len {
+        let byte = (value >> (i * 8)) as u8;
+        unsafe { core::ptr::from_exposed_addr_mut::<u8>(address + i).write(byte) };
+    }
 
-            let existing_value_low =
-                unsafe { core::ptr::from_exposed_addr::<u32>(aligned_address as usize).read() };
-            let existing_value_high =
-                unsafe { core::ptr::from_exposed_addr::<u32>(next_address as usize).read() };
-
-            let value_mask = match bytes_to_write {
-                2 => 0x0000ffffu32,
-                4 => 0xffffffffu32,
-                _ => unsafe { unreachable_unchecked() },
-            };
-            let masked_value = value_to_write & value_mask;
-
-            let (mask_existing_low, mask_existing_high) = match (unalignment, bytes_to_write) {
-                (1, 4) => (0x000000ffu32, 0xffffff00u32),
-                (2, 4) => (0x0000ffffu32, 0xffff0000u32),
-                (3, 4) => (0x00ffffffu32, 0xff000000u32),
-                (3, 2) => (0x00ffffffu32, 0xffffff00u32),
-                _ => unsafe { unreachable_unchecked() },
-            };
-
-            let shift = unalignment * 8;
-            let new_low = (existing_value_low & mask_existing_low) | (masked_value << shift);
-            let new_high =
-                (existing_value_high & mask_existing_high) | (masked_value >> (32 - shift));
-
-            unsafe {
-                core::ptr::from_exposed_addr_mut::<u32>(aligned_address as usize).write(new_low)
-            };
-            unsafe {
-                core::ptr::from_exposed_addr_mut::<u32>(next_address as usize).write(new_high)
-            };
-        }
-        _ => unsafe {
-            riscv::asm::wfi();
+    Some(())
+}
 
-            unreachable_unchecked()
-        },
-    };
+/// Registers the handlers of the kernel: emulation of misaligned accesses, ecalls and preemption
+pub fn register_handlers() {
+    for cause in [
+        traps::EXCEPTION_INSTRUCTION_MISALIGNED,
+        traps::EXCEPTION_LOAD_MISALIGNED,
+        traps::EXCEPTION_STORE_MISALIGNED,
+    ] {
+        traps::register_exception(cause, handle_misaligned);
+    }
+    traps::register_exception(traps::EXCEPTION_USER_ECALL, handle_ecall);
+    traps::register_exception(traps::EXCEPTION_MACHINE_ECALL, handle_ecall);
+    traps::register_interrupt(traps::INTERRUPT_MACHINE_TIMER, handle_timer);
+}
 
-    // return to mepc + 4
-    (epc.wrapping_add(4), false)
+/// Whether the trap comes from U-mode code entered by `user_mode::run`
+#[inline(always)]
+fn is_user(trap_frame: &TrapFrame) -> bool {
+    trap_frame.previous_mode() == MPP::User && user_mode::is_active()
 }
 
+// fast track for misaligned memory access
 #[link_section = ".trap.rust"]
-#[export_name = "MachineExceptionHandler"]
-fn custom_machine_exception_handler(trap_frame: &mut MachineTrapFrame) -> usize {
-    let cause = riscv::register::mcause::read();
-    let status = riscv::register::mstatus::read();
-    let previous_mode = status.mpp();
-    let cause_num = cause.code();
-    let epc = riscv::register::mepc::read();
+fn handle_misaligned(trap_frame: &mut TrapFrame) -> Option<usize> {
+    let previous_mode = trap_frame.previous_mode();
+    let epc = trap_frame.mepc;
     let satp = riscv::register::satp::read();
+    let user = is_user(trap_frame);
+
+    if previous_mode == MPP::Machine || satp.mode() == Mode::Bare {
+        // we do not need a translation, but we also have an opcode value in the TVAL
+        let instr = trap_frame.mtval as u32;
 
-    // fast track for misaligned memory access
-    match cause_num {
-        0 | 4 | 6 => {
-            if previous_mode == MPP::Machine || satp.mode() == Mode::Bare {
-                // we do not need a translation, but we also have an opcode value in the TVAL
-                let instr = riscv::register::mtval::read();
-                let instr = instr as u32;
-
-                //// we can also do by dereference
-                // let mepc = core::ptr::from_exposed_addr::<u32>(epc);
-                // let instr = unsafe { mepc.read() };
-
-                let opcode = get_opcode(instr);
-
-                if opcode == 0b0000011 {
-                    // LOAD
-                    let (new_pc, invalid_instruction) =
-                        machine_mode_handle_unaligned_load(trap_frame, instr, epc);
-                    if invalid_instruction {
-                        unsafe { riscv::asm::wfi() }
-                    } else {
-                        return new_pc;
-                    }
-                } else if opcode == 0b0100011 {
-                    // STORE
-                    let (new_pc, invalid_instruction) =
-                        machine_mode_handle_unaligned_store(trap_frame, instr, epc);
-                    if invalid_instruction {
-                        unsafe { riscv::asm::wfi() }
-                    } else {
-                        return new_pc;
-                    }
-                } else {
-                    unsafe { riscv::asm::wfi() }
-                }
-            } else {
-                // need translation
+        //// we can also do by dereference
+        // let mepc = core::ptr::from_exposed_addr::<u32>(epc);
+        // let instr = unsafe { mepc.read() };
+
+        let opcode = get_opcode(instr);
+
+        if opcode == 0b0000011 {
+            // LOAD
+            let (new_pc, invalid_instruction) =
+                machine_mode_handle_unaligned_load(trap_frame, instr, epc, user);
+            if !invalid_instruction {
+                return Some(new_pc);
+            }
+            if !user {
+                unsafe { riscv::asm::wfi() }
+            }
+        } else if opcode == 0b0100011 {
+            // STORE
+            let (new_pc, invalid_instruction) =
+                machine_mode_handle_unaligned_store(trap_frame, instr, epc, user);
+            if !invalid_instruction {
+                return Some(new_pc);
+            }
+            if !user {
                 unsafe { riscv::asm::wfi() }
             }
+        } else if !user {
+            unsafe { riscv::asm::wfi() }
+        }
+    } else {
+        // need translation
+        unsafe { riscv::asm::wfi() }
+    }
+
+    None
+}
+
+/// ECALL from U or M mode
+#[link_section = ".trap.rust"]
+fn handle_ecall(trap_frame: &mut TrapFrame) -> Option<usize> {
+    let epc = trap_frame.mepc;
+    let number = trap_frame.registers[gp(Registers::A7)] as u32;
+    if trap_frame.cause() == traps::EXCEPTION_MACHINE_ECALL && number == ENTER_USER_MODE {
+        return Some(user_mode::enter(trap_frame, epc));
+    }
+    // syscalls of the user code are exits to the kernel, other ecalls are faults
+    if is_user(trap_frame) && number & EXIT_CALL_BIT != 0 {
+        let exit = UserExit::Call(number & !EXIT_CALL_BIT);
+        return Some(user_mode::leave(trap_frame, epc.wrapping_add(4), exit));
+    }
+
+    None
+}
+
+/// Deadline of the U-mode code, it gets back to the kernel at the interrupted instruction
+#[link_section = ".trap.rust"]
+fn handle_timer(trap_frame: &mut TrapFrame) -> Option<usize> {
+    let epc = trap_frame.mepc;
+    crate::timer::disarm();
+    if is_user(trap_frame) {
+        return Some(user_mode::leave(trap_frame, epc, UserExit::Preempted));
+    }
+
+    // a deadline that came due as the code exited, the interrupt is cleared now
+    Some(epc)
+}
+
+/// Traps without a handler that took them: faults of the user code are reported to the kernel
+/// code that runs it, anything else is fatal
+#[link_section = ".trap.rust"]
+pub fn handle_unhandled(trap_frame: &mut TrapFrame) -> usize {
+    if is_user(trap_frame) && !trap_frame.is_interrupt() {
+        let exit = UserExit::Exception {
+            cause: trap_frame.cause(),
+            tval: trap_frame.mtval,
+        };
+        return user_mode::leave(trap_frame, trap_frame.mepc, exit);
+    }
+
+    dump_fatal_trap(trap_frame);
+    crate::rust_abort();
+}
+
+/// Instructions disassembled on both sides of the faulting one
+const DISASSEMBLY_WINDOW: usize = 4;
+
+extern "C" {
+    static _stext: u8;
+    static _etext: u8;
+}
+
+/// Prints the frame and the code around mepc, as far as it is kernel code
+#[inline(never)]
+fn dump_fatal_trap(trap_frame: &TrapFrame) {
+    println!("fatal trap");
+    dump_registers(trap_frame);
+
+    let epc = trap_frame.mepc;
+
+    let (text_start, text_end) = unsafe {
+        (
+            core::ptr::addr_of!(_stext).expose_addr(),
+            core::ptr::addr_of!(_etext).expose_addr(),
+        )
+    };
+    let start = (epc & !3)
+        .saturating_sub(DISASSEMBLY_WINDOW * 4)
+        .max(text_start);
+    let end = (epc & !3)
+        .saturating_add((DISASSEMBLY_WINDOW + 1) * 4)
+        .min(text_end);
+    for address in (start..end).step_by(4) {
+        let word = unsafe { core::ptr::from_exposed_addr::<u32>(address).read() };
+        let marker = if address == epc & !3 { "=>" } else { "  " };
+        match Instruction::decode(word) {
+            Some(instruction) => {
+                println!("{} {:08x}: {:08x}  {}", marker, address, word, instruction)
+            }
+            None => println!("{} {:08x}: {:08x}  <invalid>", marker, address, word),
         }
-        _ => {}
     }
+}
 
+/// Reports a trap whose frame didn't fit on the exception stack, the entry calls it
+/// from the top of the stack with the frame in the guard
+#[link_section = ".trap.rust"]
+#[export_name = "_exception_stack_overflow"]
+extern "C" fn exception_stack_overflow(trap_frame: &TrapFrame) -> ! {
+    println!("exception stack overflow");
+    dump_fatal_trap(trap_frame);
     crate::rust_abort();
 }
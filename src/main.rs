@@ -19,19 +19,40 @@ extern "C" {
     static _sidata: u32;
 }
 
-core::arch::global_asm!(include_str!("asm/asm.S"));
-
+// the assembly is the same for both widths, apart from the word size and its loads and stores
+#[cfg(target_arch = "riscv32")]
+core::arch::global_asm!(include_str!("asm/xlen32.S"), include_str!("asm/asm.S"));
+#[cfg(target_arch = "riscv64")]
+core::arch::global_asm!(include_str!("asm/xlen64.S"), include_str!("asm/asm.S"));
+
+pub mod bigint;
+pub mod context;
 pub mod cpu;
+pub mod crypto;
+pub mod evm;
 pub mod helper_reg_utils;
 pub mod machine_trap;
+pub mod native;
+pub mod oracle;
+pub mod pmp;
+pub mod precompiles;
 pub mod quasi_uart;
+pub mod system;
+pub mod timer;
 pub mod trap_frame;
+pub mod traps;
+pub mod user_mode;
 pub mod utils;
+pub mod wasm;
 
-use riscv::register::mcause as xcause;
-use riscv_rt::__INTERRUPTS;
+use riscv::register::mie;
+use riscv::register::mscratch;
+use riscv::register::mtvec::{self, TrapMode};
 
-use self::trap_frame::MachineTrapFrame;
+use self::oracle::{Oracle, OracleQuery};
+use self::system::transaction::Transaction;
+use self::system::SystemError;
+use self::trap_frame::TrapFrame;
 
 // ///////////////////////////////////
 // / RUST MACROS
@@ -120,57 +141,90 @@ fn main() -> ! {
     use core::fmt::Write;
     let _ = pinger.write_str("Hello from kernel");
 
-    // and test cross-word boundary unaligned load/store
-
-    let a = 0x12345678u32;
-
-    let b = unsafe {
-        let ptr = core::ptr::from_exposed_addr_mut::<u32>(0x17 as usize);
-        ptr.write_volatile(a);
-        ptr.read_volatile()
-    };
-
-    if a == b {
-        let _ = pinger.write_str("Unaligned u32 store/load is fine");
-    } else {
-        let _ = pinger.write_str("Unaligned u32 store/load is broken");
-        let low = unsafe { core::ptr::from_exposed_addr::<u32>(0x14 as usize).read_volatile() };
-        let high = unsafe { core::ptr::from_exposed_addr::<u32>(0x18 as usize).read_volatile() };
-        println!("Low = 0x{:08x}", low);
-        println!("High = 0x{:08x}", high);
-        println!("Expected 0x12345678, read 0x{:08x}", b);
+    init_system();
+    run_block();
+
+    loop {}
+}
+
+/// Hands the heap to the system layer and registers interpreters for the code types we support,
+/// and the precompiles
+fn init_system() {
+    extern "C" {
+        static _sheap: u8;
+        static _eheap: u8;
     }
 
-    let a = 0x1234u16;
-
-    let b = unsafe {
-        let ptr = core::ptr::from_exposed_addr_mut::<u16>(0x23 as usize);
-        ptr.write_volatile(a);
-        ptr.read_volatile()
-    };
-
-    if a == b {
-        let _ = pinger.write_str("Unaligned u16 store/load is fine");
-    } else {
-        let _ = pinger.write_str("Unaligned u16 store/load is broken");
-        let low = unsafe { core::ptr::from_exposed_addr::<u32>(0x20 as usize).read_volatile() };
-        let high = unsafe { core::ptr::from_exposed_addr::<u32>(0x24 as usize).read_volatile() };
-        println!("Low = 0x{:08x}", low);
-        println!("High = 0x{:08x}", high);
-        println!("Expected 0x1234, read 0x{:04x}", b);
+    let system = system::system();
+    unsafe {
+        let start = core::ptr::addr_of!(_sheap) as usize;
+        let end = core::ptr::addr_of!(_eheap) as usize;
+        system.memory.init(start, end);
     }
+    system
+        .interpreters
+        .register(system::account::CodeType::Evm, &evm::INTERPRETER);
+    system
+        .interpreters
+        .register(system::account::CodeType::Wasm, &wasm::INTERPRETER);
+    system
+        .interpreters
+        .register(system::account::CodeType::Native, &native::INTERPRETER);
+    precompiles::register(&mut system.precompiles);
+}
 
-    loop {}
+/// Executes the transactions of the block in order, until the oracle has no more of them.
+/// Transactions are authenticated by their signatures, so whoever answers the oracle can't
+/// spend from other accounts
+fn run_block() {
+    let system = system::system();
+    system.start_block();
+    for index in 0u64.. {
+        let encoding = system.memory.allocate_with(|buffer| {
+            Oracle::new()
+                .query_bytes(
+                    OracleQuery::Transaction,
+                    &[index as u32, (index >> 32) as u32],
+                    buffer,
+                )
+                .ok_or(SystemError::OracleResponseTooLarge)
+        });
+        // skipping a transaction would change the block, so it can't be executed at all
+        let Ok(encoding) = encoding else {
+            println!(
+                "Transaction {} does not fit into memory, block aborted",
+                index
+            );
+            rust_abort();
+        };
+        if encoding.is_empty() {
+            break;
+        }
+        let result = Transaction::decode(encoding.as_slice())
+            .and_then(|transaction| system.execute_transaction(&transaction));
+        system.memory.release(encoding);
+        match result {
+            Ok(result) => println!(
+                "Transaction {}: {:?}, {} gas used",
+                index, result.status, result.gas_used
+            ),
+            Err(SystemError::OracleResponseTooLarge) => {
+                println!(
+                    "Transaction {} needs more data than fits into memory, block aborted",
+                    index
+                );
+                rust_abort();
+            }
+            Err(error) => println!("Transaction {} is invalid: {:?}", index, error),
+        }
+    }
 }
 
 use riscv_rt::pre_init;
 
 #[pre_init]
 unsafe fn machine_pre_init() {
-    // extern "C" {
-    //     fn _machine_start_trap();
-    // }
-    // xtvec::write(_machine_start_trap as *const () as usize, xTrapMode::Direct);
+    // mtvec and mscratch are set up by `_start`, before anything may trap
 }
 
 #[export_name = "_mp_hook"]
@@ -183,41 +237,29 @@ pub fn mp_hook(hartid: usize) -> bool {
     }
 }
 
+/// Overrides the riscv-rt one, that would point mtvec to its own `_start_trap`
 #[export_name = "_setup_interrupts"]
 pub unsafe fn custom_setup_interrupts() {
     extern "C" {
-        fn _machine_start_trap();
+        fn machine_trap_vector();
+        static _eexception_stack: u8;
     }
 
-    // xtvec::write(_machine_start_trap as *const () as usize, xTrapMode::Direct);
+    machine_trap::register_handlers();
+    // harts without vectored mode keep it direct, and the first vector is the entry anyway
+    mtvec::write(machine_trap_vector as *const () as usize, TrapMode::Vectored);
+    // no trap is being handled, so the whole exception stack is free
+    mscratch::write(core::ptr::addr_of!(_eexception_stack) as usize);
+    // the kernel runs with mstatus.MIE clear, so the timer only interrupts U-mode code
+    timer::disarm();
+    mie::set_mtimer();
 }
 
 #[link_section = ".trap.rust"]
 #[export_name = "_machine_start_trap_rust"]
-pub extern "C" fn machine_start_trap_rust(trap_frame: *mut MachineTrapFrame) -> usize {
-    extern "C" {
-        fn MachineExceptionHandler(trap_frame: &mut MachineTrapFrame) -> usize;
-        fn DefaultHandler();
-    }
+pub extern "C" fn machine_start_trap_rust(trap_frame: *mut TrapFrame) -> usize {
+    let trap_frame = unsafe { &mut *trap_frame };
+    trap_frame.context_id = user_mode::active_context_id();
 
-    unsafe {
-        let cause = xcause::read();
-
-        if cause.is_exception() {
-            MachineExceptionHandler(&mut *trap_frame)
-        } else {
-            if cause.code() < __INTERRUPTS.len() {
-                let h = &__INTERRUPTS[cause.code()];
-                if h.reserved == 0 {
-                    DefaultHandler();
-                } else {
-                    (h.handler)();
-                }
-            } else {
-                DefaultHandler();
-            }
-
-            riscv::register::mepc::read()
-        }
-    }
+    traps::dispatch(trap_frame).unwrap_or_else(|| machine_trap::handle_unhandled(trap_frame))
 }
@@ -0,0 +1,347 @@
+use super::image::{Range, MAX_IMAGE_SIZE};
+use super::verifier;
+use super::NativeError;
+
+// ELF32 executables, as the linker makes them for `riscv32i-unknown-none-elf`. There is no address
+// translation, so the segments can't go to the addresses they were linked at, and are loaded into
+// the arena instead, keeping their layout. Code reaches its data pc-relatively, but the pointers
+// stored in the data (vtables, jump tables) are absolute, so the executable has to be static-pie
+// (`-C relocation-model=pie -C link-arg=--pie`), and its `R_RISCV_RELATIVE` relocations are
+// applied at every load. Plain `ET_EXEC` files are rejected, as nothing tells where their
+// absolute addresses are.
+//
+// Loadable segments are grouped by permission into code (X or RX), read-only data (R) and writable
+// data (RW). Every group is a contiguous range with its own PMP region, and the writable one comes
+// last, so that the stack goes right after it. Writable code, TLS and dynamic linking are rejected.
+
+pub const ELF_MAGIC: [u8; 4] = *b"\x7fELF";
+const ELFCLASS32: u8 = 1;
+const ELFDATA2LSB: u8 = 1;
+const EV_CURRENT: u8 = 1;
+/// Static-pie executables are shared objects with an entry
+const ET_DYN: u16 = 3;
+const EM_RISCV: u16 = 243;
+/// `EF_RISCV_RVE` and the hard-float ABIs
+const EF_RISCV_UNSUPPORTED: u32 = 0xe;
+
+const EHDR_SIZE: usize = 52;
+const PHDR_SIZE: usize = 32;
+const RELA_SIZE: usize = 12;
+pub const MAX_SEGMENTS: usize = 8;
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const PT_INTERP: u32 = 3;
+const PT_TLS: u32 = 7;
+
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+const PF_RX: u32 = PF_R | PF_X;
+const PF_RW: u32 = PF_R | PF_W;
+
+const DT_NULL: u32 = 0;
+const DT_NEEDED: u32 = 1;
+const DT_PLTRELSZ: u32 = 2;
+const DT_RELA: u32 = 7;
+const DT_RELASZ: u32 = 8;
+const DT_RELAENT: u32 = 9;
+const DT_REL: u32 = 17;
+const DT_RELR: u32 = 36;
+
+const R_RISCV_NONE: u32 = 0;
+const R_RISCV_RELATIVE: u32 = 3;
+
+/// Kinds of the segments, by their permissions
+const CODE: usize = 0;
+const READONLY: usize = 1;
+const WRITABLE: usize = 2;
+
+#[derive(Clone, Copy, Default)]
+struct Segment {
+    offset: usize,
+    vaddr: u32,
+    filesz: usize,
+    memsz: usize,
+    flags: u32,
+    kind: usize,
+}
+
+pub struct Elf<'a> {
+    blob: &'a [u8],
+    /// Lowest address of the segments, that becomes offset 0 of the image
+    pub start: u32,
+    /// Offset of the entry point
+    pub entry: usize,
+    /// Up to the end of the last segment, rounded up to a word
+    pub size: usize,
+    /// Code can be read too, e.g. when the linker merged it with rodata
+    pub code_readable: bool,
+    /// Indexed by the kind of the segments
+    ranges: [Option<Range>; 3],
+    segments: [Segment; MAX_SEGMENTS],
+    num_segments: usize,
+    relocations: &'a [u8],
+}
+
+#[must_use]
+#[inline(always)]
+pub fn is_elf(blob: &[u8]) -> bool {
+    blob.starts_with(&ELF_MAGIC)
+}
+
+impl<'a> Elf<'a> {
+    pub fn parse(blob: &'a [u8]) -> Result<Self, NativeError> {
+        if blob.len() < EHDR_SIZE || !is_elf(blob) {
+            return Err(NativeError::Malformed);
+        }
+        if blob[4] != ELFCLASS32 || blob[5] != ELFDATA2LSB || blob[6] != EV_CURRENT {
+            return Err(NativeError::Malformed);
+        }
+        if half(blob, 16) != ET_DYN || half(blob, 18) != EM_RISCV {
+            return Err(NativeError::Malformed);
+        }
+        if word(blob, 36) & EF_RISCV_UNSUPPORTED != 0 {
+            return Err(NativeError::Malformed);
+        }
+        let entry = word(blob, 24);
+        let phoff = word(blob, 28) as usize;
+        let phnum = half(blob, 44) as usize;
+        if half(blob, 42) as usize != PHDR_SIZE {
+            return Err(NativeError::Malformed);
+        }
+        let headers = phoff
+            .checked_add(phnum * PHDR_SIZE)
+            .and_then(|end| blob.get(phoff..end))
+            .ok_or(NativeError::Malformed)?;
+
+        let mut elf = Self {
+            blob,
+            start: 0,
+            entry: 0,
+            size: 0,
+            code_readable: false,
+            ranges: [None; 3],
+            segments: [Segment::default(); MAX_SEGMENTS],
+            num_segments: 0,
+            relocations: &[],
+        };
+        let mut dynamic = None;
+        for header in headers.chunks_exact(PHDR_SIZE) {
+            let segment = Segment {
+                offset: word(header, 4) as usize,
+                vaddr: word(header, 8),
+                filesz: word(header, 16) as usize,
+                memsz: word(header, 20) as usize,
+                flags: word(header, 24),
+                kind: CODE,
+            };
+            match word(header, 0) {
+                PT_LOAD => elf.add_segment(segment)?,
+                PT_DYNAMIC => dynamic = Some(segment),
+                PT_INTERP | PT_TLS => return Err(NativeError::Malformed),
+                _ => {}
+            }
+        }
+        let code = elf.ranges[CODE].ok_or(NativeError::Malformed)?;
+        let entry = entry.wrapping_sub(elf.start) as usize;
+        if entry & 3 != 0 || entry < code.start || entry >= code.end {
+            return Err(NativeError::Malformed);
+        }
+        elf.entry = entry;
+        if let Some(dynamic) = dynamic {
+            elf.relocations = elf.parse_dynamic(dynamic)?;
+            elf.check_relocations()?;
+        }
+
+        Ok(elf)
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn code(&self) -> Range {
+        // parsed images always have code
+        self.ranges[CODE].unwrap_or_default()
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn readonly(&self) -> Option<Range> {
+        self.ranges[READONLY]
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn writable(&self) -> Option<Range> {
+        self.ranges[WRITABLE]
+    }
+
+    /// Checks the code of every executable segment, they may jump to each other
+    pub fn verify(&self) -> Result<(), NativeError> {
+        for segment in self.segments() {
+            if segment.kind == CODE {
+                verifier::verify(
+                    &self.blob[segment.offset..segment.offset + segment.filesz],
+                    segment.vaddr.wrapping_sub(self.start) as usize,
+                    self.code(),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies the segments into `memory`, which is zeroed and placed at `base`, and relocates them
+    pub fn load(&self, memory: &mut [u8], base: u32) {
+        for segment in self.segments() {
+            let offset = segment.vaddr.wrapping_sub(self.start) as usize;
+            memory[offset..offset + segment.filesz]
+                .copy_from_slice(&self.blob[segment.offset..segment.offset + segment.filesz]);
+        }
+        let delta = base.wrapping_sub(self.start);
+        for relocation in self.relocations.chunks_exact(RELA_SIZE) {
+            if word(relocation, 4) & 0xff == R_RISCV_RELATIVE {
+                let offset = word(relocation, 0).wrapping_sub(self.start) as usize;
+                let value = delta.wrapping_add(word(relocation, 8));
+                memory[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+
+    fn segments(&self) -> impl Iterator<Item = &Segment> {
+        self.segments[..self.num_segments].iter()
+    }
+
+    /// Segments come sorted by address, and get appended to the range of their permission
+    fn add_segment(&mut self, segment: Segment) -> Result<(), NativeError> {
+        if segment.memsz == 0 {
+            return Ok(());
+        }
+        if self.num_segments == MAX_SEGMENTS {
+            return Err(NativeError::LimitExceeded);
+        }
+        let file_end = segment.offset.checked_add(segment.filesz);
+        if !matches!(file_end, Some(end) if end <= self.blob.len())
+            || segment.filesz > segment.memsz
+        {
+            return Err(NativeError::Malformed);
+        }
+        if self.num_segments == 0 {
+            self.start = segment.vaddr;
+        }
+        let start = segment.vaddr.wrapping_sub(self.start) as usize;
+        if segment.vaddr & 3 != 0 || segment.vaddr < self.start || start < self.size {
+            return Err(NativeError::Malformed);
+        }
+        let end = start
+            .checked_add(segment.memsz)
+            .filter(|end| *end <= MAX_IMAGE_SIZE)
+            .ok_or(NativeError::LimitExceeded)?
+            .next_multiple_of(4);
+
+        let kind = match segment.flags & (PF_R | PF_W | PF_X) {
+            PF_X | PF_RX => {
+                // code is verified as it is in the file
+                if segment.filesz != segment.memsz || segment.filesz & 3 != 0 {
+                    return Err(NativeError::Malformed);
+                }
+                self.code_readable |= segment.flags & PF_R != 0;
+                CODE
+            }
+            PF_R => READONLY,
+            PF_RW => WRITABLE,
+            _ => return Err(NativeError::Malformed),
+        };
+        // ranges can't interleave, and nothing may come after the writable one
+        let previous = self.segments[..self.num_segments]
+            .last()
+            .map(|segment| segment.kind);
+        if self.ranges[WRITABLE].is_some() && kind != WRITABLE
+            || self.ranges[kind].is_some() && previous != Some(kind)
+        {
+            return Err(NativeError::Malformed);
+        }
+        self.ranges[kind].get_or_insert(Range { start, end }).end = end;
+        self.size = end;
+        self.segments[self.num_segments] = Segment { kind, ..segment };
+        self.num_segments += 1;
+
+        Ok(())
+    }
+
+    /// Relocation table of the dynamic section, which is in a loaded segment
+    fn parse_dynamic(&self, dynamic: Segment) -> Result<&'a [u8], NativeError> {
+        let entries = self
+            .bytes(dynamic.vaddr, dynamic.filesz)
+            .ok_or(NativeError::Malformed)?;
+        let mut rela = None;
+        let mut rela_size = 0;
+        for entry in entries.chunks_exact(8) {
+            let value = word(entry, 4);
+            match word(entry, 0) {
+                DT_NULL => break,
+                DT_RELA => rela = Some(value),
+                DT_RELASZ => rela_size = value as usize,
+                DT_RELAENT if value as usize != RELA_SIZE => return Err(NativeError::Malformed),
+                DT_PLTRELSZ if value != 0 => return Err(NativeError::Malformed),
+                DT_NEEDED | DT_REL | DT_RELR => return Err(NativeError::Malformed),
+                _ => {}
+            }
+        }
+
+        match rela {
+            Some(address) if rela_size % RELA_SIZE == 0 => {
+                self.bytes(address, rela_size).ok_or(NativeError::Malformed)
+            }
+            Some(_) => Err(NativeError::Malformed),
+            None => Ok(&[]),
+        }
+    }
+
+    /// Relocations may only patch data, the code was verified as it is
+    fn check_relocations(&self) -> Result<(), NativeError> {
+        for relocation in self.relocations.chunks_exact(RELA_SIZE) {
+            let info = word(relocation, 4);
+            let target = word(relocation, 0);
+            let is_data = self.segments().any(|segment| {
+                let offset = target.wrapping_sub(segment.vaddr) as usize;
+                segment.kind != CODE && offset < segment.memsz && segment.memsz - offset >= 4
+            });
+            match info & 0xff {
+                R_RISCV_NONE => {}
+                R_RISCV_RELATIVE if info >> 8 == 0 && is_data => {}
+                _ => return Err(NativeError::Malformed),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// File contents of `[address, address + len)`, that must be within one segment
+    fn bytes(&self, address: u32, len: usize) -> Option<&'a [u8]> {
+        let blob = self.blob;
+        self.segments().find_map(|segment| {
+            let offset = address.wrapping_sub(segment.vaddr) as usize;
+            if offset <= segment.filesz && segment.filesz - offset >= len {
+                blob.get(segment.offset + offset..segment.offset + offset + len)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[inline(always)]
+fn half(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+#[inline(always)]
+fn word(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
@@ -0,0 +1,6 @@
+// Fuel schedule of native contracts, in the kernel resource ticks. Every retired instruction is
+// a tick, including the ones of the kernel switching to and from the contract. Access to the
+// state and hashing are charged by `system::costs`, and calls of the precompiles
+// by `crate::precompiles`
+
+pub const SYSCALL: u64 = 10;
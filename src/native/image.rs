@@ -0,0 +1,89 @@
+use super::NativeError;
+
+// Binary format of native contracts: a header of little-endian u32 words, followed by the code
+// and the read-only data. The image is position-independent and reaches its data pc-relatively,
+// so it's loaded as one block in the order the linker laid it out:
+//
+//   [code | rodata | constants | bss | stack | calldata]
+//
+// Constants are the immutables set by the deployer, placed where the image reserved room for
+// them, and bss is zeroed. All the lengths are multiples of 4, as PMP regions are.
+
+/// "ZKRV"
+pub const NATIVE_MAGIC: u32 = u32::from_le_bytes(*b"ZKRV");
+pub const HEADER_SIZE: usize = 24;
+/// Everything but the stack and calldata
+pub const MAX_IMAGE_SIZE: usize = 1 << 20;
+
+/// Offsets of `[start, end)` in the loaded image
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Range {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// Offset of the entry point in the code
+    pub entry: u32,
+    pub code_len: u32,
+    pub rodata_len: u32,
+    /// Room for the constants, the deployed ones may be shorter and are padded with zeroes
+    pub constants_len: u32,
+    pub bss_len: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct Image<'a> {
+    pub header: Header,
+    pub code: &'a [u8],
+    pub rodata: &'a [u8],
+}
+
+impl<'a> Image<'a> {
+    pub fn parse(blob: &'a [u8]) -> Result<Self, NativeError> {
+        if blob.len() < HEADER_SIZE {
+            return Err(NativeError::Malformed);
+        }
+        let mut words = [0u32; HEADER_SIZE / 4];
+        for (word, bytes) in words.iter_mut().zip(blob.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        let [magic, entry, code_len, rodata_len, constants_len, bss_len] = words;
+        if magic != NATIVE_MAGIC {
+            return Err(NativeError::Malformed);
+        }
+        if (code_len | rodata_len | constants_len | bss_len | entry) & 3 != 0 || entry >= code_len {
+            return Err(NativeError::Malformed);
+        }
+        let size = [code_len, rodata_len, constants_len, bss_len]
+            .iter()
+            .try_fold(0usize, |size, len| size.checked_add(*len as usize));
+        if !matches!(size, Some(size) if size <= MAX_IMAGE_SIZE) {
+            return Err(NativeError::LimitExceeded);
+        }
+        let code_end = HEADER_SIZE + code_len as usize;
+        if code_end + rodata_len as usize != blob.len() {
+            return Err(NativeError::Malformed);
+        }
+
+        Ok(Self {
+            header: Header {
+                entry,
+                code_len,
+                rodata_len,
+                constants_len,
+                bss_len,
+            },
+            code: &blob[HEADER_SIZE..code_end],
+            rodata: &blob[code_end..],
+        })
+    }
+
+    /// Code, rodata, constants and bss
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        let header = &self.header;
+        (header.code_len + header.rodata_len + header.constants_len + header.bss_len) as usize
+    }
+}
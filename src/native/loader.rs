@@ -0,0 +1,223 @@
+use super::elf::Elf;
+use super::image::Image;
+use super::NativeError;
+use crate::context::Context;
+use crate::cpu::Registers;
+use crate::pmp::{
+    PmpConfig, PMP_REGION_CODE, PMP_REGION_CONSTANTS, PMP_REGION_MEMORY, PMP_REGION_STACK,
+};
+use crate::system::interpreter::ExecutionFrame;
+use crate::system::memory::MemoryArena;
+use crate::user_mode::UserExit;
+
+use riscv::register::Permission;
+
+pub const STACK_SIZE: usize = 64 << 10;
+/// Stack pointer at the entry, as the psABI wants it
+const STACK_ALIGNMENT: usize = 16;
+
+/// Native contract loaded into the memory arena, with its own stack and a copy of the calldata.
+/// The code is mapped execute-only, rodata and constants read-only, and the rest read-write
+pub struct Process {
+    memory: &'static mut [u8],
+    /// Maps the parts of `memory` the contract may access
+    pub context: Context,
+}
+
+impl Process {
+    /// Lays out the image, calldata goes on top of the stack. At the entry a0 and a1 are
+    /// the calldata and its length, a2 is set in the constructor, and a3 and a4 are
+    /// the constants and their length
+    pub fn load(
+        image: &Image,
+        frame: &ExecutionFrame,
+        arena: &mut MemoryArena,
+    ) -> Result<Self, NativeError> {
+        let header = &image.header;
+        if frame.constants.len() > header.constants_len as usize {
+            return Err(NativeError::Malformed);
+        }
+        let code_end = header.code_len as usize;
+        let rodata_end = code_end + header.rodata_len as usize;
+        let readonly_end = rodata_end + header.constants_len as usize;
+        let size = image.size() + STACK_SIZE + STACK_ALIGNMENT + frame.calldata.len();
+
+        // arena is zeroed, so is bss
+        let mut region = arena.allocate(size.next_multiple_of(4))?;
+        let memory = region.as_mut_slice();
+        let stack_top = stack_top(memory, image.size());
+        memory[..code_end].copy_from_slice(image.code);
+        memory[code_end..rodata_end].copy_from_slice(image.rodata);
+        memory[rodata_end..rodata_end + frame.constants.len()].copy_from_slice(frame.constants);
+        memory[stack_top..stack_top + frame.calldata.len()].copy_from_slice(frame.calldata);
+
+        let base = memory.as_ptr() as usize;
+        let mut pmp = PmpConfig::EMPTY;
+        pmp.map(PMP_REGION_CODE, base, code_end, Permission::X);
+        pmp.map(
+            PMP_REGION_CONSTANTS,
+            base + code_end,
+            readonly_end - code_end,
+            Permission::R,
+        );
+        pmp.map(
+            PMP_REGION_STACK,
+            base + readonly_end,
+            memory.len() - readonly_end,
+            Permission::RW,
+        );
+
+        Ok(Self::new(
+            memory,
+            pmp,
+            header.entry as usize,
+            stack_top,
+            rodata_end,
+            frame,
+        ))
+    }
+
+    /// Lays out the segments of the executable, followed by the stack, the calldata and
+    /// the constants, that get the region of the linear memory. Registers are the same as
+    /// for the images
+    pub fn load_elf(
+        elf: &Elf,
+        frame: &ExecutionFrame,
+        arena: &mut MemoryArena,
+    ) -> Result<Self, NativeError> {
+        let calldata_len = frame.calldata.len().next_multiple_of(4);
+        let size = elf.size
+            + STACK_SIZE
+            + STACK_ALIGNMENT
+            + calldata_len
+            + frame.constants.len().next_multiple_of(4);
+
+        // the image keeps the alignment it was linked with, up to the one of the stack
+        let mut region = arena.allocate(size + STACK_ALIGNMENT)?;
+        let memory = region.as_mut_slice();
+        let padding =
+            (elf.start as usize).wrapping_sub(memory.as_ptr() as usize) & (STACK_ALIGNMENT - 1);
+        let memory = &mut memory[padding..padding + size];
+        elf.load(memory, memory.as_ptr() as usize as u32);
+        let stack_top = stack_top(memory, elf.size);
+        let constants = stack_top + calldata_len;
+        memory[stack_top..stack_top + frame.calldata.len()].copy_from_slice(frame.calldata);
+        memory[constants..constants + frame.constants.len()].copy_from_slice(frame.constants);
+
+        let code = elf.code();
+        let code_permission = if elf.code_readable {
+            Permission::RX
+        } else {
+            Permission::X
+        };
+        let writable_start = elf.writable().map_or(elf.size, |writable| writable.start);
+        let base = memory.as_ptr() as usize;
+        let mut pmp = PmpConfig::EMPTY;
+        pmp.map(
+            PMP_REGION_CODE,
+            base + code.start,
+            code.end - code.start,
+            code_permission,
+        );
+        if let Some(readonly) = elf.readonly() {
+            pmp.map(
+                PMP_REGION_CONSTANTS,
+                base + readonly.start,
+                readonly.end - readonly.start,
+                Permission::R,
+            );
+        }
+        pmp.map(
+            PMP_REGION_STACK,
+            base + writable_start,
+            constants - writable_start,
+            Permission::RW,
+        );
+        if !frame.constants.is_empty() {
+            pmp.map(
+                PMP_REGION_MEMORY,
+                base + constants,
+                memory.len() - constants,
+                Permission::R,
+            );
+        }
+
+        Ok(Self::new(
+            memory, pmp, elf.entry, stack_top, constants, frame,
+        ))
+    }
+
+    fn new(
+        memory: &'static mut [u8],
+        pmp: PmpConfig,
+        entry: usize,
+        stack_top: usize,
+        constants: usize,
+        frame: &ExecutionFrame,
+    ) -> Self {
+        let base = memory.as_ptr() as usize as u32;
+        let mut context = Context::new(base + entry as u32);
+        context.pmp = pmp;
+        context.set_register(Registers::Sp, base + stack_top as u32);
+        context.set_register(Registers::A0, base + stack_top as u32);
+        context.set_register(Registers::A1, frame.calldata.len() as u32);
+        context.set_register(Registers::A2, frame.is_constructor as u32);
+        context.set_register(Registers::A3, base + constants as u32);
+        context.set_register(Registers::A4, frame.constants.len() as u32);
+
+        Self { memory, context }
+    }
+
+    /// Runs the contract until its next exit or its budget, returns the exit along with
+    /// the instructions retired
+    #[inline(always)]
+    pub fn resume(&mut self, budget: u64) -> (UserExit, u64) {
+        self.context.resume(budget)
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn register(&self, register: Registers) -> u32 {
+        self.context.register(register)
+    }
+
+    /// Data the contract can read at `address`
+    pub fn readable(&self, address: u32, len: usize) -> Result<&[u8], NativeError> {
+        let offset = self.offset(address, len, false)?;
+
+        Ok(&self.memory[offset..offset + len])
+    }
+
+    /// Data the contract can write at `address`
+    pub fn writable(&mut self, address: u32, len: usize) -> Result<&mut [u8], NativeError> {
+        let offset = self.offset(address, len, true)?;
+
+        Ok(&mut self.memory[offset..offset + len])
+    }
+
+    /// Offset of `[address, address + len)` in the process memory, that the PMP config of
+    /// the contract must allow the access to, possibly by adjoining regions
+    fn offset(&self, address: u32, len: usize, write: bool) -> Result<usize, NativeError> {
+        // empty ranges are fine anywhere, e.g. a null pointer to nothing
+        if len == 0 {
+            return Ok(0);
+        }
+        let offset = address.wrapping_sub(self.memory.as_ptr() as usize as u32) as usize;
+        let end = offset
+            .checked_add(len)
+            .ok_or(NativeError::MemoryOutOfBounds)?;
+        let base = self.memory.as_ptr() as usize;
+        if end > self.memory.len() || !self.context.pmp.is_accessible(base + offset, len, write) {
+            return Err(NativeError::MemoryOutOfBounds);
+        }
+
+        Ok(offset)
+    }
+}
+
+/// Offset of the initial stack pointer, for the stack that goes after the first `size` bytes
+fn stack_top(memory: &[u8], size: usize) -> usize {
+    let base = memory.as_ptr() as usize;
+
+    (base + size + STACK_SIZE).next_multiple_of(STACK_ALIGNMENT) - base
+}
@@ -0,0 +1,414 @@
+pub mod elf;
+pub mod fuel;
+pub mod image;
+pub mod loader;
+pub mod verifier;
+
+use self::elf::Elf;
+use self::image::{Image, Range};
+use self::loader::Process;
+use crate::bigint::U256;
+use crate::cpu::Registers;
+use crate::crypto::keccak256;
+use crate::system::account::CodeType;
+use crate::system::call::CallRequest;
+use crate::system::costs;
+use crate::system::deployment::{DeploymentRequest, DeploymentScheme};
+use crate::system::interpreter::{ExecutionFrame, ExecutionStatus, Interpreter};
+use crate::system::resources::Resources;
+use crate::system::types::{Address, Bytes32};
+use crate::system::{System, SystemError};
+use crate::user_mode::UserExit;
+
+// Native RISC-V contracts, either in the image format or as static-pie ELF32 executables. The code
+// is verified at deployment and runs in U-mode, isolated by PMP. Contract gets to the kernel with
+// an `ecall` that has `EXIT_CALL_BIT` in a7, the rest of a7 is the number of the syscall, a0..a5
+// are its arguments and a0 (a1 for the high half) is its result. Any other exception fails
+// the frame. Retired instructions are charged at every exit, and the machine timer, armed at
+// the fuel left, preempts a contract that would spend it all without exiting
+
+/// Ends the execution with the output at a0 of length a1
+pub const SYSCALL_RETURN: u32 = 0;
+pub const SYSCALL_REVERT: u32 = 1;
+/// Reads the slot with the key at a0 into a1
+pub const SYSCALL_STORAGE_READ: u32 = 2;
+/// Writes the value at a1 into the slot with the key at a0
+pub const SYSCALL_STORAGE_WRITE: u32 = 3;
+/// Writes the 32-byte caller address to a0
+pub const SYSCALL_CALLER: u32 = 4;
+pub const SYSCALL_ADDRESS: u32 = 5;
+/// Writes the big-endian call value to a0
+pub const SYSCALL_CALL_VALUE: u32 = 6;
+pub const SYSCALL_FUEL_LEFT: u32 = 7;
+/// Copies a2 bytes of the constants from offset a1 to a0, zeroes past their end. The constants
+/// are also mapped read-only, at a3 on entry
+pub const SYSCALL_CONSTANTS_READ: u32 = 8;
+/// Writes the constants hash of the account with the address at a0 to a1
+pub const SYSCALL_CONSTANTS_HASH: u32 = 9;
+/// Calls the address at a0 with the big-endian value at a1 and the calldata at a2 of length a3,
+/// giving it at most the fuel in a4 (a5 for the high half). The caller is suspended until
+/// the callee returns, a0 is then 0 on success, 1 on revert and 2 on failure. Precompiles are
+/// called this way at their addresses, as from the EVM
+pub const SYSCALL_CALL: u32 = 10;
+pub const SYSCALL_RETURNDATA_SIZE: u32 = 11;
+/// Copies a2 bytes of the returndata from offset a1 to a0
+pub const SYSCALL_RETURNDATA_COPY: u32 = 12;
+/// Deploys a contract from the request at a0, nine words: the code type, the code and its
+/// length, the constants and their length, the constructor calldata and its length, the
+/// big-endian value, and the salt (null for an address from the nonce). The constructor gets at
+/// most the fuel in a1 (a2 for the high half). The address of the contract goes to a3, zeroes
+/// unless it was deployed, and a0 is the status code as for `SYSCALL_CALL`
+pub const SYSCALL_DEPLOY: u32 = 13;
+/// Writes the Keccak-256 of a1 bytes at a0 to a2
+pub const SYSCALL_KECCAK256: u32 = 14;
+
+/// Deployment-time errors (malformed image, forbidden code) and runtime faults. All of them,
+/// except `System`, consume the resources of the frame and fail it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NativeError {
+    Malformed,
+    LimitExceeded,
+    InvalidInstruction,
+    ForbiddenInstruction,
+    /// Static jump target is not an instruction of the code
+    InvalidJump,
+    /// Exception other than a syscall
+    Fault,
+    InvalidSyscall,
+    MemoryOutOfBounds,
+    ReturnDataOutOfBounds,
+    OutOfMemory,
+    OutOfFuel,
+    StaticStateChange,
+    System(SystemError),
+}
+
+impl From<SystemError> for NativeError {
+    fn from(error: SystemError) -> Self {
+        match error {
+            SystemError::OutOfResources => Self::OutOfFuel,
+            SystemError::OutOfMemory => Self::OutOfMemory,
+            error => Self::System(error),
+        }
+    }
+}
+
+pub const INTERPRETER: Interpreter = Interpreter {
+    validate,
+    preprocess: None,
+    execute,
+    constructor_returns_code: false,
+};
+
+fn validate(code: &[u8]) -> bool {
+    if elf::is_elf(code) {
+        return Elf::parse(code).and_then(|elf| elf.verify()).is_ok();
+    }
+
+    Image::parse(code)
+        .and_then(|image| {
+            let code = Range {
+                start: 0,
+                end: image.code.len(),
+            };
+            verifier::verify(image.code, 0, code)
+        })
+        .is_ok()
+}
+
+fn execute(
+    system: &mut System,
+    frame: &ExecutionFrame,
+    resources: &mut Resources,
+) -> Result<ExecutionStatus, SystemError> {
+    // everything the frame allocates is released at once by resetting the arena to this point
+    let frame_start = system.memory.allocate(0)?;
+    let result = execute_in_arena(system, frame, resources);
+    system.memory.release(frame_start);
+
+    match result {
+        Ok(status) => Ok(status),
+        Err(NativeError::System(error)) => Err(error),
+        Err(_) => {
+            resources.burn();
+            system.returndata.clear();
+            Ok(ExecutionStatus::Failure)
+        }
+    }
+}
+
+fn execute_in_arena(
+    system: &mut System,
+    frame: &ExecutionFrame,
+    resources: &mut Resources,
+) -> Result<ExecutionStatus, NativeError> {
+    // code was verified on deployment
+    let mut process = if elf::is_elf(frame.code) {
+        Process::load_elf(&Elf::parse(frame.code)?, frame, &mut system.memory)?
+    } else {
+        Process::load(&Image::parse(frame.code)?, frame, &mut system.memory)?
+    };
+
+    loop {
+        let (exit, retired) = process.resume(resources.remaining());
+        resources.charge(retired)?;
+        let number = match exit {
+            UserExit::Call(number) => number,
+            // the deadline came before the fuel ran out, e.g. with the syscalls in between
+            UserExit::Preempted => continue,
+            UserExit::Exception { .. } => return Err(NativeError::Fault),
+        };
+        resources.charge(fuel::SYSCALL)?;
+        if let Some(status) = syscall(system, frame, resources, &mut process, number)? {
+            return Ok(status);
+        }
+    }
+}
+
+/// Handles the syscall, returns `Some` if it finished the execution
+fn syscall(
+    system: &mut System,
+    frame: &ExecutionFrame,
+    resources: &mut Resources,
+    process: &mut Process,
+    number: u32,
+) -> Result<Option<ExecutionStatus>, NativeError> {
+    let a0 = process.register(Registers::A0);
+    let a1 = process.register(Registers::A1);
+    match number {
+        SYSCALL_RETURN | SYSCALL_REVERT => {
+            system.returndata.set(process.readable(a0, a1 as usize)?)?;
+            return Ok(Some(if number == SYSCALL_RETURN {
+                ExecutionStatus::Success
+            } else {
+                ExecutionStatus::Revert
+            }));
+        }
+        SYSCALL_STORAGE_READ => {
+            let key = read_word(process, a0)?;
+            let access = system.storage.read(&frame.address, &key)?;
+            resources.charge(if access.was_warm {
+                costs::WARM_ACCESS
+            } else {
+                costs::COLD_SLOAD
+            })?;
+            process.writable(a1, 32)?.copy_from_slice(&access.value.0);
+        }
+        SYSCALL_STORAGE_WRITE => {
+            if frame.is_static {
+                return Err(NativeError::StaticStateChange);
+            }
+            let key = read_word(process, a0)?;
+            let value = read_word(process, a1)?;
+            if resources.remaining() <= costs::STORAGE_STIPEND {
+                return Err(NativeError::OutOfFuel);
+            }
+            let access = system.storage.write(&frame.address, &key, &value)?;
+            let (cost, refund) = costs::storage_write_cost(&access.original, &access.value, &value);
+            let cold_cost = if access.was_warm {
+                0
+            } else {
+                costs::COLD_SLOAD
+            };
+            resources.charge(cost + cold_cost)?;
+            system.refund += refund;
+        }
+        SYSCALL_CALLER => process.writable(a0, 32)?.copy_from_slice(&frame.caller.0),
+        SYSCALL_ADDRESS => process.writable(a0, 32)?.copy_from_slice(&frame.address.0),
+        SYSCALL_CALL_VALUE => process
+            .writable(a0, 32)?
+            .copy_from_slice(&frame.value.to_be_bytes()),
+        SYSCALL_FUEL_LEFT => {
+            let fuel = resources.remaining();
+            process.context.set_register(Registers::A0, fuel as u32);
+            process
+                .context
+                .set_register(Registers::A1, (fuel >> 32) as u32);
+        }
+        SYSCALL_CONSTANTS_READ => {
+            let len = process.register(Registers::A2) as usize;
+            resources.charge(costs::copy_cost(len))?;
+            let constants = frame.constants;
+            let source = constants
+                .get((a1 as usize).min(constants.len())..)
+                .unwrap_or(&[]);
+            let to_copy = len.min(source.len());
+            let destination = process.writable(a0, len)?;
+            destination[..to_copy].copy_from_slice(&source[..to_copy]);
+            destination[to_copy..].fill(0);
+        }
+        SYSCALL_CONSTANTS_HASH => {
+            let address = Address(read_word(process, a0)?.0);
+            let was_warm = system.accounts.touch(&address)?;
+            resources.charge(if was_warm {
+                costs::WARM_ACCESS
+            } else {
+                costs::COLD_ACCOUNT_ACCESS
+            })?;
+            let hash = system.constants_hash(&address)?;
+            process.writable(a1, 32)?.copy_from_slice(&hash.0);
+        }
+        SYSCALL_CALL => {
+            let status = call(system, frame, resources, process)?;
+            process.context.set_register(Registers::A0, status);
+        }
+        SYSCALL_RETURNDATA_SIZE => {
+            let len = system.returndata.as_slice().len();
+            process.context.set_register(Registers::A0, len as u32);
+        }
+        SYSCALL_RETURNDATA_COPY => {
+            let len = process.register(Registers::A2) as usize;
+            resources.charge(costs::copy_cost(len))?;
+            let offset = a1 as usize;
+            let source = system
+                .returndata
+                .as_slice()
+                .get(offset..offset.saturating_add(len))
+                .ok_or(NativeError::ReturnDataOutOfBounds)?;
+            process.writable(a0, len)?.copy_from_slice(source);
+        }
+        SYSCALL_DEPLOY => {
+            let (status, address) = deploy(system, frame, resources, process)?;
+            process
+                .writable(process.register(Registers::A3), 32)?
+                .copy_from_slice(&address.0);
+            process.context.set_register(Registers::A0, status);
+        }
+        SYSCALL_KECCAK256 => {
+            resources.charge(costs::keccak256_cost(a1 as usize))?;
+            let hash = keccak256(process.readable(a0, a1 as usize)?);
+            process
+                .writable(process.register(Registers::A2), 32)?
+                .copy_from_slice(&hash);
+        }
+        _ => return Err(NativeError::InvalidSyscall),
+    }
+
+    Ok(None)
+}
+
+/// Runs the callee of `SYSCALL_CALL` in its own context, returns its status code
+fn call(
+    system: &mut System,
+    frame: &ExecutionFrame,
+    resources: &mut Resources,
+    process: &mut Process,
+) -> Result<u32, NativeError> {
+    let address = Address(read_word(process, process.register(Registers::A0))?.0);
+    let value = U256::from_be_bytes(&read_word(process, process.register(Registers::A1))?.0);
+    let calldata = process.readable(
+        process.register(Registers::A2),
+        process.register(Registers::A3) as usize,
+    )?;
+    let requested =
+        process.register(Registers::A4) as u64 | (process.register(Registers::A5) as u64) << 32;
+    if !value.is_zero() && frame.is_static {
+        return Err(NativeError::StaticStateChange);
+    }
+
+    let was_warm = system.accounts.touch(&address)?;
+    resources.charge(if was_warm {
+        costs::WARM_ACCESS
+    } else {
+        costs::COLD_ACCOUNT_ACCESS
+    })?;
+    if !value.is_zero() {
+        resources.charge(costs::CALL_VALUE)?;
+    }
+
+    system.returndata.clear();
+    if system.accounts.get(&frame.address)?.balance < value {
+        return Ok(2);
+    }
+
+    // the context of the caller stays in `process`, and is switched back to on its next resume
+    let available = costs::all_but_one_64th(resources.remaining());
+    let mut callee_resources = resources.take(requested.min(available));
+    let request = CallRequest {
+        is_static: frame.is_static,
+        ..CallRequest::new(frame.address, address, value, calldata)
+    };
+    let status = system.call(&request, &mut callee_resources)?;
+    resources.reclaim(callee_resources);
+
+    Ok(match status {
+        ExecutionStatus::Success => 0,
+        ExecutionStatus::Revert => 1,
+        ExecutionStatus::Failure => 2,
+    })
+}
+
+/// Runs the constructor of `SYSCALL_DEPLOY` in its own context, returns its status code and
+/// the address of the contract
+fn deploy(
+    system: &mut System,
+    frame: &ExecutionFrame,
+    resources: &mut Resources,
+    process: &mut Process,
+) -> Result<(u32, Address), NativeError> {
+    if frame.is_static {
+        return Err(NativeError::StaticStateChange);
+    }
+    let mut words = [0u32; 9];
+    let fields = process.readable(process.register(Registers::A0), words.len() * 4)?;
+    for (word, bytes) in words.iter_mut().zip(fields.chunks_exact(4)) {
+        *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    let [code_type, code, code_len, constants, constants_len, calldata, calldata_len, value, salt] =
+        words;
+    let code_type = match CodeType::from_u32(code_type) {
+        Some(CodeType::Empty) | None => return Err(NativeError::InvalidSyscall),
+        Some(code_type) => code_type,
+    };
+    let code = process.readable(code, code_len as usize)?;
+    let constants = process.readable(constants, constants_len as usize)?;
+    let value = U256::from_be_bytes(&read_word(process, value)?.0);
+    let scheme = if salt == 0 {
+        DeploymentScheme::Create
+    } else {
+        DeploymentScheme::Create2 {
+            salt: read_word(process, salt)?,
+        }
+    };
+    let requested =
+        process.register(Registers::A1) as u64 | (process.register(Registers::A2) as u64) << 32;
+    resources.charge(costs::deployment_cost(
+        code.len(),
+        constants.len(),
+        salt != 0,
+    ))?;
+
+    let request = DeploymentRequest {
+        deployer: frame.address,
+        code_type,
+        code,
+        constants,
+        constructor_calldata: process.readable(calldata, calldata_len as usize)?,
+        value,
+        scheme,
+    };
+    let available = costs::all_but_one_64th(resources.remaining());
+    let mut constructor_resources = resources.take(requested.min(available));
+    let result = match system.deploy(&request, &mut constructor_resources) {
+        Ok(result) => result,
+        // the fuel given to the constructor is lost, as with a failed constructor
+        Err(SystemError::InvalidCode | SystemError::AddressCollision) => {
+            return Ok((2, Address::ZERO));
+        }
+        Err(error) => return Err(error.into()),
+    };
+    resources.reclaim(constructor_resources);
+
+    Ok(match result.status {
+        ExecutionStatus::Success => (0, result.address),
+        ExecutionStatus::Revert => (1, Address::ZERO),
+        ExecutionStatus::Failure => (2, Address::ZERO),
+    })
+}
+
+fn read_word(process: &Process, address: u32) -> Result<Bytes32, NativeError> {
+    let mut word = [0u8; 32];
+    word.copy_from_slice(process.readable(address, 32)?);
+
+    Ok(Bytes32(word))
+}
@@ -0,0 +1,49 @@
+use super::image::Range;
+use super::NativeError;
+use crate::cpu::{Instruction, Registers};
+
+// Native code runs in U-mode, where privileged instructions trap anyway. Still, `wfi` may be
+// executed in U-mode and stall the hart, and CSR reads give away the counters, so the code is
+// decoded once at deployment, and anything that is not RV32IM or touches the privileged state
+// is rejected then rather than failing at runtime. Jumps with a static target must land on
+// an instruction of the code: branches, `jal`, `jalr` on the result of the `auipc` right
+// before it, and `jalr` off the zero register, that never lands in the contract
+
+/// Checks every instruction word of `code`, that starts at `offset` of the image. Static jumps
+/// may go anywhere in `bounds`
+pub fn verify(code: &[u8], offset: usize, bounds: Range) -> Result<(), NativeError> {
+    let mut previous = None;
+    for (index, bytes) in code.chunks_exact(4).enumerate() {
+        let word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let instruction = Instruction::decode(word).ok_or(NativeError::InvalidInstruction)?;
+        if instruction.is_privileged() {
+            return Err(NativeError::ForbiddenInstruction);
+        }
+
+        let pc = (offset + index * 4) as i64;
+        let target = match instruction {
+            Instruction::Jal { offset, .. } | Instruction::Branch { offset, .. } => {
+                Some(pc + offset as i64)
+            }
+            Instruction::Jalr { rs1, .. } if rs1 == Registers::Zero as u8 => {
+                return Err(NativeError::InvalidJump);
+            }
+            // `jalr` clears the lowest bit of the target
+            Instruction::Jalr { rs1, offset, .. } => match previous {
+                Some(Instruction::Auipc { rd, imm }) if rd == rs1 => {
+                    Some((pc - 4 + imm as i32 as i64 + offset as i64) & !1)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(target) = target {
+            if target & 3 != 0 || target < bounds.start as i64 || target >= bounds.end as i64 {
+                return Err(NativeError::InvalidJump);
+            }
+        }
+        previous = Some(instruction);
+    }
+
+    Ok(())
+}
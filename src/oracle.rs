@@ -0,0 +1,80 @@
+use crate::quasi_uart::{QuasiUART, QUASI_UART_ADDRESS};
+
+// Oracle is a source of non-deterministic data, accessed through the same quasi-UART
+// as console output: we write a query header and parameters word by word, and then read
+// exactly as many words as the query expects back. Any data obtained this way is NOT trusted
+// by itself and must be checked by the caller against some commitment if needed
+
+pub const ORACLE_ADDRESS: u32 = QUASI_UART_ADDRESS;
+
+// marker that can not be a first word of printable output (that is always NUL terminated bytes),
+// so simulator can distinguish queries from the console output
+pub const ORACLE_QUERY_MARKER: u32 = 0xffff_ffff;
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OracleQuery {
+    AccountData = 1,
+    CodeByHash = 2,
+    ConstantsByHash = 3,
+    ArtifactByCodeHash = 4,
+    StorageSlot = 5,
+    BlockContext = 6,
+    BlockHash = 7,
+    /// RLP of the signed transaction at the given index in the block, empty past the last one
+    Transaction = 8,
+}
+
+pub struct Oracle {
+    uart: QuasiUART,
+}
+
+impl Oracle {
+    #[inline(never)]
+    pub fn new() -> Self {
+        Self {
+            uart: QuasiUART::new(ORACLE_ADDRESS),
+        }
+    }
+
+    #[inline(never)]
+    pub fn query(&self, query: OracleQuery, params: &[u32], response: &mut [u32]) {
+        self.uart.write_word(ORACLE_QUERY_MARKER);
+        self.uart.write_word(query as u32);
+        self.uart.write_word(params.len() as u32);
+        for word in params.iter() {
+            self.uart.write_word(*word);
+        }
+        for dst in response.iter_mut() {
+            *dst = self.uart.read_word();
+        }
+    }
+
+    /// Query with variable length response, that is prefixed by the length in bytes.
+    /// Returns `None` if response doesn't fit into `buffer`, it is still read to the end,
+    /// so that the next query gets its own response
+    #[inline(never)]
+    pub fn query_bytes(
+        &self,
+        query: OracleQuery,
+        params: &[u32],
+        buffer: &mut [u8],
+    ) -> Option<usize> {
+        let mut len = [0u32; 1];
+        self.query(query, params, &mut len);
+        let len = len[0] as usize;
+        if len > buffer.len() {
+            for _ in 0..len.div_ceil(4) {
+                self.uart.read_word();
+            }
+            return None;
+        }
+
+        for chunk in buffer[..len].chunks_mut(4) {
+            let word = self.uart.read_word().to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+
+        Some(len)
+    }
+}
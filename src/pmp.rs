@@ -0,0 +1,186 @@
+#[cfg(target_arch = "riscv32")]
+use riscv::register::pmpcfg1;
+use riscv::register::{
+    pmpaddr0, pmpaddr1, pmpaddr2, pmpaddr3, pmpaddr4, pmpaddr5, pmpaddr6, pmpaddr7, pmpcfg0,
+    Permission, Range,
+};
+
+// Physical memory protection is the only isolation we have, as there is no translation.
+// Entries are used in TOR pairs, so every region takes two consecutive PMP slots
+
+pub const PMP_REGION_CONSTANTS: usize = 0;
+pub const PMP_REGION_CODE: usize = 1;
+pub const PMP_REGION_STACK: usize = 2;
+pub const PMP_REGION_MEMORY: usize = 3;
+pub const NUM_REGIONS: usize = 4;
+
+#[derive(Clone, Copy)]
+struct Mapping {
+    start: usize,
+    end: usize,
+    permission: Permission,
+}
+
+/// What all the regions map. Every execution context keeps its own, and gets it programmed
+/// whenever it runs
+#[derive(Clone, Copy)]
+pub struct PmpConfig {
+    mappings: [Option<Mapping>; NUM_REGIONS],
+}
+
+impl PmpConfig {
+    pub const EMPTY: Self = Self {
+        mappings: [None; NUM_REGIONS],
+    };
+
+    /// Maps `[start, start + len)` with the given permission, once the config is applied
+    pub fn map(&mut self, region: usize, start: usize, len: usize, permission: Permission) {
+        if let Some(mapping) = self.mappings.get_mut(region) {
+            *mapping = Some(Mapping {
+                start,
+                end: (start + len + 3) & !3,
+                permission,
+            });
+        }
+    }
+
+    /// Whether U-mode can access all of `[start, start + len)` under this config. Regions may
+    /// adjoin, e.g. the read-only data and the stack of native contracts, and an access may span
+    /// both of them
+    #[must_use]
+    pub fn is_accessible(&self, start: usize, len: usize, write: bool) -> bool {
+        let Some(end) = start.checked_add(len) else {
+            return false;
+        };
+        let mut covered = start;
+        while covered < end {
+            let mapping = self.mappings.iter().flatten().find(|mapping| {
+                let allowed = match mapping.permission {
+                    Permission::R | Permission::RX => !write,
+                    Permission::RW | Permission::RWX => true,
+                    Permission::W | Permission::WX => write,
+                    Permission::NONE | Permission::X => false,
+                };
+                allowed && mapping.start <= covered && covered < mapping.end
+            });
+            match mapping {
+                Some(mapping) => covered = mapping.end,
+                None => return false,
+            }
+        }
+
+        true
+    }
+}
+
+/// Copy of what is programmed into the PMP, so that the kernel can check user addresses
+/// without reading the CSRs back
+static mut CURRENT: PmpConfig = PmpConfig::EMPTY;
+
+/// Makes `[start, start + len)` accessible from U-mode with the given permission
+///
+/// # Safety
+///
+/// Region must not overlap with kernel memory, otherwise contract can read or modify it
+pub unsafe fn map_region(region: usize, start: usize, len: usize, permission: Permission) {
+    let end = start + len;
+    match region {
+        PMP_REGION_CONSTANTS => {
+            pmpaddr0::write(start >> 2);
+            pmpaddr1::write((end + 3) >> 2);
+        }
+        PMP_REGION_CODE => {
+            pmpaddr2::write(start >> 2);
+            pmpaddr3::write((end + 3) >> 2);
+        }
+        PMP_REGION_STACK => {
+            pmpaddr4::write(start >> 2);
+            pmpaddr5::write((end + 3) >> 2);
+        }
+        PMP_REGION_MEMORY => {
+            pmpaddr6::write(start >> 2);
+            pmpaddr7::write((end + 3) >> 2);
+        }
+        _ => return,
+    }
+
+    let top_index = 2 * region + 1;
+    clear_entry(top_index - 1);
+    set_entry(top_index, permission);
+    CURRENT.mappings[region] = Some(Mapping {
+        start,
+        end: (end + 3) & !3,
+        permission,
+    });
+}
+
+pub unsafe fn unmap_region(region: usize) {
+    if region >= NUM_REGIONS {
+        return;
+    }
+    clear_entry(2 * region + 1);
+    CURRENT.mappings[region] = None;
+}
+
+// pmpcfg0 holds the first two pairs on RV32, pmpcfg1 the next two. RV64 has all of them
+// in pmpcfg0
+
+#[cfg(target_arch = "riscv32")]
+unsafe fn set_entry(index: usize, permission: Permission) {
+    if index < 4 {
+        pmpcfg0::set_pmp(index, Range::TOR, permission, false);
+    } else {
+        pmpcfg1::set_pmp(index - 4, Range::TOR, permission, false);
+    }
+}
+
+#[cfg(target_arch = "riscv32")]
+unsafe fn clear_entry(index: usize) {
+    if index < 4 {
+        pmpcfg0::clear_pmp(index);
+    } else {
+        pmpcfg1::clear_pmp(index - 4);
+    }
+}
+
+#[cfg(target_arch = "riscv64")]
+unsafe fn set_entry(index: usize, permission: Permission) {
+    pmpcfg0::set_pmp(index, Range::TOR, permission, false);
+}
+
+#[cfg(target_arch = "riscv64")]
+unsafe fn clear_entry(index: usize) {
+    pmpcfg0::clear_pmp(index);
+}
+
+/// Config as it is programmed now
+#[must_use]
+pub fn current() -> PmpConfig {
+    unsafe { CURRENT }
+}
+
+/// Programs every region of the config, the ones it doesn't map are made inaccessible
+///
+/// # Safety
+///
+/// Same as for `map_region`, for every mapping of the config
+pub unsafe fn apply(config: &PmpConfig) {
+    for (region, mapping) in config.mappings.iter().enumerate() {
+        match mapping {
+            Some(mapping) => map_region(
+                region,
+                mapping.start,
+                mapping.end - mapping.start,
+                mapping.permission,
+            ),
+            None => unmap_region(region),
+        }
+    }
+}
+
+/// Whether U-mode can access all of `[start, start + len)`, used when the kernel performs
+/// an access on behalf of the contract, e.g. emulates a misaligned load
+#[must_use]
+pub fn is_accessible(start: usize, len: usize, write: bool) -> bool {
+    current().is_accessible(start, len, write)
+}
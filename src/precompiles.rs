@@ -0,0 +1,211 @@
+use crate::bigint::modexp;
+use crate::crypto::bn254::{self, ecadd, ecmul, ecpairing};
+use crate::crypto::{blake2, blake2f, ecrecover, ripemd160, sha256};
+use crate::system::precompiles::{Precompile, PrecompileRegistry};
+use crate::system::types::Address;
+use crate::system::{System, SystemError};
+
+// Ethereum precompiles 0x01..=0x09 with their EVM semantics, whoever calls them: inputs that are
+// too short are padded with zeroes and extra bytes are ignored, except for the ones with
+// variable length input, and costs are in gas, that is a tick each
+
+pub const ECRECOVER: u64 = 3000;
+pub const SHA256: u64 = 60;
+pub const SHA256_WORD: u64 = 12;
+pub const RIPEMD160: u64 = 600;
+pub const RIPEMD160_WORD: u64 = 120;
+pub const IDENTITY: u64 = 15;
+pub const IDENTITY_WORD: u64 = 3;
+pub const BN254_ADD: u64 = 150;
+pub const BN254_MUL: u64 = 6000;
+pub const BN254_PAIRING: u64 = 45000;
+pub const BN254_PAIRING_PAIR: u64 = 34000;
+pub const BLAKE2F_ROUND: u64 = 1;
+
+/// Precompiles at the addresses from 0x01 on
+pub const ETHEREUM: [Precompile; 9] = [
+    Precompile {
+        cost: ecrecover_cost,
+        execute: execute_ecrecover,
+    },
+    Precompile {
+        cost: sha256_cost,
+        execute: execute_sha256,
+    },
+    Precompile {
+        cost: ripemd160_cost,
+        execute: execute_ripemd160,
+    },
+    Precompile {
+        cost: identity_cost,
+        execute: execute_identity,
+    },
+    Precompile {
+        cost: modexp::cost,
+        execute: execute_modexp,
+    },
+    Precompile {
+        cost: bn254_add_cost,
+        execute: execute_bn254_add,
+    },
+    Precompile {
+        cost: bn254_mul_cost,
+        execute: execute_bn254_mul,
+    },
+    Precompile {
+        cost: bn254_pairing_cost,
+        execute: execute_bn254_pairing,
+    },
+    Precompile {
+        cost: blake2f_cost,
+        execute: execute_blake2f,
+    },
+];
+
+pub fn register(registry: &mut PrecompileRegistry) {
+    for (index, precompile) in ETHEREUM.into_iter().enumerate() {
+        registry.register(index + 1, precompile);
+    }
+}
+
+/// First `N` bytes of the input, padded with zeroes
+fn padded<const N: usize>(input: &[u8]) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    let len = core::cmp::min(input.len(), N);
+    bytes[..len].copy_from_slice(&input[..len]);
+
+    bytes
+}
+
+const fn word_cost(base: u64, per_word: u64, len: usize) -> u64 {
+    base + per_word * (len as u64).div_ceil(32)
+}
+
+fn ecrecover_cost(_: &[u8]) -> u64 {
+    ECRECOVER
+}
+
+/// Invalid signatures give empty output, the call still succeeds
+fn execute_ecrecover(system: &mut System, input: &[u8]) -> Result<bool, SystemError> {
+    match ecrecover(&padded(input)) {
+        Some(address) => system
+            .returndata
+            .set(&Address::from_evm_address(&address).0)?,
+        None => system.returndata.clear(),
+    }
+
+    Ok(true)
+}
+
+fn sha256_cost(input: &[u8]) -> u64 {
+    word_cost(SHA256, SHA256_WORD, input.len())
+}
+
+fn execute_sha256(system: &mut System, input: &[u8]) -> Result<bool, SystemError> {
+    system.returndata.set(&sha256(input))?;
+
+    Ok(true)
+}
+
+fn ripemd160_cost(input: &[u8]) -> u64 {
+    word_cost(RIPEMD160, RIPEMD160_WORD, input.len())
+}
+
+/// Hash is left-padded to a word
+fn execute_ripemd160(system: &mut System, input: &[u8]) -> Result<bool, SystemError> {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(&ripemd160(input));
+    system.returndata.set(&word)?;
+
+    Ok(true)
+}
+
+fn identity_cost(input: &[u8]) -> u64 {
+    word_cost(IDENTITY, IDENTITY_WORD, input.len())
+}
+
+fn execute_identity(system: &mut System, input: &[u8]) -> Result<bool, SystemError> {
+    system.returndata.set(input)?;
+
+    Ok(true)
+}
+
+/// Scratch and output come from the arena, the dispatcher releases them. If they don't fit,
+/// the call fails as out of resources
+fn execute_modexp(system: &mut System, input: &[u8]) -> Result<bool, SystemError> {
+    let Some(len) = modexp::output_len(input) else {
+        return Ok(false);
+    };
+    let scratch_len = modexp::scratch_len(len).ok_or(SystemError::OutOfMemory)?;
+    let (_, scratch) = system.memory.allocate_array(scratch_len, 0u32)?;
+    let (_, output) = system.memory.allocate_array(len, 0u8)?;
+    if !modexp::modexp(input, output, scratch) {
+        return Ok(false);
+    }
+    system.returndata.set(output)?;
+
+    Ok(true)
+}
+
+fn bn254_add_cost(_: &[u8]) -> u64 {
+    BN254_ADD
+}
+
+fn execute_bn254_add(system: &mut System, input: &[u8]) -> Result<bool, SystemError> {
+    let Some(sum) = ecadd(&padded(input)) else {
+        return Ok(false);
+    };
+    system.returndata.set(&sum)?;
+
+    Ok(true)
+}
+
+fn bn254_mul_cost(_: &[u8]) -> u64 {
+    BN254_MUL
+}
+
+fn execute_bn254_mul(system: &mut System, input: &[u8]) -> Result<bool, SystemError> {
+    let Some(product) = ecmul(&padded(input)) else {
+        return Ok(false);
+    };
+    system.returndata.set(&product)?;
+
+    Ok(true)
+}
+
+/// Input is not padded, it has to be a whole number of pairs
+fn bn254_pairing_cost(input: &[u8]) -> u64 {
+    BN254_PAIRING + BN254_PAIRING_PAIR * (input.len() / bn254::PAIR_ENCODED_LEN) as u64
+}
+
+/// Output is a word, 1 if the product of the pairings is one
+fn execute_bn254_pairing(system: &mut System, input: &[u8]) -> Result<bool, SystemError> {
+    let Some(is_one) = ecpairing(input) else {
+        return Ok(false);
+    };
+    let mut word = [0u8; 32];
+    word[31] = is_one as u8;
+    system.returndata.set(&word)?;
+
+    Ok(true)
+}
+
+/// Input of any other length than the exact one is invalid, and costs nothing before that
+fn blake2f_cost(input: &[u8]) -> u64 {
+    match <&[u8; blake2::INPUT_LEN]>::try_from(input) {
+        Ok(input) => BLAKE2F_ROUND * blake2::rounds(input) as u64,
+        Err(_) => 0,
+    }
+}
+
+fn execute_blake2f(system: &mut System, input: &[u8]) -> Result<bool, SystemError> {
+    let Ok(input) = <&[u8; blake2::INPUT_LEN]>::try_from(input) else {
+        return Ok(false);
+    };
+    let Some(state) = blake2f(input) else {
+        return Ok(false);
+    };
+    system.returndata.set(&state)?;
+
+    Ok(true)
+}
@@ -22,6 +22,12 @@ impl QuasiUART {
         unsafe { dst.write_volatile(word) };
     }
 
+    #[inline(never)]
+    pub fn read_word(&self) -> u32 {
+        let src = core::ptr::from_exposed_addr::<u32>(self.address as usize);
+        unsafe { src.read_volatile() }
+    }
+
     #[inline(never)]
     pub fn write_byte(&mut self, byte: u8) {
         self.buffer[self.len] = byte;
@@ -0,0 +1,319 @@
+use super::types::{Address, Bytes32};
+use super::SystemError;
+use crate::bigint::U256;
+use crate::oracle::{Oracle, OracleQuery};
+
+pub const MAX_CACHED_ACCOUNTS: usize = 256;
+pub const MAX_ACCOUNT_JOURNAL_LENGTH: usize = 1024;
+
+// balance (8 words), nonce (2 words), code hash (8 words), code type (1 word), constants hash (8 words),
+// preprocessing artifact hash (8 words)
+pub const ACCOUNT_DATA_WORDS: usize = 35;
+
+/// Kind of interpreter that should execute the account's code
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodeType {
+    Empty = 0,
+    Native = 1,
+    Evm = 2,
+    Wasm = 3,
+}
+
+impl CodeType {
+    #[must_use]
+    pub const fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Empty),
+            1 => Some(Self::Native),
+            2 => Some(Self::Evm),
+            3 => Some(Self::Wasm),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Account {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code_hash: Bytes32,
+    pub code_type: CodeType,
+    pub constants_hash: Bytes32,
+    pub artifact_hash: Bytes32,
+}
+
+impl Account {
+    pub const EMPTY: Self = Self {
+        balance: U256::ZERO,
+        nonce: 0,
+        code_hash: Bytes32::ZERO,
+        code_type: CodeType::Empty,
+        constants_hash: Bytes32::ZERO,
+        artifact_hash: Bytes32::ZERO,
+    };
+
+    #[must_use]
+    pub fn from_oracle_response(words: &[u32; ACCOUNT_DATA_WORDS]) -> Option<Self> {
+        let mut balance = U256::ZERO;
+        balance.0.copy_from_slice(&words[0..8]);
+        let nonce = (words[8] as u64) | ((words[9] as u64) << 32);
+        let mut code_hash = [0u32; 8];
+        code_hash.copy_from_slice(&words[10..18]);
+        let code_type = CodeType::from_u32(words[18])?;
+        let mut constants_hash = [0u32; 8];
+        constants_hash.copy_from_slice(&words[19..27]);
+        let mut artifact_hash = [0u32; 8];
+        artifact_hash.copy_from_slice(&words[27..35]);
+
+        Some(Self {
+            balance,
+            nonce,
+            code_hash: Bytes32::from_words(&code_hash),
+            code_type,
+            constants_hash: Bytes32::from_words(&constants_hash),
+            artifact_hash: Bytes32::from_words(&artifact_hash),
+        })
+    }
+
+    /// Empty in the EIP-161 sense
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nonce == 0 && self.balance.is_zero() && self.code_type == CodeType::Empty
+    }
+}
+
+// per-transaction flags of the cached account
+const FLAG_WARM: u8 = 1 << 0;
+const FLAG_CREATED: u8 = 1 << 1;
+const FLAG_DESTRUCTED: u8 = 1 << 2;
+
+#[derive(Clone, Copy, Debug)]
+struct CacheEntry {
+    address: Address,
+    account: Account,
+    flags: u8,
+}
+
+impl CacheEntry {
+    const EMPTY: Self = Self {
+        address: Address::ZERO,
+        account: Account::EMPTY,
+        flags: 0,
+    };
+}
+
+#[derive(Clone, Copy, Debug)]
+struct JournalEntry {
+    slot: usize,
+    previous: Account,
+    previous_flags: u8,
+}
+
+impl JournalEntry {
+    const EMPTY: Self = Self {
+        slot: 0,
+        previous: Account::EMPTY,
+        previous_flags: 0,
+    };
+}
+
+/// Accounts touched during the block. Account data is lazily requested from the oracle on
+/// the first access, and every modification is journaled so call frames can be reverted
+pub struct AccountCache {
+    entries: [CacheEntry; MAX_CACHED_ACCOUNTS],
+    len: usize,
+    journal: [JournalEntry; MAX_ACCOUNT_JOURNAL_LENGTH],
+    journal_len: usize,
+}
+
+impl AccountCache {
+    pub const fn new() -> Self {
+        Self {
+            entries: [CacheEntry::EMPTY; MAX_CACHED_ACCOUNTS],
+            len: 0,
+            journal: [JournalEntry::EMPTY; MAX_ACCOUNT_JOURNAL_LENGTH],
+            journal_len: 0,
+        }
+    }
+
+    fn slot_for(&mut self, address: &Address) -> Result<usize, SystemError> {
+        for (slot, entry) in self.entries[..self.len].iter().enumerate() {
+            if &entry.address == address {
+                return Ok(slot);
+            }
+        }
+
+        if self.len == MAX_CACHED_ACCOUNTS {
+            return Err(SystemError::AccountCacheFull);
+        }
+
+        let mut response = [0u32; ACCOUNT_DATA_WORDS];
+        Oracle::new().query(OracleQuery::AccountData, &address.to_words(), &mut response);
+        let account =
+            Account::from_oracle_response(&response).ok_or(SystemError::InvalidOracleResponse)?;
+
+        let slot = self.len;
+        self.entries[slot] = CacheEntry {
+            address: *address,
+            account,
+            flags: 0,
+        };
+        self.len += 1;
+
+        Ok(slot)
+    }
+
+    fn record(&mut self, slot: usize) -> Result<(), SystemError> {
+        if self.journal_len == MAX_ACCOUNT_JOURNAL_LENGTH {
+            return Err(SystemError::JournalFull);
+        }
+        self.journal[self.journal_len] = JournalEntry {
+            slot,
+            previous: self.entries[slot].account,
+            previous_flags: self.entries[slot].flags,
+        };
+        self.journal_len += 1;
+
+        Ok(())
+    }
+
+    fn set_flag(&mut self, address: &Address, flag: u8) -> Result<bool, SystemError> {
+        let slot = self.slot_for(address)?;
+        let was_set = self.entries[slot].flags & flag != 0;
+        if !was_set {
+            self.record(slot)?;
+            self.entries[slot].flags |= flag;
+        }
+
+        Ok(was_set)
+    }
+
+    fn has_flag(&mut self, address: &Address, flag: u8) -> Result<bool, SystemError> {
+        let slot = self.slot_for(address)?;
+
+        Ok(self.entries[slot].flags & flag != 0)
+    }
+
+    pub fn get(&mut self, address: &Address) -> Result<&Account, SystemError> {
+        let slot = self.slot_for(address)?;
+
+        Ok(&self.entries[slot].account)
+    }
+
+    /// Marks the account as accessed in this transaction, and returns whether it already was
+    pub fn touch(&mut self, address: &Address) -> Result<bool, SystemError> {
+        self.set_flag(address, FLAG_WARM)
+    }
+
+    pub fn mark_created(&mut self, address: &Address) -> Result<(), SystemError> {
+        self.set_flag(address, FLAG_CREATED).map(|_| ())
+    }
+
+    pub fn is_created_in_transaction(&mut self, address: &Address) -> Result<bool, SystemError> {
+        self.has_flag(address, FLAG_CREATED)
+    }
+
+    /// Account will be deleted at the end of the transaction
+    pub fn mark_destructed(&mut self, address: &Address) -> Result<(), SystemError> {
+        self.set_flag(address, FLAG_DESTRUCTED).map(|_| ())
+    }
+
+    /// Applies `f` to a copy of the account, and only if it succeeds journals the previous
+    /// value and writes the result back
+    pub fn update<F>(&mut self, address: &Address, f: F) -> Result<(), SystemError>
+    where
+        F: FnOnce(&mut Account) -> Result<(), SystemError>,
+    {
+        let slot = self.slot_for(address)?;
+        let mut account = self.entries[slot].account;
+        f(&mut account)?;
+        self.record(slot)?;
+        self.entries[slot].account = account;
+
+        Ok(())
+    }
+
+    pub fn transfer(&mut self, from: &Address, to: &Address, value: &U256) -> Result<(), SystemError> {
+        if value.is_zero() {
+            return Ok(());
+        }
+
+        self.update(from, |account| {
+            account.balance = account
+                .balance
+                .checked_sub(value)
+                .ok_or(SystemError::InsufficientBalance)?;
+            Ok(())
+        })?;
+        self.update(to, |account| {
+            account.balance = account
+                .balance
+                .checked_add(value)
+                .ok_or(SystemError::BalanceOverflow)?;
+            Ok(())
+        })
+    }
+
+    /// Increments the nonce and returns the previous value
+    pub fn increment_nonce(&mut self, address: &Address) -> Result<u64, SystemError> {
+        let mut previous_nonce = 0;
+        self.update(address, |account| {
+            previous_nonce = account.nonce;
+            account.nonce = account
+                .nonce
+                .checked_add(1)
+                .ok_or(SystemError::NonceOverflow)?;
+            Ok(())
+        })?;
+
+        Ok(previous_nonce)
+    }
+
+    pub fn set_code(
+        &mut self,
+        address: &Address,
+        code_hash: &Bytes32,
+        code_type: CodeType,
+        constants_hash: &Bytes32,
+        artifact_hash: &Bytes32,
+    ) -> Result<(), SystemError> {
+        self.update(address, |account| {
+            account.code_hash = *code_hash;
+            account.code_type = code_type;
+            account.constants_hash = *constants_hash;
+            account.artifact_hash = *artifact_hash;
+            Ok(())
+        })
+    }
+
+    #[must_use]
+    pub const fn snapshot(&self) -> usize {
+        self.journal_len
+    }
+
+    pub fn revert_to_snapshot(&mut self, snapshot: usize) {
+        while self.journal_len > snapshot {
+            self.journal_len -= 1;
+            let JournalEntry {
+                slot,
+                previous,
+                previous_flags,
+            } = self.journal[self.journal_len];
+            self.entries[slot].account = previous;
+            self.entries[slot].flags = previous_flags;
+        }
+    }
+
+    /// Deletes destructed accounts and resets per-transaction flags. Changes made
+    /// by the transaction can not be reverted after this point
+    pub fn finish_transaction(&mut self) {
+        for entry in self.entries[..self.len].iter_mut() {
+            if entry.flags & FLAG_DESTRUCTED != 0 {
+                entry.account = Account::EMPTY;
+            }
+            entry.flags = 0;
+        }
+        self.journal_len = 0;
+    }
+}
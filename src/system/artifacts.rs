@@ -0,0 +1,99 @@
+use super::blob_storage::BlobStorage;
+use super::memory::MemoryArena;
+use super::types::Bytes32;
+use super::SystemError;
+use crate::crypto::keccak256;
+use crate::oracle::{Oracle, OracleQuery};
+
+// Results of the deployment-time analysis of the code (jumpdest tables, side tables, compiled code, etc.).
+// Artifact is produced once when the code is deployed, and stored alongside the code keyed by the
+// code hash. Its hash is kept in the account, so later it can be lazily loaded from the oracle
+// without re-running the analysis
+
+pub const ARTIFACTS_ARENA_SIZE: usize = 1 << 24;
+pub const MAX_ARTIFACTS: usize = 1024;
+
+/// Produces an artifact for `code` into `artifact` buffer and returns its length
+pub type PreprocessingFn = fn(code: &[u8], artifact: &mut [u8]) -> Result<usize, SystemError>;
+
+#[must_use]
+pub fn artifact_hash(artifact: &[u8]) -> Bytes32 {
+    if artifact.is_empty() {
+        Bytes32::ZERO
+    } else {
+        Bytes32(keccak256(artifact))
+    }
+}
+
+pub struct ArtifactStorage {
+    blobs: BlobStorage<ARTIFACTS_ARENA_SIZE, MAX_ARTIFACTS>,
+}
+
+impl ArtifactStorage {
+    pub const fn new() -> Self {
+        Self {
+            blobs: BlobStorage::new(),
+        }
+    }
+
+    /// Runs the preprocessing for the code unless we already have an artifact for it
+    pub fn generate(
+        &mut self,
+        code_hash: &Bytes32,
+        code: &[u8],
+        preprocess: Option<PreprocessingFn>,
+    ) -> Result<(Bytes32, &'static [u8]), SystemError> {
+        let Some(preprocess) = preprocess else {
+            return Ok((Bytes32::ZERO, &[]));
+        };
+        let artifact = self
+            .blobs
+            .insert_with(code_hash, |buffer| preprocess(code, buffer))?;
+
+        Ok((artifact_hash(artifact), artifact))
+    }
+
+    /// Runs the preprocessing into the memory arena, for code that is executed once and never
+    /// stored (init code). The artifact lives until `memory` is released below it
+    pub fn generate_transient(
+        code: &[u8],
+        preprocess: Option<PreprocessingFn>,
+        memory: &mut MemoryArena,
+    ) -> Result<&'static [u8], SystemError> {
+        let Some(preprocess) = preprocess else {
+            return Ok(&[]);
+        };
+        let region = memory.allocate_with(|buffer| preprocess(code, buffer))?;
+
+        Ok(region.as_slice())
+    }
+
+    /// Returns the artifact for the code, requesting it from the oracle on the first use in the block
+    pub fn get(
+        &mut self,
+        code_hash: &Bytes32,
+        expected_artifact_hash: &Bytes32,
+    ) -> Result<&'static [u8], SystemError> {
+        if expected_artifact_hash == &Bytes32::ZERO {
+            return Ok(&[]);
+        }
+        if let Some(artifact) = self.blobs.get(code_hash) {
+            return Ok(artifact);
+        }
+
+        self.blobs.insert_with(code_hash, |buffer| {
+            let len = Oracle::new()
+                .query_bytes(
+                    OracleQuery::ArtifactByCodeHash,
+                    &code_hash.to_words(),
+                    buffer,
+                )
+                .ok_or(SystemError::OracleResponseTooLarge)?;
+            if &artifact_hash(&buffer[..len]) != expected_artifact_hash {
+                return Err(SystemError::InvalidOracleResponse);
+            }
+
+            Ok(len)
+        })
+    }
+}
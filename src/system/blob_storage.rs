@@ -0,0 +1,116 @@
+use super::types::Bytes32;
+use super::SystemError;
+use crate::crypto::keccak256;
+use crate::oracle::{Oracle, OracleQuery};
+
+#[derive(Clone, Copy, Debug)]
+struct BlobEntry {
+    hash: Bytes32,
+    offset: usize,
+    len: usize,
+}
+
+impl BlobEntry {
+    const EMPTY: Self = Self {
+        hash: Bytes32::ZERO,
+        offset: 0,
+        len: 0,
+    };
+}
+
+/// Blobs are kept word aligned, so that interpreters can read their artifacts as words
+#[repr(C, align(4))]
+struct Arena<const SIZE: usize>([u8; SIZE]);
+
+/// Append-only arena of immutable blobs keyed by their hash. Blobs are never moved
+/// or overwritten, and the storage lives inside of the static `System`, so we can hand
+/// out `'static` slices into it
+pub struct BlobStorage<const ARENA_SIZE: usize, const MAX_BLOBS: usize> {
+    arena: Arena<ARENA_SIZE>,
+    used: usize,
+    entries: [BlobEntry; MAX_BLOBS],
+    len: usize,
+}
+
+impl<const ARENA_SIZE: usize, const MAX_BLOBS: usize> BlobStorage<ARENA_SIZE, MAX_BLOBS> {
+    pub const fn new() -> Self {
+        Self {
+            arena: Arena([0u8; ARENA_SIZE]),
+            used: 0,
+            entries: [BlobEntry::EMPTY; MAX_BLOBS],
+            len: 0,
+        }
+    }
+
+    fn slice(&self, offset: usize, len: usize) -> &'static [u8] {
+        unsafe { core::slice::from_raw_parts(self.arena.0.as_ptr().add(offset), len) }
+    }
+
+    #[must_use]
+    pub fn get(&self, hash: &Bytes32) -> Option<&'static [u8]> {
+        self.entries[..self.len]
+            .iter()
+            .find(|entry| &entry.hash == hash)
+            .map(|entry| self.slice(entry.offset, entry.len))
+    }
+
+    pub fn insert(&mut self, hash: &Bytes32, blob: &[u8]) -> Result<&'static [u8], SystemError> {
+        self.insert_with(hash, |buffer| {
+            if blob.len() > buffer.len() {
+                return Err(SystemError::BlobStorageFull);
+            }
+            buffer[..blob.len()].copy_from_slice(blob);
+            Ok(blob.len())
+        })
+    }
+
+    /// Lets `fill` write the blob directly into the free part of the arena and return its
+    /// length, so that we do not need an intermediate buffer. Nothing is committed if `fill` fails
+    pub fn insert_with<F>(&mut self, hash: &Bytes32, fill: F) -> Result<&'static [u8], SystemError>
+    where
+        F: FnOnce(&mut [u8]) -> Result<usize, SystemError>,
+    {
+        if let Some(existing) = self.get(hash) {
+            return Ok(existing);
+        }
+        if self.len == MAX_BLOBS {
+            return Err(SystemError::BlobStorageFull);
+        }
+
+        let offset = self.used;
+        let len = fill(&mut self.arena.0[offset..])?;
+        // keep blobs word aligned
+        self.used = core::cmp::min(offset + ((len + 3) & !3), ARENA_SIZE);
+        self.entries[self.len] = BlobEntry {
+            hash: *hash,
+            offset,
+            len,
+        };
+        self.len += 1;
+
+        Ok(self.slice(offset, len))
+    }
+
+    /// Returns the blob, lazily requesting it from the oracle if it wasn't used in this block yet.
+    /// Oracle's answer is checked against the Keccak-256 hash, so it doesn't need to be trusted
+    pub fn get_or_fetch(
+        &mut self,
+        hash: &Bytes32,
+        query: OracleQuery,
+    ) -> Result<&'static [u8], SystemError> {
+        if let Some(blob) = self.get(hash) {
+            return Ok(blob);
+        }
+
+        self.insert_with(hash, |buffer| {
+            let len = Oracle::new()
+                .query_bytes(query, &hash.to_words(), buffer)
+                .ok_or(SystemError::OracleResponseTooLarge)?;
+            if keccak256(&buffer[..len]) != hash.0 {
+                return Err(SystemError::InvalidOracleResponse);
+            }
+
+            Ok(len)
+        })
+    }
+}
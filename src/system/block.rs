@@ -0,0 +1,83 @@
+use super::types::{Address, Bytes32};
+use crate::bigint::U256;
+use crate::oracle::{Oracle, OracleQuery};
+
+// number (2 words), timestamp (2 words), gas limit (2 words), chain id (2 words), coinbase (8 words),
+// base fee (8 words), prevrandao (8 words), blob base fee (8 words)
+pub const BLOCK_CONTEXT_WORDS: usize = 40;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockContext {
+    pub number: u64,
+    pub timestamp: u64,
+    pub gas_limit: u64,
+    pub chain_id: u64,
+    pub coinbase: Address,
+    pub base_fee: U256,
+    pub prevrandao: Bytes32,
+    pub blob_base_fee: U256,
+}
+
+impl BlockContext {
+    pub const EMPTY: Self = Self {
+        number: 0,
+        timestamp: 0,
+        gas_limit: 0,
+        chain_id: 0,
+        coinbase: Address::ZERO,
+        base_fee: U256::ZERO,
+        prevrandao: Bytes32::ZERO,
+        blob_base_fee: U256::ZERO,
+    };
+
+    /// Block parameters are chosen by the sequencer, and are part of the public input
+    pub fn from_oracle() -> Self {
+        let mut words = [0u32; BLOCK_CONTEXT_WORDS];
+        Oracle::new().query(OracleQuery::BlockContext, &[], &mut words);
+        let u64_at = |i: usize| (words[i] as u64) | ((words[i + 1] as u64) << 32);
+        let words8_at = |i: usize| {
+            let mut result = [0u32; 8];
+            result.copy_from_slice(&words[i..i + 8]);
+            result
+        };
+
+        Self {
+            number: u64_at(0),
+            timestamp: u64_at(2),
+            gas_limit: u64_at(4),
+            chain_id: u64_at(6),
+            coinbase: Address(Bytes32::from_words(&words8_at(8)).0),
+            base_fee: U256(words8_at(16)),
+            prevrandao: Bytes32::from_words(&words8_at(24)),
+            blob_base_fee: U256(words8_at(32)),
+        }
+    }
+
+    /// Hash of one of the 256 most recent blocks, or zero
+    pub fn block_hash(&self, number: u64) -> Bytes32 {
+        if number >= self.number || self.number - number > 256 {
+            return Bytes32::ZERO;
+        }
+        let mut words = [0u32; 8];
+        Oracle::new().query(
+            OracleQuery::BlockHash,
+            &[number as u32, (number >> 32) as u32],
+            &mut words,
+        );
+
+        Bytes32::from_words(&words)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransactionContext {
+    pub origin: Address,
+    pub gas_price: U256,
+}
+
+impl TransactionContext {
+    pub const EMPTY: Self = Self {
+        origin: Address::ZERO,
+        gas_price: U256::ZERO,
+    };
+}
@@ -0,0 +1,174 @@
+use super::account::{Account, CodeType};
+use super::interpreter::{ContractCode, ExecutionFrame, ExecutionStatus};
+use super::precompiles::Precompile;
+use super::resources::Resources;
+use super::types::Address;
+use super::{Snapshot, System, SystemError, MAX_CALL_DEPTH};
+use crate::bigint::U256;
+
+pub struct CallRequest<'a> {
+    pub caller: Address,
+    /// Account whose state is accessed
+    pub callee: Address,
+    /// Account whose code is executed, differs from `callee` for delegate calls
+    pub code_address: Address,
+    pub value: U256,
+    /// Delegate calls only expose the value of the parent frame, without transferring it
+    pub transfer_value: bool,
+    pub calldata: &'a [u8],
+    /// Callee (and everything it calls) can not modify the state
+    pub is_static: bool,
+}
+
+impl<'a> CallRequest<'a> {
+    /// Plain call of `callee`'s code with value transfer
+    #[must_use]
+    pub const fn new(caller: Address, callee: Address, value: U256, calldata: &'a [u8]) -> Self {
+        Self {
+            caller,
+            callee,
+            code_address: callee,
+            value,
+            transfer_value: true,
+            calldata,
+            is_static: false,
+        }
+    }
+}
+
+impl System {
+    /// Opens a new call frame and transfers the value from caller to callee. Returned snapshot
+    /// must be passed into `finish_call` once the callee is done
+    pub fn start_call(&mut self, request: &CallRequest) -> Result<Snapshot, SystemError> {
+        let snapshot = self.snapshot();
+        if request.transfer_value {
+            if let Err(error) =
+                self.accounts
+                    .transfer(&request.caller, &request.callee, &request.value)
+            {
+                self.revert_to_snapshot(snapshot);
+                return Err(error);
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Closes the call frame, and if the callee failed reverts everything it did,
+    /// including the value transfer
+    pub fn finish_call(&mut self, snapshot: Snapshot, success: bool) {
+        if !success {
+            self.revert_to_snapshot(snapshot);
+        }
+    }
+
+    /// Transfers the value and runs the code of `request.code_address` with the interpreter
+    /// selected by its code type, or the precompile registered at that address. `resources` are
+    /// the callee's budget, and on return hold whatever it didn't spend
+    pub fn call(
+        &mut self,
+        request: &CallRequest,
+        resources: &mut Resources,
+    ) -> Result<ExecutionStatus, SystemError> {
+        self.returndata.clear();
+        if self.depth >= MAX_CALL_DEPTH {
+            return Ok(ExecutionStatus::Failure);
+        }
+
+        let snapshot = self.start_call(request)?;
+        let result = self.run_callee(request, resources);
+        self.finish_call(snapshot, result == Ok(ExecutionStatus::Success));
+
+        result
+    }
+
+    /// Everything that happens inside of the call frame, so that every exit goes through
+    /// `finish_call`
+    fn run_callee(
+        &mut self,
+        request: &CallRequest,
+        resources: &mut Resources,
+    ) -> Result<ExecutionStatus, SystemError> {
+        if let Some(precompile) = self.precompiles.get(&request.code_address) {
+            return self.execute_precompile(precompile, request.calldata, resources);
+        }
+
+        let account = *self.accounts.get(&request.code_address)?;
+        if account.code_type == CodeType::Empty {
+            return Ok(ExecutionStatus::Success);
+        }
+        let contract_code = self.load_code(&account)?;
+
+        self.execute_code(&contract_code, request, resources, false)
+    }
+
+    fn load_code(&mut self, account: &Account) -> Result<ContractCode<'static>, SystemError> {
+        Ok(ContractCode {
+            code_type: account.code_type,
+            code: self.code.get(&account.code_hash)?,
+            code_hash: account.code_hash,
+            constants: self.constants.get(&account.constants_hash)?,
+            artifact: self
+                .artifacts
+                .get(&account.code_hash, &account.artifact_hash)?,
+        })
+    }
+
+    pub(crate) fn execute_code(
+        &mut self,
+        code: &ContractCode,
+        request: &CallRequest,
+        resources: &mut Resources,
+        is_constructor: bool,
+    ) -> Result<ExecutionStatus, SystemError> {
+        let interpreter = self.interpreters.get(code.code_type)?;
+        let frame = ExecutionFrame {
+            caller: request.caller,
+            address: request.callee,
+            value: request.value,
+            calldata: request.calldata,
+            code: code.code,
+            code_hash: code.code_hash,
+            constants: code.constants,
+            artifact: code.artifact,
+            is_constructor,
+            is_static: request.is_static,
+            depth: self.depth,
+        };
+
+        self.depth += 1;
+        let result = (interpreter.execute)(self, &frame, resources);
+        self.depth -= 1;
+
+        result
+    }
+
+    /// Runs the precompile as the callee of a call. Running out of resources or memory, and
+    /// invalid input, fail the call like a fault of an interpreter does
+    fn execute_precompile(
+        &mut self,
+        precompile: Precompile,
+        input: &[u8],
+        resources: &mut Resources,
+    ) -> Result<ExecutionStatus, SystemError> {
+        if resources.charge((precompile.cost)(input)).is_err() {
+            self.returndata.clear();
+            return Ok(ExecutionStatus::Failure);
+        }
+
+        // scratch of the precompile is released at once, as for interpreter frames
+        let frame_start = self.memory.allocate(0)?;
+        let result = (precompile.execute)(self, input);
+        self.memory.release(frame_start);
+
+        match result {
+            Ok(true) => Ok(ExecutionStatus::Success),
+            Ok(false) | Err(SystemError::OutOfResources | SystemError::OutOfMemory) => {
+                resources.burn();
+                self.returndata.clear();
+                Ok(ExecutionStatus::Failure)
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
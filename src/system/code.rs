@@ -0,0 +1,38 @@
+use super::blob_storage::BlobStorage;
+use super::types::Bytes32;
+use super::SystemError;
+use crate::crypto::keccak256;
+use crate::oracle::OracleQuery;
+
+pub const CODE_ARENA_SIZE: usize = 1 << 22;
+pub const MAX_CODE_BLOBS: usize = 1024;
+
+/// Code hash only covers the logic, constants are hashed separately
+#[must_use]
+pub fn code_hash(code: &[u8]) -> Bytes32 {
+    Bytes32(keccak256(code))
+}
+
+/// Code of all the contracts, deduplicated by code hash
+pub struct CodeStorage {
+    blobs: BlobStorage<CODE_ARENA_SIZE, MAX_CODE_BLOBS>,
+}
+
+impl CodeStorage {
+    pub const fn new() -> Self {
+        Self {
+            blobs: BlobStorage::new(),
+        }
+    }
+
+    pub fn insert(&mut self, code: &[u8]) -> Result<(Bytes32, &'static [u8]), SystemError> {
+        let hash = code_hash(code);
+        let stored = self.blobs.insert(&hash, code)?;
+
+        Ok((hash, stored))
+    }
+
+    pub fn get(&mut self, hash: &Bytes32) -> Result<&'static [u8], SystemError> {
+        self.blobs.get_or_fetch(hash, OracleQuery::CodeByHash)
+    }
+}
@@ -0,0 +1,48 @@
+use super::blob_storage::BlobStorage;
+use super::types::{Address, Bytes32};
+use super::{System, SystemError};
+use crate::crypto::keccak256;
+use crate::oracle::OracleQuery;
+
+// Immutables of the contract are not mixed into its code, but set at deployment as a separate
+// blob. So all the contracts with the same logic have the same code hash regardless of the
+// constants chosen by the deployer. The executing contract gets its own constants in the frame,
+// and the constants hash of any account through the system
+
+pub const CONSTANTS_ARENA_SIZE: usize = 1 << 20;
+pub const MAX_CONSTANTS_BLOBS: usize = 1024;
+
+#[must_use]
+pub fn constants_hash(constants: &[u8]) -> Bytes32 {
+    Bytes32(keccak256(constants))
+}
+
+pub struct ConstantsStorage {
+    blobs: BlobStorage<CONSTANTS_ARENA_SIZE, MAX_CONSTANTS_BLOBS>,
+}
+
+impl ConstantsStorage {
+    pub const fn new() -> Self {
+        Self {
+            blobs: BlobStorage::new(),
+        }
+    }
+
+    pub fn insert(&mut self, constants: &[u8]) -> Result<(Bytes32, &'static [u8]), SystemError> {
+        let hash = constants_hash(constants);
+        let stored = self.blobs.insert(&hash, constants)?;
+
+        Ok((hash, stored))
+    }
+
+    pub fn get(&mut self, hash: &Bytes32) -> Result<&'static [u8], SystemError> {
+        self.blobs.get_or_fetch(hash, OracleQuery::ConstantsByHash)
+    }
+}
+
+impl System {
+    /// Constants hash of the account, zero if it has no code
+    pub fn constants_hash(&mut self, address: &Address) -> Result<Bytes32, SystemError> {
+        Ok(self.accounts.get(address)?.constants_hash)
+    }
+}
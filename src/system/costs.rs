@@ -0,0 +1,94 @@
+// Costs of the system operations that every interpreter but EVM charges in ticks on its own:
+// access to the state, copying and hashing. They are the same as the gas of EVM, so that
+// contracts of all kinds pay the same for the same work
+
+use crate::system::types::Bytes32;
+
+pub const WARM_ACCESS: u64 = 100;
+pub const COLD_ACCOUNT_ACCESS: u64 = 2600;
+pub const COLD_SLOAD: u64 = 2100;
+pub const STORAGE_SET: u64 = 20000;
+pub const STORAGE_RESET: u64 = 2900;
+pub const STORAGE_CLEARS_REFUND: i64 = 4800;
+/// Storage can't be written with this much left or less (EIP-2200)
+pub const STORAGE_STIPEND: u64 = 2300;
+pub const CALL_VALUE: u64 = 9000;
+pub const CREATE: u64 = 32000;
+/// Per word of the deployed code and constants (EIP-3860)
+pub const CODE_WORD: u64 = 2;
+pub const COPY_WORD: u64 = 3;
+pub const KECCAK256: u64 = 30;
+pub const KECCAK256_WORD: u64 = 6;
+
+#[must_use]
+#[inline(always)]
+pub const fn copy_cost(len: usize) -> u64 {
+    COPY_WORD * (len as u64).div_ceil(32)
+}
+
+#[must_use]
+#[inline(always)]
+pub const fn keccak256_cost(len: usize) -> u64 {
+    KECCAK256 + KECCAK256_WORD * (len as u64).div_ceil(32)
+}
+
+/// Cost of a deployment from a contract, with hashing of the code for a salted address
+#[must_use]
+#[inline(always)]
+pub const fn deployment_cost(code_len: usize, constants_len: usize, is_salted: bool) -> u64 {
+    let code_words = (code_len as u64).div_ceil(32);
+    let hashing_cost = if is_salted {
+        KECCAK256_WORD * code_words
+    } else {
+        0
+    };
+
+    CREATE + CODE_WORD * (code_words + (constants_len as u64).div_ceil(32)) + hashing_cost
+}
+
+/// Returns the cost and refund of a storage write without the cold access surcharge, from
+/// the value of the slot at the start of the transaction, the current one and the new one
+/// (EIP-2200, EIP-3529)
+#[must_use]
+pub fn storage_write_cost(original: &Bytes32, current: &Bytes32, new: &Bytes32) -> (u64, i64) {
+    let zero = Bytes32::ZERO;
+    if current == new {
+        return (WARM_ACCESS, 0);
+    }
+    if original == current {
+        if original == &zero {
+            return (STORAGE_SET, 0);
+        }
+        let refund = if new == &zero {
+            STORAGE_CLEARS_REFUND
+        } else {
+            0
+        };
+        return (STORAGE_RESET, refund);
+    }
+
+    let mut refund = 0;
+    if original != &zero {
+        if current == &zero {
+            refund -= STORAGE_CLEARS_REFUND;
+        } else if new == &zero {
+            refund += STORAGE_CLEARS_REFUND;
+        }
+    }
+    if original == new {
+        refund += if original == &zero {
+            (STORAGE_SET - WARM_ACCESS) as i64
+        } else {
+            (STORAGE_RESET - WARM_ACCESS) as i64
+        };
+    }
+
+    (WARM_ACCESS, refund)
+}
+
+/// Callee can get at most all but one 64th of the remaining resources, as in EVM
+#[must_use]
+#[inline(always)]
+pub const fn all_but_one_64th(resources: u64) -> u64 {
+    resources - resources / 64
+}
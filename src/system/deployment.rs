@@ -0,0 +1,239 @@
+use super::account::CodeType;
+use super::artifacts::ArtifactStorage;
+use super::call::CallRequest;
+use super::code::code_hash;
+use super::interpreter::{ContractCode, ExecutionStatus, Interpreter};
+use super::resources::Resources;
+use super::types::{Address, Bytes32};
+use super::{System, SystemError, MAX_CALL_DEPTH};
+use crate::bigint::U256;
+use crate::crypto::{keccak256, Keccak256};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeploymentScheme {
+    /// Address depends on the deployer and its nonce
+    Create,
+    /// Address depends on the deployer, salt and the deployment blob
+    Create2 { salt: Bytes32 },
+}
+
+pub struct DeploymentRequest<'a> {
+    pub deployer: Address,
+    pub code_type: CodeType,
+    /// Init code for interpreters that return the code from constructor, and the code itself otherwise
+    pub code: &'a [u8],
+    /// Immutables of the contract, stored separately from the code
+    pub constants: &'a [u8],
+    pub constructor_calldata: &'a [u8],
+    pub value: U256,
+    pub scheme: DeploymentScheme,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeploymentResult {
+    pub address: Address,
+    pub status: ExecutionStatus,
+}
+
+// Addresses are derived the same way as in Ethereum, so EVM contracts can address
+// every contract regardless of its kind
+
+#[must_use]
+pub fn create_address(deployer: &Address, nonce: u64) -> Address {
+    // rlp([deployer, nonce])
+    let mut encoding = [0u8; 1 + 21 + 9];
+    encoding[1] = 0x80 + 20;
+    encoding[2..22].copy_from_slice(&deployer.evm_address());
+    let mut len = 22;
+    if nonce == 0 {
+        encoding[len] = 0x80;
+        len += 1;
+    } else if nonce < 0x80 {
+        encoding[len] = nonce as u8;
+        len += 1;
+    } else {
+        let bytes = nonce.to_be_bytes();
+        let skip = (nonce.leading_zeros() / 8) as usize;
+        encoding[len] = 0x80 + (8 - skip) as u8;
+        encoding[len + 1..len + 1 + 8 - skip].copy_from_slice(&bytes[skip..]);
+        len += 1 + 8 - skip;
+    }
+    encoding[0] = 0xc0 + (len - 1) as u8;
+
+    address_from_hash(&keccak256(&encoding[..len]))
+}
+
+#[must_use]
+pub fn create2_address(deployer: &Address, salt: &Bytes32, code: &[u8]) -> Address {
+    let mut hasher = Keccak256::new();
+    hasher.update(&[0xff]);
+    hasher.update(&deployer.evm_address());
+    hasher.update(&salt.0);
+    hasher.update(&keccak256(code));
+
+    address_from_hash(&hasher.finalize())
+}
+
+fn address_from_hash(hash: &[u8; 32]) -> Address {
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+
+    Address::from_evm_address(&address)
+}
+
+impl System {
+    /// Deploys a new contract: derives its address, runs the constructor with the interpreter
+    /// selected by `code_type`, and stores validated code under its hash. `resources` are
+    /// the constructor's budget, and on return hold whatever it didn't spend. As in Ethereum,
+    /// only the call depth, and the balance and nonce of the deployer fail the deployment before
+    /// it takes the nonce, invalid code and address collisions come after
+    pub fn deploy(
+        &mut self,
+        request: &DeploymentRequest,
+        resources: &mut Resources,
+    ) -> Result<DeploymentResult, SystemError> {
+        let interpreter = self.interpreters.get(request.code_type)?;
+        let deployer = *self.accounts.get(&request.deployer)?;
+        let address = match request.scheme {
+            DeploymentScheme::Create => create_address(&request.deployer, deployer.nonce),
+            DeploymentScheme::Create2 { salt } => {
+                create2_address(&request.deployer, &salt, request.code)
+            }
+        };
+
+        self.returndata.clear();
+        if self.depth >= MAX_CALL_DEPTH
+            || deployer.balance < request.value
+            || deployer.nonce == u64::MAX
+        {
+            return Ok(DeploymentResult {
+                address,
+                status: ExecutionStatus::Failure,
+            });
+        }
+        let _ = self.accounts.increment_nonce(&request.deployer)?;
+        let _ = self.accounts.touch(&address)?;
+
+        if !interpreter.constructor_returns_code && !(interpreter.validate)(request.code) {
+            return Err(SystemError::InvalidCode);
+        }
+        let existing = self.accounts.get(&address)?;
+        if existing.nonce != 0 || existing.code_type != CodeType::Empty {
+            return Err(SystemError::AddressCollision);
+        }
+
+        let call_request = CallRequest::new(
+            request.deployer,
+            address,
+            request.value,
+            request.constructor_calldata,
+        );
+        let snapshot = self.start_call(&call_request)?;
+        let result = match self.run_constructor(request, &call_request, &interpreter, resources) {
+            // the code doesn't fit into the kernel, that is the deployer's problem and not
+            // the transaction's
+            Err(SystemError::BlobStorageFull | SystemError::OutOfMemory) => {
+                resources.burn();
+                self.returndata.clear();
+                Ok(ExecutionStatus::Failure)
+            }
+            result => result,
+        };
+        self.finish_call(snapshot, result == Ok(ExecutionStatus::Success));
+
+        Ok(DeploymentResult {
+            address,
+            status: result?,
+        })
+    }
+
+    fn run_constructor(
+        &mut self,
+        request: &DeploymentRequest,
+        call_request: &CallRequest,
+        interpreter: &Interpreter,
+        resources: &mut Resources,
+    ) -> Result<ExecutionStatus, SystemError> {
+        let address = &call_request.callee;
+        let _ = self.accounts.increment_nonce(address)?;
+        self.accounts.mark_created(address)?;
+        let (constants_hash, constants) = self.constants.insert(request.constants)?;
+
+        if interpreter.constructor_returns_code {
+            // init code runs once, so its artifact is only kept in the memory arena while it runs
+            let frame_start = self.memory.allocate(0)?;
+            let status =
+                self.run_init_code(request, call_request, interpreter, constants, resources);
+            self.memory.release(frame_start);
+            let status = status?;
+            if status != ExecutionStatus::Success {
+                return Ok(status);
+            }
+
+            if !(interpreter.validate)(self.returndata.as_slice()) {
+                resources.burn();
+                return Ok(ExecutionStatus::Failure);
+            }
+            let (hash, code) = self.code.insert(self.returndata.as_slice())?;
+            self.returndata.clear();
+            let (artifact_hash, _) =
+                self.artifacts
+                    .generate(&hash, code, interpreter.preprocess)?;
+            self.accounts.set_code(
+                address,
+                &hash,
+                request.code_type,
+                &constants_hash,
+                &artifact_hash,
+            )?;
+
+            Ok(status)
+        } else {
+            // code is known upfront, so the constructor can already see it as its own
+            let (hash, code) = self.code.insert(request.code)?;
+            let (artifact_hash, artifact) =
+                self.artifacts
+                    .generate(&hash, code, interpreter.preprocess)?;
+            self.accounts.set_code(
+                address,
+                &hash,
+                request.code_type,
+                &constants_hash,
+                &artifact_hash,
+            )?;
+            let contract_code = ContractCode {
+                code_type: request.code_type,
+                code,
+                code_hash: hash,
+                constants,
+                artifact,
+            };
+
+            self.execute_code(&contract_code, call_request, resources, true)
+        }
+    }
+
+    fn run_init_code(
+        &mut self,
+        request: &DeploymentRequest,
+        call_request: &CallRequest,
+        interpreter: &Interpreter,
+        constants: &'static [u8],
+        resources: &mut Resources,
+    ) -> Result<ExecutionStatus, SystemError> {
+        let artifact = ArtifactStorage::generate_transient(
+            request.code,
+            interpreter.preprocess,
+            &mut self.memory,
+        )?;
+        let init_code = ContractCode {
+            code_type: request.code_type,
+            code: request.code,
+            code_hash: code_hash(request.code),
+            constants,
+            artifact,
+        };
+
+        self.execute_code(&init_code, call_request, resources, true)
+    }
+}
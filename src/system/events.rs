@@ -0,0 +1,102 @@
+use super::types::{Address, Bytes32};
+use super::SystemError;
+
+pub const MAX_EVENTS: usize = 4096;
+pub const MAX_TOPICS: usize = 4;
+pub const EVENTS_DATA_ARENA_SIZE: usize = 1 << 20;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Event {
+    pub address: Address,
+    topics: [Bytes32; MAX_TOPICS],
+    num_topics: usize,
+    data_offset: usize,
+    data_len: usize,
+}
+
+impl Event {
+    const EMPTY: Self = Self {
+        address: Address::ZERO,
+        topics: [Bytes32::ZERO; MAX_TOPICS],
+        num_topics: 0,
+        data_offset: 0,
+        data_len: 0,
+    };
+
+    #[must_use]
+    pub fn topics(&self) -> &[Bytes32] {
+        &self.topics[..self.num_topics]
+    }
+}
+
+/// Events emitted during the block. Events of reverted frames are dropped by truncation
+pub struct EventLog {
+    events: [Event; MAX_EVENTS],
+    len: usize,
+    data: [u8; EVENTS_DATA_ARENA_SIZE],
+    data_len: usize,
+}
+
+impl EventLog {
+    pub const fn new() -> Self {
+        Self {
+            events: [Event::EMPTY; MAX_EVENTS],
+            len: 0,
+            data: [0u8; EVENTS_DATA_ARENA_SIZE],
+            data_len: 0,
+        }
+    }
+
+    pub fn emit(&mut self, address: &Address, topics: &[Bytes32], data: &[u8]) -> Result<(), SystemError> {
+        if topics.len() > MAX_TOPICS {
+            return Err(SystemError::TooManyTopics);
+        }
+        if self.len == MAX_EVENTS || data.len() > EVENTS_DATA_ARENA_SIZE - self.data_len {
+            return Err(SystemError::EventLogFull);
+        }
+
+        let mut event = Event {
+            address: *address,
+            topics: [Bytes32::ZERO; MAX_TOPICS],
+            num_topics: topics.len(),
+            data_offset: self.data_len,
+            data_len: data.len(),
+        };
+        event.topics[..topics.len()].copy_from_slice(topics);
+        self.data[self.data_len..self.data_len + data.len()].copy_from_slice(data);
+        self.data_len += data.len();
+        self.events[self.len] = event;
+        self.len += 1;
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<(&Event, &[u8])> {
+        let event = self.events[..self.len].get(index)?;
+
+        Some((event, &self.data[event.data_offset..event.data_offset + event.data_len]))
+    }
+
+    #[must_use]
+    pub const fn snapshot(&self) -> usize {
+        self.len
+    }
+
+    pub fn revert_to_snapshot(&mut self, snapshot: usize) {
+        if snapshot < self.len {
+            self.len = snapshot;
+            self.data_len = self.events[snapshot].data_offset;
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use super::account::CodeType;
+use super::artifacts::PreprocessingFn;
+use super::resources::Resources;
+use super::types::{Address, Bytes32};
+use super::{System, SystemError};
+use crate::bigint::U256;
+
+pub const NUM_CODE_TYPES: usize = 4;
+pub const MAX_RETURNDATA_SIZE: usize = 1 << 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExecutionStatus {
+    Success,
+    Revert,
+    Failure,
+}
+
+/// Everything an interpreter needs to know about the call it executes
+pub struct ExecutionFrame<'a> {
+    pub caller: Address,
+    pub address: Address,
+    pub value: U256,
+    pub calldata: &'a [u8],
+    pub code: &'a [u8],
+    pub code_hash: Bytes32,
+    pub constants: &'static [u8],
+    /// Result of the deployment-time preprocessing of the code, empty if interpreter has no preprocessing
+    pub artifact: &'static [u8],
+    pub is_constructor: bool,
+    pub is_static: bool,
+    /// Number of frames below this one
+    pub depth: usize,
+}
+
+/// Code of the contract along with everything deployed alongside it
+#[derive(Clone, Copy)]
+pub struct ContractCode<'a> {
+    pub code_type: CodeType,
+    pub code: &'a [u8],
+    pub code_hash: Bytes32,
+    pub constants: &'static [u8],
+    pub artifact: &'static [u8],
+}
+
+#[derive(Clone, Copy)]
+pub struct Interpreter {
+    /// Checks the code before it's stored as a code of some account
+    pub validate: fn(code: &[u8]) -> bool,
+    /// Deployment-time analysis of the validated code, the result is available
+    /// to `execute` as `ExecutionFrame::artifact`
+    pub preprocess: Option<PreprocessingFn>,
+    /// Runs the code, spending `resources`. Output (returned or revert data) is placed
+    /// into `System::returndata`
+    pub execute: fn(
+        system: &mut System,
+        frame: &ExecutionFrame,
+        resources: &mut Resources,
+    ) -> Result<ExecutionStatus, SystemError>,
+    /// If set, then deployment blob is init code and constructor returns the code to deploy
+    /// (EVM way). Otherwise blob is deployed as is and constructor is the same code
+    /// executed with `is_constructor` flag
+    pub constructor_returns_code: bool,
+}
+
+/// Interpreters are registered by the kernel at boot, so that the dispatcher
+/// can route calls and deployments by the code type tag. They are kept by reference, as `None`
+/// of a reference is zero, while `None` of `Interpreter` itself is wherever its layout puts it
+pub struct InterpreterRegistry {
+    interpreters: [Option<&'static Interpreter>; NUM_CODE_TYPES],
+}
+
+impl InterpreterRegistry {
+    pub const fn new() -> Self {
+        Self {
+            interpreters: [None; NUM_CODE_TYPES],
+        }
+    }
+
+    pub fn register(&mut self, code_type: CodeType, interpreter: &'static Interpreter) {
+        self.interpreters[code_type as usize] = Some(interpreter);
+    }
+
+    pub fn get(&self, code_type: CodeType) -> Result<Interpreter, SystemError> {
+        self.interpreters[code_type as usize]
+            .copied()
+            .ok_or(SystemError::NoInterpreter)
+    }
+}
+
+pub struct ReturnData {
+    buffer: [u8; MAX_RETURNDATA_SIZE],
+    len: usize,
+}
+
+impl ReturnData {
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0u8; MAX_RETURNDATA_SIZE],
+            len: 0,
+        }
+    }
+
+    pub fn set(&mut self, data: &[u8]) -> Result<(), SystemError> {
+        if data.len() > MAX_RETURNDATA_SIZE {
+            return Err(SystemError::ReturnDataTooLarge);
+        }
+        self.buffer[..data.len()].copy_from_slice(data);
+        self.len = data.len();
+
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    #[must_use]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+}
@@ -0,0 +1,151 @@
+use super::SystemError;
+
+// Memory of the interpreters (EVM stacks and memories, WASM linear memories, etc.) is taken
+// from the heap region in a stack-like manner. Execution is strictly serial, so only
+// the frame that allocated last is running at any time, and it is the only one that can grow
+// its region. Caller's regions are untouched while callee is running, and callee's regions
+// are released when it returns
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryRegion {
+    start: usize,
+    len: usize,
+}
+
+impl MemoryRegion {
+    #[must_use]
+    #[inline(always)]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn as_slice(&self) -> &'static [u8] {
+        unsafe { core::slice::from_raw_parts(self.start as *const u8, self.len) }
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn as_mut_slice(&mut self) -> &'static mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.start as *mut u8, self.len) }
+    }
+}
+
+pub struct MemoryArena {
+    start: usize,
+    end: usize,
+    top: usize,
+}
+
+impl MemoryArena {
+    pub const fn new() -> Self {
+        Self {
+            start: 0,
+            end: 0,
+            top: 0,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `[start, end)` must be a valid memory range that is not used for anything else
+    pub unsafe fn init(&mut self, start: usize, end: usize) {
+        debug_assert!(start & (REGION_ALIGNMENT - 1) == 0);
+        self.start = start;
+        self.end = end;
+        self.top = start;
+    }
+
+    /// Allocates zeroed region on top of the arena
+    pub fn allocate(&mut self, len: usize) -> Result<MemoryRegion, SystemError> {
+        let start = self.top;
+        self.bump(start, len)?;
+        let mut region = MemoryRegion { start, len };
+        region.as_mut_slice().fill(0);
+
+        Ok(region)
+    }
+
+    /// Allocates an array of `len` copies of `value` on top of the arena
+    pub fn allocate_array<T: Copy>(
+        &mut self,
+        len: usize,
+        value: T,
+    ) -> Result<(MemoryRegion, &'static mut [T]), SystemError> {
+        debug_assert!(core::mem::align_of::<T>() <= REGION_ALIGNMENT);
+        let size = len
+            .checked_mul(core::mem::size_of::<T>())
+            .ok_or(SystemError::OutOfMemory)?;
+        let mut region = self.allocate(size)?;
+        let array = unsafe {
+            let start = region.as_mut_slice().as_mut_ptr().cast::<T>();
+            for i in 0..len {
+                start.add(i).write(value);
+            }
+            core::slice::from_raw_parts_mut(start, len)
+        };
+
+        Ok((region, array))
+    }
+
+    /// Lets `fill` write directly into the free part of the arena and return the length it used,
+    /// which is then allocated. The free part is not zeroed, and nothing is allocated if `fill`
+    /// fails
+    pub fn allocate_with<F>(&mut self, fill: F) -> Result<MemoryRegion, SystemError>
+    where
+        F: FnOnce(&mut [u8]) -> Result<usize, SystemError>,
+    {
+        let start = self.top;
+        let free = unsafe { core::slice::from_raw_parts_mut(start as *mut u8, self.end - start) };
+        let len = fill(free)?;
+        self.bump(start, len)?;
+
+        Ok(MemoryRegion { start, len })
+    }
+
+    /// Grows the region that must be the last allocated one, new part is zeroed
+    pub fn grow(&mut self, region: &mut MemoryRegion, new_len: usize) -> Result<(), SystemError> {
+        if aligned_end(region.start, region.len) != Some(self.top) {
+            return Err(SystemError::OutOfMemory);
+        }
+        if new_len <= region.len {
+            return Ok(());
+        }
+        self.bump(region.start, new_len)?;
+        let old_len = region.len;
+        region.len = new_len;
+        region.as_mut_slice()[old_len..].fill(0);
+
+        Ok(())
+    }
+
+    /// Releases the region and everything allocated after it
+    pub fn release(&mut self, region: MemoryRegion) {
+        debug_assert!(region.start >= self.start && region.start <= self.top);
+        self.top = region.start;
+    }
+
+    fn bump(&mut self, start: usize, len: usize) -> Result<(), SystemError> {
+        let end = aligned_end(start, len).ok_or(SystemError::OutOfMemory)?;
+        if end > self.end {
+            return Err(SystemError::OutOfMemory);
+        }
+        self.top = end;
+
+        Ok(())
+    }
+}
+
+/// Regions are aligned enough to hold any primitive type
+pub const REGION_ALIGNMENT: usize = 8;
+
+fn aligned_end(start: usize, len: usize) -> Option<usize> {
+    Some(start.checked_add(len)?.checked_add(REGION_ALIGNMENT - 1)? & !(REGION_ALIGNMENT - 1))
+}
@@ -0,0 +1,144 @@
+// System layer: state and IO shared by all the interpreters. Interpreters never keep
+// their own copy of balances, storage, etc., and instead go through the `System`
+
+pub mod account;
+pub mod artifacts;
+pub mod blob_storage;
+pub mod block;
+pub mod call;
+pub mod code;
+pub mod constants;
+pub mod costs;
+pub mod deployment;
+pub mod events;
+pub mod interpreter;
+pub mod memory;
+pub mod precompiles;
+pub mod resources;
+pub mod storage;
+pub mod transaction;
+pub mod types;
+
+use self::account::AccountCache;
+use self::artifacts::ArtifactStorage;
+use self::block::{BlockContext, TransactionContext};
+use self::code::CodeStorage;
+use self::constants::ConstantsStorage;
+use self::events::EventLog;
+use self::interpreter::{InterpreterRegistry, ReturnData};
+use self::memory::MemoryArena;
+use self::precompiles::PrecompileRegistry;
+use self::storage::{Storage, TransientStorage};
+
+pub const MAX_CALL_DEPTH: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SystemError {
+    AccountCacheFull,
+    StorageCacheFull,
+    JournalFull,
+    InvalidOracleResponse,
+    /// The block needs more data from the oracle than the kernel can hold, so it can't be
+    /// executed at all. Unlike the rest, this is never the fault of a transaction
+    OracleResponseTooLarge,
+    InsufficientBalance,
+    BalanceOverflow,
+    NonceOverflow,
+    InvalidNonce,
+    InvalidTransaction,
+    InvalidSignature,
+    GasLimitTooHigh,
+    GasPriceTooLow,
+    BlobStorageFull,
+    NoInterpreter,
+    InvalidCode,
+    AddressCollision,
+    ReturnDataTooLarge,
+    OutOfResources,
+    OutOfMemory,
+    TooManyTopics,
+    EventLogFull,
+}
+
+/// Point in the state history that we can revert to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    accounts: usize,
+    storage: usize,
+    transient_storage: usize,
+    events: usize,
+    refund: i64,
+}
+
+pub struct System {
+    pub accounts: AccountCache,
+    pub storage: Storage,
+    pub transient_storage: TransientStorage,
+    pub events: EventLog,
+    pub code: CodeStorage,
+    pub constants: ConstantsStorage,
+    pub artifacts: ArtifactStorage,
+    pub interpreters: InterpreterRegistry,
+    pub precompiles: PrecompileRegistry,
+    pub returndata: ReturnData,
+    pub memory: MemoryArena,
+    pub block: BlockContext,
+    pub transaction: TransactionContext,
+    /// Resources refunded at the end of transaction (e.g. for clearing storage), can temporarily go negative
+    pub refund: i64,
+    pub depth: usize,
+}
+
+static mut SYSTEM: System = System::new();
+
+/// There is a single hart and execution is strictly serial, so the system layer is a singleton
+pub fn system() -> &'static mut System {
+    unsafe { &mut *core::ptr::addr_of_mut!(SYSTEM) }
+}
+
+impl System {
+    pub const fn new() -> Self {
+        Self {
+            accounts: AccountCache::new(),
+            storage: Storage::new(),
+            transient_storage: TransientStorage::new(),
+            events: EventLog::new(),
+            code: CodeStorage::new(),
+            constants: ConstantsStorage::new(),
+            artifacts: ArtifactStorage::new(),
+            interpreters: InterpreterRegistry::new(),
+            precompiles: PrecompileRegistry::new(),
+            returndata: ReturnData::new(),
+            memory: MemoryArena::new(),
+            block: BlockContext::EMPTY,
+            transaction: TransactionContext::EMPTY,
+            refund: 0,
+            depth: 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            accounts: self.accounts.snapshot(),
+            storage: self.storage.snapshot(),
+            transient_storage: self.transient_storage.snapshot(),
+            events: self.events.snapshot(),
+            refund: self.refund,
+        }
+    }
+
+    pub fn revert_to_snapshot(&mut self, snapshot: Snapshot) {
+        self.accounts.revert_to_snapshot(snapshot.accounts);
+        self.storage.revert_to_snapshot(snapshot.storage);
+        self.transient_storage
+            .revert_to_snapshot(snapshot.transient_storage);
+        self.events.revert_to_snapshot(snapshot.events);
+        self.refund = snapshot.refund;
+    }
+
+    /// Requests the block parameters from the oracle, must be called before the first transaction
+    pub fn start_block(&mut self) {
+        self.block = BlockContext::from_oracle();
+    }
+}
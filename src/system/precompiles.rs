@@ -0,0 +1,68 @@
+use super::types::Address;
+use super::{System, SystemError};
+
+// Precompiles are contracts implemented in the kernel at the reserved addresses
+// 1..=MAX_PRECOMPILES (the address as a big-endian number). The call dispatcher runs them instead
+// of the code of the account, so that EVM, native and WASM callers all reach the same
+// implementation, and a new system feature is a single registration
+
+pub const MAX_PRECOMPILES: usize = 32;
+
+#[derive(Clone, Copy)]
+pub struct Precompile {
+    /// Resources of the call with the input, charged before `execute`
+    pub cost: fn(input: &[u8]) -> u64,
+    /// Places the output into `System::returndata`. Returns `false` if the input is invalid,
+    /// which fails the call and burns its resources, as in EVM
+    pub execute: fn(system: &mut System, input: &[u8]) -> Result<bool, SystemError>,
+}
+
+/// Precompiles are registered by the kernel at boot, along with the interpreters
+pub struct PrecompileRegistry {
+    precompiles: [Option<Precompile>; MAX_PRECOMPILES],
+}
+
+impl PrecompileRegistry {
+    pub const fn new() -> Self {
+        Self {
+            precompiles: [None; MAX_PRECOMPILES],
+        }
+    }
+
+    /// Address of the precompile `number`, in 1..=MAX_PRECOMPILES
+    #[must_use]
+    pub const fn address(number: usize) -> Address {
+        let mut address = Address::ZERO;
+        address.0[31] = number as u8;
+
+        address
+    }
+
+    /// Number of the precompile at `address`, if the address is a reserved one
+    fn number(address: &Address) -> Option<usize> {
+        let number = address.0[31] as usize;
+        let reserved = address.0[..31].iter().all(|byte| *byte == 0)
+            && (1..=MAX_PRECOMPILES).contains(&number);
+
+        reserved.then_some(number)
+    }
+
+    pub fn register(&mut self, number: usize, precompile: Precompile) {
+        debug_assert!((1..=MAX_PRECOMPILES).contains(&number));
+        self.precompiles[number - 1] = Some(precompile);
+    }
+
+    #[must_use]
+    pub fn get(&self, address: &Address) -> Option<Precompile> {
+        Self::number(address).and_then(|number| self.precompiles[number - 1])
+    }
+
+    /// Addresses of the registered precompiles, they are warm from the start of a transaction
+    pub fn addresses(&self) -> impl Iterator<Item = Address> + '_ {
+        self.precompiles
+            .iter()
+            .enumerate()
+            .filter(|(_, precompile)| precompile.is_some())
+            .map(|(index, _)| Self::address(index + 1))
+    }
+}
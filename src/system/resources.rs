@@ -0,0 +1,54 @@
+use super::SystemError;
+
+/// Execution resources (time ticks). Every call frame owns its resources, passes
+/// a part of them to the callee, and takes back whatever the callee didn't spend.
+/// Interpreters measure their own units in ticks, e.g. one EVM gas is one tick
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Resources {
+    ticks: u64,
+}
+
+impl Resources {
+    pub const EMPTY: Self = Self { ticks: 0 };
+
+    #[must_use]
+    pub const fn new(ticks: u64) -> Self {
+        Self { ticks }
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn remaining(&self) -> u64 {
+        self.ticks
+    }
+
+    /// On failure all the remaining resources are burnt, as in EVM
+    #[inline(always)]
+    pub fn charge(&mut self, ticks: u64) -> Result<(), SystemError> {
+        if ticks > self.ticks {
+            self.ticks = 0;
+            return Err(SystemError::OutOfResources);
+        }
+        self.ticks -= ticks;
+
+        Ok(())
+    }
+
+    /// Splits off up to `ticks` to be passed to the callee
+    #[must_use]
+    pub fn take(&mut self, ticks: u64) -> Self {
+        let taken = core::cmp::min(ticks, self.ticks);
+        self.ticks -= taken;
+
+        Self { ticks: taken }
+    }
+
+    /// Returns unspent resources of the callee
+    pub fn reclaim(&mut self, other: Self) {
+        self.ticks += other.ticks;
+    }
+
+    pub fn burn(&mut self) {
+        self.ticks = 0;
+    }
+}
@@ -0,0 +1,229 @@
+use super::types::{Address, Bytes32};
+use super::SystemError;
+use crate::oracle::{Oracle, OracleQuery};
+
+pub const MAX_STORAGE_SLOTS: usize = 16384;
+pub const MAX_STORAGE_JOURNAL_LENGTH: usize = 16384;
+pub const MAX_TRANSIENT_SLOTS: usize = 1024;
+pub const MAX_TRANSIENT_JOURNAL_LENGTH: usize = 1024;
+
+/// Persistent storage. Slots are lazily requested from the oracle
+pub type Storage = SlotCache<MAX_STORAGE_SLOTS, MAX_STORAGE_JOURNAL_LENGTH, false>;
+/// Transient storage (EIP-1153). Slots start as zeroes and are cleared after every transaction
+pub type TransientStorage = SlotCache<MAX_TRANSIENT_SLOTS, MAX_TRANSIENT_JOURNAL_LENGTH, true>;
+
+/// State of the slot observed by an access, before the access modified it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlotAccess {
+    pub value: Bytes32,
+    /// Value at the beginning of the transaction
+    pub original: Bytes32,
+    /// If the slot was already accessed in this transaction
+    pub was_warm: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SlotEntry {
+    address: Address,
+    key: Bytes32,
+    value: Bytes32,
+    original: Bytes32,
+    warm: bool,
+}
+
+impl SlotEntry {
+    const EMPTY: Self = Self {
+        address: Address::ZERO,
+        key: Bytes32::ZERO,
+        value: Bytes32::ZERO,
+        original: Bytes32::ZERO,
+        warm: false,
+    };
+}
+
+#[derive(Clone, Copy, Debug)]
+struct JournalEntry {
+    slot: u32,
+    previous_warm: bool,
+    previous: Bytes32,
+}
+
+impl JournalEntry {
+    const EMPTY: Self = Self {
+        slot: 0,
+        previous_warm: false,
+        previous: Bytes32::ZERO,
+    };
+}
+
+/// Journaled `(address, key) -> value` map. Slots are found through an open addressing index
+/// with twice as many buckets as slots, as linear search over thousands of slots
+/// is too expensive to prove. The kind of the storage is a parameter rather than a field, so that
+/// the system singleton is all zeroes and goes to .bss
+pub struct SlotCache<const MAX_SLOTS: usize, const MAX_JOURNAL_LENGTH: usize, const TRANSIENT: bool>
+{
+    entries: [SlotEntry; MAX_SLOTS],
+    len: usize,
+    // entry index + 1, 0 is an empty bucket. Length is always 2 * MAX_SLOTS
+    index: [[u32; 2]; MAX_SLOTS],
+    journal: [JournalEntry; MAX_JOURNAL_LENGTH],
+    journal_len: usize,
+}
+
+fn slot_hash(address: &Address, key: &Bytes32) -> usize {
+    let mut hash = 0x811c9dc5u32;
+    for word in address.to_words().iter().chain(key.to_words().iter()) {
+        hash = (hash ^ word).wrapping_mul(0x01000193);
+    }
+
+    hash as usize
+}
+
+impl<const MAX_SLOTS: usize, const MAX_JOURNAL_LENGTH: usize, const TRANSIENT: bool>
+    SlotCache<MAX_SLOTS, MAX_JOURNAL_LENGTH, TRANSIENT>
+{
+    pub const fn new() -> Self {
+        Self {
+            entries: [SlotEntry::EMPTY; MAX_SLOTS],
+            len: 0,
+            index: [[0u32; 2]; MAX_SLOTS],
+            journal: [JournalEntry::EMPTY; MAX_JOURNAL_LENGTH],
+            journal_len: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn bucket(&mut self, bucket: usize) -> &mut u32 {
+        &mut self.index[bucket / 2][bucket % 2]
+    }
+
+    fn slot_for(&mut self, address: &Address, key: &Bytes32) -> Result<usize, SystemError> {
+        let num_buckets = 2 * MAX_SLOTS;
+        let mut bucket = slot_hash(address, key) % num_buckets;
+        loop {
+            let entry_index = *self.bucket(bucket);
+            if entry_index == 0 {
+                break;
+            }
+            let entry = &self.entries[entry_index as usize - 1];
+            if &entry.address == address && &entry.key == key {
+                return Ok(entry_index as usize - 1);
+            }
+            bucket = (bucket + 1) % num_buckets;
+        }
+
+        if self.len == MAX_SLOTS {
+            return Err(SystemError::StorageCacheFull);
+        }
+
+        let value = if TRANSIENT {
+            Bytes32::ZERO
+        } else {
+            let mut params = [0u32; 16];
+            params[..8].copy_from_slice(&address.to_words());
+            params[8..].copy_from_slice(&key.to_words());
+            let mut response = [0u32; 8];
+            Oracle::new().query(OracleQuery::StorageSlot, &params, &mut response);
+
+            Bytes32::from_words(&response)
+        };
+
+        let slot = self.len;
+        self.entries[slot] = SlotEntry {
+            address: *address,
+            key: *key,
+            value,
+            original: value,
+            warm: false,
+        };
+        self.len += 1;
+        *self.bucket(bucket) = self.len as u32;
+
+        Ok(slot)
+    }
+
+    fn access(&mut self, address: &Address, key: &Bytes32) -> Result<(usize, SlotAccess), SystemError> {
+        let slot = self.slot_for(address, key)?;
+        let entry = self.entries[slot];
+        if !entry.warm {
+            self.record(slot)?;
+            self.entries[slot].warm = true;
+        }
+
+        Ok((
+            slot,
+            SlotAccess {
+                value: entry.value,
+                original: entry.original,
+                was_warm: entry.warm,
+            },
+        ))
+    }
+
+    fn record(&mut self, slot: usize) -> Result<(), SystemError> {
+        if self.journal_len == MAX_JOURNAL_LENGTH {
+            return Err(SystemError::JournalFull);
+        }
+        self.journal[self.journal_len] = JournalEntry {
+            slot: slot as u32,
+            previous_warm: self.entries[slot].warm,
+            previous: self.entries[slot].value,
+        };
+        self.journal_len += 1;
+
+        Ok(())
+    }
+
+    pub fn read(&mut self, address: &Address, key: &Bytes32) -> Result<SlotAccess, SystemError> {
+        self.access(address, key).map(|(_, access)| access)
+    }
+
+    /// Writes the value and returns the state of the slot before the write
+    pub fn write(
+        &mut self,
+        address: &Address,
+        key: &Bytes32,
+        value: &Bytes32,
+    ) -> Result<SlotAccess, SystemError> {
+        let (slot, access) = self.access(address, key)?;
+        if &access.value != value {
+            self.record(slot)?;
+            self.entries[slot].value = *value;
+        }
+
+        Ok(access)
+    }
+
+    #[must_use]
+    pub const fn snapshot(&self) -> usize {
+        self.journal_len
+    }
+
+    pub fn revert_to_snapshot(&mut self, snapshot: usize) {
+        while self.journal_len > snapshot {
+            self.journal_len -= 1;
+            let JournalEntry {
+                slot,
+                previous_warm,
+                previous,
+            } = self.journal[self.journal_len];
+            self.entries[slot as usize].value = previous;
+            self.entries[slot as usize].warm = previous_warm;
+        }
+    }
+
+    /// Persistent slots start the next transaction cold, with current values as original ones.
+    /// Transient storage is wiped
+    pub fn finish_transaction(&mut self) {
+        self.journal_len = 0;
+        if TRANSIENT {
+            self.len = 0;
+            self.index = [[0u32; 2]; MAX_SLOTS];
+        } else {
+            for entry in self.entries[..self.len].iter_mut() {
+                entry.original = entry.value;
+                entry.warm = false;
+            }
+        }
+    }
+}
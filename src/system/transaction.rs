@@ -0,0 +1,659 @@
+use super::account::CodeType;
+use super::block::TransactionContext;
+use super::call::CallRequest;
+use super::deployment::{DeploymentRequest, DeploymentScheme};
+use super::interpreter::ExecutionStatus;
+use super::resources::Resources;
+use super::types::Address;
+use super::{System, SystemError};
+use crate::bigint::U256;
+use crate::crypto::secp256k1::{self, HALF_ORDER};
+use crate::crypto::Keccak256;
+
+pub const TRANSACTION_BASE_COST: u64 = 21000;
+pub const CALLDATA_ZERO_BYTE_COST: u64 = 4;
+pub const CALLDATA_NON_ZERO_BYTE_COST: u64 = 16;
+pub const CREATION_COST: u64 = 32000;
+/// Per word of the deployed code and constants (EIP-3860)
+pub const CODE_WORD_COST: u64 = 2;
+/// At most 1/5 of the spent resources is refunded (EIP-3529)
+pub const MAX_REFUND_QUOTIENT: u64 = 5;
+
+/// EIP-2718 type of the transactions that deploy a contract of any code type along with its
+/// constants: `0x7f || rlp([chain_id, nonce, gas_price, gas_limit, value, code_type, code,
+/// constants, calldata, y_parity, r, s])`, signed over the same without the signature
+pub const DEPLOYMENT_TRANSACTION_TYPE: u8 = 0x7f;
+
+/// Legacy transactions are signed over the RLP of their fields. `v` is 27 or 28 plus the
+/// parity of the signature, or 35 plus twice the chain id with replay protection (EIP-155).
+/// Deployment transactions always have the chain id, and `v` is the parity
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub v: u64,
+    pub r: U256,
+    pub s: U256,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransactionKind<'a> {
+    Call(Address),
+    /// Legacy transaction with an empty `to`, the calldata is the EVM init code
+    Create,
+    /// Deployment transaction, the calldata goes to the constructor
+    Deploy {
+        chain_id: u64,
+        code_type: CodeType,
+        code: &'a [u8],
+        constants: &'a [u8],
+    },
+}
+
+/// The sender is not a field, it is always recovered from the signature
+pub struct Transaction<'a> {
+    pub kind: TransactionKind<'a>,
+    pub value: U256,
+    pub nonce: u64,
+    pub gas_limit: u64,
+    pub gas_price: U256,
+    pub calldata: &'a [u8],
+    pub signature: Signature,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransactionResult {
+    pub status: ExecutionStatus,
+    pub gas_used: u64,
+}
+
+#[must_use]
+pub fn data_cost(data: &[u8]) -> u64 {
+    data.iter().fold(0, |cost, byte| {
+        cost + if *byte == 0 {
+            CALLDATA_ZERO_BYTE_COST
+        } else {
+            CALLDATA_NON_ZERO_BYTE_COST
+        }
+    })
+}
+
+#[inline(always)]
+fn words(data: &[u8]) -> u64 {
+    (data.len() as u64).div_ceil(32)
+}
+
+impl<'a> Transaction<'a> {
+    /// Cost of the transaction before any execution: the base, every byte it carries, and
+    /// the creation with the words of the code for deployments
+    #[must_use]
+    pub fn intrinsic_cost(&self) -> u64 {
+        let cost = TRANSACTION_BASE_COST + data_cost(self.calldata);
+        match self.kind {
+            TransactionKind::Call(_) => cost,
+            TransactionKind::Create => cost + CREATION_COST + CODE_WORD_COST * words(self.calldata),
+            TransactionKind::Deploy {
+                code, constants, ..
+            } => {
+                cost + data_cost(code)
+                    + data_cost(constants)
+                    + CREATION_COST
+                    + CODE_WORD_COST * (words(code) + words(constants))
+            }
+        }
+    }
+
+    /// Deployment that a creation makes: EVM init code is the calldata of a legacy transaction,
+    /// and the code of a deployment transaction comes with its constants and constructor calldata
+    #[must_use]
+    pub fn deployment_request(&self, deployer: Address) -> DeploymentRequest<'a> {
+        let (code_type, code, constants, constructor_calldata) = match self.kind {
+            TransactionKind::Deploy {
+                code_type,
+                code,
+                constants,
+                ..
+            } => (code_type, code, constants, self.calldata),
+            _ => (CodeType::Evm, self.calldata, &[][..], &[][..]),
+        };
+
+        DeploymentRequest {
+            deployer,
+            code_type,
+            code,
+            constants,
+            constructor_calldata,
+            value: self.value,
+            scheme: DeploymentScheme::Create,
+        }
+    }
+
+    /// Keccak-256 of the RLP of the signed fields, with the chain id and two zeroes after them
+    /// for the replay protected legacy signatures. Deployment transactions have their own
+    /// chain id, and their type before the RLP
+    #[must_use]
+    pub fn signing_hash(&self, chain_id: Option<u64>) -> [u8; 32] {
+        let payload_len = self.encode_fields(chain_id, &mut |_| {});
+        let mut hasher = Keccak256::new();
+        if let TransactionKind::Deploy { .. } = self.kind {
+            hasher.update(&[DEPLOYMENT_TRANSACTION_TYPE]);
+        }
+        rlp_header(0xc0, payload_len, &mut |bytes| hasher.update(bytes));
+        self.encode_fields(chain_id, &mut |bytes| hasher.update(bytes));
+
+        hasher.finalize()
+    }
+
+    /// Address that signed the transaction. Signatures with `s` in the upper half of the order
+    /// are rejected, as their negation is valid as well (EIP-2)
+    pub fn recover_sender(&self, chain_id: u64) -> Result<Address, SystemError> {
+        let signature = &self.signature;
+        let (y_parity, replay_protection) = match (self.kind, signature.v) {
+            (TransactionKind::Deploy { chain_id: id, .. }, v) => {
+                if id != chain_id || v > 1 {
+                    return Err(SystemError::InvalidSignature);
+                }
+                (v == 1, Some(chain_id))
+            }
+            (_, 27 | 28) => (signature.v == 28, None),
+            (_, v) if v >= 35 && (v - 35) / 2 == chain_id => ((v - 35) % 2 == 1, Some(chain_id)),
+            _ => return Err(SystemError::InvalidSignature),
+        };
+        if signature.s > HALF_ORDER {
+            return Err(SystemError::InvalidSignature);
+        }
+
+        let hash = self.signing_hash(replay_protection);
+        secp256k1::recover(&hash, y_parity, &signature.r, &signature.s)
+            .map(|address| Address::from_evm_address(&address))
+            .ok_or(SystemError::InvalidSignature)
+    }
+
+    /// Decodes a signed legacy or deployment transaction
+    pub fn decode(encoding: &'a [u8]) -> Result<Self, SystemError> {
+        match encoding.split_first() {
+            Some((&DEPLOYMENT_TRANSACTION_TYPE, payload)) => Self::decode_deployment(payload),
+            _ => Self::decode_legacy(encoding),
+        }
+        .ok_or(SystemError::InvalidTransaction)
+    }
+
+    fn decode_legacy(encoding: &'a [u8]) -> Option<Self> {
+        let [nonce, gas_price, gas_limit, to, value, calldata, v, r, s] = rlp_strings(encoding)?;
+        let kind = if to.is_empty() {
+            TransactionKind::Create
+        } else {
+            TransactionKind::Call(Address::from_evm_address(to.try_into().ok()?))
+        };
+
+        Some(Self {
+            kind,
+            nonce: rlp_u64(nonce)?,
+            gas_price: rlp_u256(gas_price)?,
+            gas_limit: rlp_u64(gas_limit)?,
+            value: rlp_u256(value)?,
+            calldata,
+            signature: Signature {
+                v: rlp_u64(v)?,
+                r: rlp_u256(r)?,
+                s: rlp_u256(s)?,
+            },
+        })
+    }
+
+    fn decode_deployment(encoding: &'a [u8]) -> Option<Self> {
+        let fields: [&[u8]; 12] = rlp_strings(encoding)?;
+        let [chain_id, nonce, gas_price, gas_limit, value, fields @ ..] = fields;
+        let [code_type, code, constants, calldata, y_parity, r, s] = fields;
+        let code_type = CodeType::from_u32(u32::try_from(rlp_u64(code_type)?).ok()?)
+            .filter(|code_type| *code_type != CodeType::Empty)?;
+
+        Some(Self {
+            kind: TransactionKind::Deploy {
+                chain_id: rlp_u64(chain_id)?,
+                code_type,
+                code,
+                constants,
+            },
+            nonce: rlp_u64(nonce)?,
+            gas_price: rlp_u256(gas_price)?,
+            gas_limit: rlp_u64(gas_limit)?,
+            value: rlp_u256(value)?,
+            calldata,
+            signature: Signature {
+                v: rlp_u64(y_parity)?,
+                r: rlp_u256(r)?,
+                s: rlp_u256(s)?,
+            },
+        })
+    }
+
+    /// Feeds the RLP of the fields to `sink`, returns its length
+    fn encode_fields(&self, chain_id: Option<u64>, sink: &mut dyn FnMut(&[u8])) -> usize {
+        if let TransactionKind::Deploy {
+            chain_id,
+            code_type,
+            code,
+            constants,
+        } = self.kind
+        {
+            let mut len = rlp_integer(&U256::from_u64(chain_id), sink);
+            len += rlp_integer(&U256::from_u64(self.nonce), sink);
+            len += rlp_integer(&self.gas_price, sink);
+            len += rlp_integer(&U256::from_u64(self.gas_limit), sink);
+            len += rlp_integer(&self.value, sink);
+            len += rlp_integer(&U256::from_u64(code_type as u64), sink);
+            len += rlp_bytes(code, sink);
+            len += rlp_bytes(constants, sink);
+            len += rlp_bytes(self.calldata, sink);
+            return len;
+        }
+
+        let mut len = rlp_integer(&U256::from_u64(self.nonce), sink);
+        len += rlp_integer(&self.gas_price, sink);
+        len += rlp_integer(&U256::from_u64(self.gas_limit), sink);
+        len += match self.kind {
+            TransactionKind::Call(to) => rlp_bytes(&to.evm_address(), sink),
+            _ => rlp_bytes(&[], sink),
+        };
+        len += rlp_integer(&self.value, sink);
+        len += rlp_bytes(self.calldata, sink);
+        if let Some(chain_id) = chain_id {
+            len += rlp_integer(&U256::from_u64(chain_id), sink);
+            len += rlp_integer(&U256::ZERO, sink);
+            len += rlp_integer(&U256::ZERO, sink);
+        }
+
+        len
+    }
+}
+
+/// Header of a string (`offset` 0x80) or a list (0xc0) of `len` bytes, returns its length
+fn rlp_header(offset: u8, len: usize, sink: &mut dyn FnMut(&[u8])) -> usize {
+    if len <= 55 {
+        sink(&[offset + len as u8]);
+        return 1;
+    }
+    let len_bytes = (len as u64).to_be_bytes();
+    let skip = ((len as u64).leading_zeros() / 8) as usize;
+    sink(&[offset + 55 + (8 - skip) as u8]);
+    sink(&len_bytes[skip..]);
+
+    1 + 8 - skip
+}
+
+fn rlp_bytes(bytes: &[u8], sink: &mut dyn FnMut(&[u8])) -> usize {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        sink(bytes);
+        return 1;
+    }
+    let header_len = rlp_header(0x80, bytes.len(), sink);
+    sink(bytes);
+
+    header_len + bytes.len()
+}
+
+/// Integers are big-endian without leading zeroes, zero is the empty string
+fn rlp_integer(value: &U256, sink: &mut dyn FnMut(&[u8])) -> usize {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|byte| **byte == 0).count();
+
+    rlp_bytes(&bytes[skip..], sink)
+}
+
+/// Splits off the first item, returns its payload, whether it is a list, and the rest
+fn rlp_item(input: &[u8]) -> Option<(&[u8], bool, &[u8])> {
+    let (&prefix, rest) = input.split_first()?;
+    let (is_list, len) = match prefix {
+        0x00..=0x7f => return Some((&input[..1], false, rest)),
+        0x80..=0xbf => (false, prefix - 0x80),
+        0xc0..=0xff => (true, prefix - 0xc0),
+    };
+    let (len, rest) = if len <= 55 {
+        (len as usize, rest)
+    } else {
+        let len_len = (len - 55) as usize;
+        if len_len > 8 || rest.len() < len_len {
+            return None;
+        }
+        let (len_bytes, rest) = rest.split_at(len_len);
+        let len = len_bytes
+            .iter()
+            .fold(0u64, |len, byte| (len << 8) | *byte as u64);
+        (usize::try_from(len).ok()?, rest)
+    };
+    if rest.len() < len {
+        return None;
+    }
+    let (payload, rest) = rest.split_at(len);
+
+    Some((payload, is_list, rest))
+}
+
+/// Payloads of a list of exactly `N` strings
+fn rlp_strings<const N: usize>(encoding: &[u8]) -> Option<[&[u8]; N]> {
+    let (mut items, true, []) = rlp_item(encoding)? else {
+        return None;
+    };
+    let mut strings = [&[][..]; N];
+    for string in strings.iter_mut() {
+        let (payload, false, rest) = rlp_item(items)? else {
+            return None;
+        };
+        *string = payload;
+        items = rest;
+    }
+
+    items.is_empty().then_some(strings)
+}
+
+fn rlp_u256(payload: &[u8]) -> Option<U256> {
+    let mut bytes = [0u8; 32];
+    let start = bytes.len().checked_sub(payload.len())?;
+    bytes[start..].copy_from_slice(payload);
+
+    Some(U256::from_be_bytes(&bytes))
+}
+
+fn rlp_u64(payload: &[u8]) -> Option<u64> {
+    if payload.len() > 8 {
+        return None;
+    }
+
+    rlp_u256(payload)?.as_u64()
+}
+
+impl System {
+    /// Recovers the sender from the signature, validates and increments its nonce, buys gas,
+    /// and performs the top level call.
+    /// Nonce increment and fee payment are done outside of the call frame, so they persist even
+    /// if the transaction itself reverts. An invalid transaction leaves no trace in the state
+    pub fn execute_transaction(
+        &mut self,
+        transaction: &Transaction,
+    ) -> Result<TransactionResult, SystemError> {
+        let snapshot = self.snapshot();
+        let result = self.run_transaction(transaction);
+        if result.is_err() {
+            self.revert_to_snapshot(snapshot);
+        }
+        self.finish_transaction();
+
+        result
+    }
+
+    fn run_transaction(
+        &mut self,
+        transaction: &Transaction,
+    ) -> Result<TransactionResult, SystemError> {
+        let sender = transaction.recover_sender(self.block.chain_id)?;
+        if transaction.gas_limit > self.block.gas_limit {
+            return Err(SystemError::GasLimitTooHigh);
+        }
+        if transaction.gas_price < self.block.base_fee {
+            return Err(SystemError::GasPriceTooLow);
+        }
+        let expected_nonce = self.accounts.get(&sender)?.nonce;
+        if expected_nonce != transaction.nonce {
+            return Err(SystemError::InvalidNonce);
+        }
+        let intrinsic_cost = transaction.intrinsic_cost();
+        if intrinsic_cost > transaction.gas_limit {
+            return Err(SystemError::OutOfResources);
+        }
+        // no balance can pay for a fee that doesn't fit into a word
+        let max_fee = U256::from_u64(transaction.gas_limit)
+            .checked_mul(&transaction.gas_price)
+            .ok_or(SystemError::InsufficientBalance)?;
+        self.accounts.update(&sender, |account| {
+            account.balance = account
+                .balance
+                .checked_sub(&max_fee)
+                .ok_or(SystemError::InsufficientBalance)?;
+            // the value must be affordable as well, as in Ethereum
+            if account.balance < transaction.value {
+                return Err(SystemError::InsufficientBalance);
+            }
+            Ok(())
+        })?;
+        // creations take the nonce in `deploy`, as the address is derived from it
+        if let TransactionKind::Call(_) = transaction.kind {
+            let _ = self.accounts.increment_nonce(&sender)?;
+        }
+
+        self.transaction = TransactionContext {
+            origin: sender,
+            gas_price: transaction.gas_price,
+        };
+        self.refund = 0;
+        let _ = self.accounts.touch(&sender)?;
+        if let TransactionKind::Call(to) = transaction.kind {
+            let _ = self.accounts.touch(&to)?;
+        }
+        let coinbase = self.block.coinbase;
+        let _ = self.accounts.touch(&coinbase)?;
+        for address in self.precompiles.addresses() {
+            let _ = self.accounts.touch(&address)?;
+        }
+
+        let mut resources = Resources::new(transaction.gas_limit - intrinsic_cost);
+        let status = match transaction.kind {
+            TransactionKind::Call(to) => self.call(
+                &CallRequest::new(sender, to, transaction.value, transaction.calldata),
+                &mut resources,
+            ),
+            TransactionKind::Create | TransactionKind::Deploy { .. } => {
+                let request = transaction.deployment_request(sender);
+                self.deploy(&request, &mut resources)
+                    .map(|result| result.status)
+            }
+        };
+        let status = match status {
+            Ok(status) => status,
+            Err(SystemError::InsufficientBalance) => ExecutionStatus::Failure,
+            // the deployer took the nonce, and the attempt costs all of the gas
+            Err(SystemError::InvalidCode | SystemError::AddressCollision) => {
+                resources.burn();
+                ExecutionStatus::Failure
+            }
+            Err(error) => return Err(error),
+        };
+
+        let mut gas_used = transaction.gas_limit - resources.remaining();
+        if status == ExecutionStatus::Success && self.refund > 0 {
+            gas_used -= core::cmp::min(self.refund as u64, gas_used / MAX_REFUND_QUOTIENT);
+        }
+
+        // return the unused gas, and pay for the used one. Both are parts of `max_fee`
+        let unused_fee =
+            U256::from_u64(transaction.gas_limit - gas_used).wrapping_mul(&transaction.gas_price);
+        let used_fee = max_fee.wrapping_sub(&unused_fee);
+        for (address, fee) in [(sender, unused_fee), (coinbase, used_fee)] {
+            self.accounts.update(&address, |account| {
+                account.balance = account
+                    .balance
+                    .checked_add(&fee)
+                    .ok_or(SystemError::BalanceOverflow)?;
+                Ok(())
+            })?;
+        }
+
+        Ok(TransactionResult { status, gas_used })
+    }
+
+    fn finish_transaction(&mut self) {
+        self.accounts.finish_transaction();
+        self.storage.finish_transaction();
+        self.transient_storage.finish_transaction();
+        self.refund = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex<const N: usize>(digits: &str) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).unwrap();
+        }
+
+        bytes
+    }
+
+    fn word(digits: &str) -> U256 {
+        U256::from_be_bytes(&hex(digits))
+    }
+
+    /// geth's TestTransactionSigHash/TestRecipientNormal, signed before EIP-155
+    fn homestead() -> Transaction<'static> {
+        Transaction {
+            kind: TransactionKind::Call(Address::from_evm_address(&hex(
+                "b94f5374fce5edbc8e2a8697c15331677e6ebf0b",
+            ))),
+            value: U256::from_u64(10),
+            nonce: 3,
+            gas_limit: 2000,
+            gas_price: U256::from_u64(1),
+            calldata: &[0x55, 0x44],
+            signature: Signature {
+                v: 28,
+                r: word("98ff921201554726367d2be8c804a7ff89ccf285ebc57dff8ae4c44b9c19ac4a"),
+                s: word("8887321be575c8095f789dd4c743dfe42c1820f9231f98a962b210e3ac2452a3"),
+            },
+        }
+    }
+
+    /// Example of EIP-155, signed with the key 0x4646..46
+    const EIP155: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    /// Deployment of a WASM module with one byte of constants, signed with the key of `EIP155`
+    const DEPLOYMENT: &str = "7ff857018001830186a08003880061736d010000002a8001a0d47644539acec3da5e3ecf5fe8863c628a9c97e8b71e9ea9167a6f4f83c03c32a029020b7803f3099cb471356f8070aebaaed0d998f0ced790c90d1904ba89dd2c";
+
+    fn sender() -> Address {
+        Address::from_evm_address(&hex("5ba306ae3650c72c3586da6f1dbac3c9fa7e529e"))
+    }
+
+    /// The vector predates EIP-2, and its `s` is in the upper half of the order
+    #[test]
+    fn malleable_signature() {
+        let transaction = homestead();
+        assert_eq!(
+            transaction.signing_hash(None),
+            hex("fe7a79529ed5f7c3375d06b26b186a8644e0e16c373d7a12be41c62d6042b77a")
+        );
+        assert_eq!(
+            transaction.recover_sender(1),
+            Err(SystemError::InvalidSignature)
+        );
+
+        // the same signature with the negated `s` and point is accepted
+        let order = word("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+        let signature = Signature {
+            v: 27,
+            r: transaction.signature.r,
+            s: order.wrapping_sub(&transaction.signature.s),
+        };
+        let transaction = Transaction {
+            signature,
+            ..homestead()
+        };
+        assert_eq!(transaction.recover_sender(1), Ok(sender()));
+        let transaction = Transaction {
+            signature: Signature { v: 28, ..signature },
+            ..homestead()
+        };
+        assert_ne!(transaction.recover_sender(1), Ok(sender()));
+    }
+
+    #[test]
+    fn eip155_signature() {
+        let encoding: [u8; 110] = hex(EIP155);
+        let transaction = Transaction::decode(&encoding).unwrap();
+        assert_eq!(transaction.nonce, 9);
+        assert_eq!(transaction.gas_limit, 21000);
+        assert_eq!(transaction.gas_price, U256::from_u64(20_000_000_000));
+        assert_eq!(transaction.value, U256::from_u64(1_000_000_000_000_000_000));
+        assert_eq!(transaction.signature.v, 37);
+        assert_eq!(
+            transaction.signing_hash(Some(1)),
+            hex("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53")
+        );
+        assert_eq!(
+            transaction.recover_sender(1),
+            Ok(Address::from_evm_address(&hex(
+                "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"
+            )))
+        );
+        // replayed on another chain
+        assert_eq!(
+            transaction.recover_sender(2),
+            Err(SystemError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn deployment_transaction() {
+        let encoding: [u8; 90] = hex(DEPLOYMENT);
+        let transaction = Transaction::decode(&encoding).unwrap();
+        assert_eq!(
+            transaction.kind,
+            TransactionKind::Deploy {
+                chain_id: 1,
+                code_type: CodeType::Wasm,
+                code: b"\0asm\x01\0\0\0",
+                constants: &[0x2a],
+            }
+        );
+        assert_eq!(transaction.gas_limit, 100_000);
+        assert_eq!(
+            transaction.intrinsic_cost(),
+            21000 + 80 + 16 + 32000 + 2 * 2
+        );
+        assert_eq!(
+            transaction.signing_hash(None),
+            hex("3b12aa9ad0939b8889087ba5cbebd4727423d915755b8efa1ffd8deffd10f332")
+        );
+        assert_eq!(
+            transaction.recover_sender(1),
+            Ok(Address::from_evm_address(&hex(
+                "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"
+            )))
+        );
+        assert_eq!(
+            transaction.recover_sender(2),
+            Err(SystemError::InvalidSignature)
+        );
+        let request = transaction.deployment_request(sender());
+        assert_eq!(request.code, b"\0asm\x01\0\0\0");
+        assert_eq!(request.constants, &[0x2a]);
+        assert!(request.constructor_calldata.is_empty());
+
+        // the code type must be one of a contract
+        let mut empty_code_type = encoding;
+        empty_code_type[11] = 0x80;
+        assert!(Transaction::decode(&empty_code_type).is_err());
+    }
+
+    #[test]
+    fn tampered_transaction() {
+        let mut transaction = homestead();
+        transaction.signature.v = 27;
+        transaction.signature.s =
+            word("7778cde41a8a37f6a087622b38bc201a8e96bbed8c2907925d204da92411ee9e");
+        transaction.value = U256::from_u64(11);
+        assert_ne!(transaction.recover_sender(1), Ok(sender()));
+    }
+
+    #[test]
+    fn invalid_encoding() {
+        let encoding: [u8; 110] = hex(EIP155);
+        // truncated, with trailing bytes, and with the payload of `to` cut short
+        assert!(Transaction::decode(&encoding[..109]).is_err());
+        let mut extended = [0u8; 111];
+        extended[..110].copy_from_slice(&encoding);
+        assert!(Transaction::decode(&extended).is_err());
+        let mut short_to = encoding;
+        short_to[12] = 0x80 + 19;
+        assert!(Transaction::decode(&short_to).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+/// Address in the common 32-byte address space shared by all the contract kinds
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Address(pub [u8; 32]);
+
+impl Address {
+    pub const ZERO: Self = Self([0u8; 32]);
+
+    /// EVM addresses are embedded into the lowest 20 bytes (big-endian)
+    #[must_use]
+    pub const fn from_evm_address(address: &[u8; 20]) -> Self {
+        let mut result = [0u8; 32];
+        let mut i = 0;
+        while i < 20 {
+            result[12 + i] = address[i];
+            i += 1;
+        }
+
+        Self(result)
+    }
+
+    #[must_use]
+    pub const fn evm_address(&self) -> [u8; 20] {
+        let mut result = [0u8; 20];
+        let mut i = 0;
+        while i < 20 {
+            result[i] = self.0[12 + i];
+            i += 1;
+        }
+
+        result
+    }
+
+    /// Packs the address into words in the form that the oracle expects
+    #[must_use]
+    pub const fn to_words(&self) -> [u32; 8] {
+        bytes32_to_words(&self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Bytes32(pub [u8; 32]);
+
+impl Bytes32 {
+    pub const ZERO: Self = Self([0u8; 32]);
+
+    #[must_use]
+    pub const fn to_words(&self) -> [u32; 8] {
+        bytes32_to_words(&self.0)
+    }
+
+    #[must_use]
+    pub const fn from_words(words: &[u32; 8]) -> Self {
+        Self(words_to_bytes32(words))
+    }
+}
+
+#[must_use]
+pub const fn bytes32_to_words(bytes: &[u8; 32]) -> [u32; 8] {
+    let mut result = [0u32; 8];
+    let mut i = 0;
+    while i < 8 {
+        result[i] = u32::from_le_bytes([
+            bytes[4 * i],
+            bytes[4 * i + 1],
+            bytes[4 * i + 2],
+            bytes[4 * i + 3],
+        ]);
+        i += 1;
+    }
+
+    result
+}
+
+#[must_use]
+pub const fn words_to_bytes32(words: &[u32; 8]) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    let mut i = 0;
+    while i < 8 {
+        let word = words[i].to_le_bytes();
+        result[4 * i] = word[0];
+        result[4 * i + 1] = word[1];
+        result[4 * i + 2] = word[2];
+        result[4 * i + 3] = word[3];
+        i += 1;
+    }
+
+    result
+}
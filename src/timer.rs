@@ -0,0 +1,45 @@
+// Deadline of the code that runs in U-mode, on the machine timer of the CLINT. The machine ticks
+// mtime once per retired instruction, so arming the timer `budget` ticks ahead interrupts a
+// contract that spent its fuel without reaching an exit. Fuel is still charged by minstret, the
+// timer only decides when the kernel gets the hart back: if it fires early, the contract is
+// charged and resumed, if late, it is charged more than it had, the outcome is the same. The
+// timer is disarmed whenever the kernel runs, so the interrupt only ever comes from U-mode
+
+/// Base of the CLINT, and the offsets of mtimecmp of hart 0 and of mtime
+pub const CLINT_BASE: usize = 0x0200_0000;
+const MTIMECMP: usize = CLINT_BASE + 0x4000;
+const MTIME: usize = CLINT_BASE + 0xbff8;
+
+/// Raises the machine timer interrupt after `budget` more ticks
+pub fn arm(budget: u64) {
+    write_mtimecmp(read_mtime().saturating_add(budget).saturating_add(1));
+}
+
+/// Moves the deadline past the end of time, which also clears a pending interrupt
+pub fn disarm() {
+    write_mtimecmp(u64::MAX);
+}
+
+#[must_use]
+fn read_mtime() -> u64 {
+    let mtime = core::ptr::from_exposed_addr::<u32>(MTIME);
+    loop {
+        unsafe {
+            let high = mtime.add(1).read_volatile();
+            let low = mtime.read_volatile();
+            if mtime.add(1).read_volatile() == high {
+                return (high as u64) << 32 | low as u64;
+            }
+        }
+    }
+}
+
+fn write_mtimecmp(value: u64) {
+    let mtimecmp = core::ptr::from_exposed_addr_mut::<u32>(MTIMECMP);
+    // the high half goes to the maximum first, so no intermediate value is in the past
+    unsafe {
+        mtimecmp.add(1).write_volatile(u32::MAX);
+        mtimecmp.write_volatile(value as u32);
+        mtimecmp.add(1).write_volatile((value >> 32) as u32);
+    }
+}
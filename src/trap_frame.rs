@@ -1,5 +1,101 @@
+use riscv::register::mstatus::MPP;
+
+/// Bytes in a register and in a CSR, `REGBYTES` of the assembly
+pub const XLEN_BYTES: usize = core::mem::size_of::<usize>();
+/// Words `machine_default_start_trap` reserves on the exception stack, keeps it 16-aligned
+pub const TRAP_FRAME_WORDS: usize = 40;
+pub const TRAP_FRAME_SIZE: usize = TRAP_FRAME_WORDS * XLEN_BYTES;
+/// Context id of the traps taken by the kernel itself
+pub const KERNEL_CONTEXT_ID: u32 = 0;
+
+const MCAUSE_INTERRUPT: usize = 1 << (usize::BITS - 1);
+const MSTATUS_MPP_SHIFT: u32 = 11;
+const MSTATUS_MPP: usize = 0b11 << MSTATUS_MPP_SHIFT;
+#[cfg(target_arch = "riscv64")]
+const MSTATUS_UXL: usize = 0b11 << 32;
+#[cfg(target_arch = "riscv64")]
+const MSTATUS_UXL_32: usize = 0b01 << 32;
+
+/// Everything the trap entry saves. Registers and mstatus are restored from here on return,
+/// so that traps of the handler itself don't clobber them, the new pc is what the handler
+/// returns, and mcause and mtval are only a snapshot. `context_id` is the `UserContext` that
+/// trapped, `KERNEL_CONTEXT_ID` for the kernel. Offsets are in words
 #[repr(C)]
-#[derive(Debug)]
-pub struct MachineTrapFrame {
-    pub registers: [u32; 32],
+#[derive(Clone, Copy, Debug)]
+pub struct TrapFrame {
+    pub registers: [usize; 32], // 0..32
+    pub mepc: usize,            // 32
+    pub mcause: usize,          // 33
+    pub mtval: usize,           // 34
+    pub mstatus: usize,         // 35
+    pub context_id: u32,        // 36
+    _reserved: [usize; 3],      // 37..40
+}
+
+const _: () = assert!(core::mem::size_of::<TrapFrame>() == TRAP_FRAME_SIZE);
+
+impl TrapFrame {
+    #[must_use]
+    #[inline(always)]
+    pub const fn is_interrupt(&self) -> bool {
+        self.mcause & MCAUSE_INTERRUPT != 0
+    }
+
+    /// Exception or interrupt code, without the interrupt bit
+    #[must_use]
+    #[inline(always)]
+    pub const fn cause(&self) -> usize {
+        self.mcause & !MCAUSE_INTERRUPT
+    }
+
+    /// Mode the trap was taken from
+    #[must_use]
+    #[inline(always)]
+    pub const fn previous_mode(&self) -> MPP {
+        match (self.mstatus & MSTATUS_MPP) >> MSTATUS_MPP_SHIFT {
+            0b00 => MPP::User,
+            0b01 => MPP::Supervisor,
+            _ => MPP::Machine,
+        }
+    }
+
+    /// Mode `mret` goes to on return
+    #[inline(always)]
+    pub fn set_previous_mode(&mut self, mode: MPP) {
+        self.mstatus = (self.mstatus & !MSTATUS_MPP) | (mode as usize) << MSTATUS_MPP_SHIFT;
+    }
+
+    /// U-mode runs RV32 code after return. UXL is WARL, harts that have it fixed to 64 run
+    /// the user code as RV64
+    #[cfg(target_arch = "riscv64")]
+    #[inline(always)]
+    pub fn set_user_xlen32(&mut self) {
+        self.mstatus = (self.mstatus & !MSTATUS_UXL) | MSTATUS_UXL_32;
+    }
+}
+
+/// Dumps the registers and CSRs of a given trap frame. This is NOT the
+/// current CPU registers!
+pub fn dump_registers(frame: &TrapFrame) {
+    use crate::{print, println};
+
+    let width = XLEN_BYTES * 2;
+    println!(
+        "   mepc:{:0w$x}   mcause:{:0w$x}   mtval:{:0w$x}   mstatus:{:0w$x}   context:{}",
+        frame.mepc,
+        frame.mcause,
+        frame.mtval,
+        frame.mstatus,
+        frame.context_id,
+        w = width
+    );
+    print!("   ");
+    for i in 1..32 {
+        if i % 4 == 0 {
+            println!();
+            print!("   ");
+        }
+        print!("x{:2}:{:0w$x}   ", i, frame.registers[i], w = width);
+    }
+    println!();
 }
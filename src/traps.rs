@@ -0,0 +1,68 @@
+use crate::trap_frame::TrapFrame;
+
+// Trap dispatch. Subsystems register handlers for the causes they deal with, and the trap entry
+// calls the one of mcause. A handler returns the pc to `mret` to, or `None` if the trap is not
+// its to handle after all (e.g. a misaligned access out of bounds), in which case the trap is
+// unhandled: it is a fault of the user code, or fatal for the kernel. mtvec is vectored, but all
+// the vectors share the entry, as it saves the frame first and looks at mcause anyway
+
+pub const EXCEPTION_INSTRUCTION_MISALIGNED: usize = 0;
+pub const EXCEPTION_INSTRUCTION_ACCESS_FAULT: usize = 1;
+pub const EXCEPTION_ILLEGAL_INSTRUCTION: usize = 2;
+pub const EXCEPTION_BREAKPOINT: usize = 3;
+pub const EXCEPTION_LOAD_MISALIGNED: usize = 4;
+pub const EXCEPTION_LOAD_ACCESS_FAULT: usize = 5;
+pub const EXCEPTION_STORE_MISALIGNED: usize = 6;
+pub const EXCEPTION_STORE_ACCESS_FAULT: usize = 7;
+pub const EXCEPTION_USER_ECALL: usize = 8;
+pub const EXCEPTION_SUPERVISOR_ECALL: usize = 9;
+pub const EXCEPTION_MACHINE_ECALL: usize = 11;
+pub const EXCEPTION_INSTRUCTION_PAGE_FAULT: usize = 12;
+pub const EXCEPTION_LOAD_PAGE_FAULT: usize = 13;
+pub const EXCEPTION_STORE_PAGE_FAULT: usize = 15;
+pub const NUM_EXCEPTIONS: usize = 16;
+
+pub const INTERRUPT_MACHINE_SOFT: usize = 3;
+pub const INTERRUPT_MACHINE_TIMER: usize = 7;
+pub const INTERRUPT_MACHINE_EXTERNAL: usize = 11;
+/// Vectors of the mtvec table, the first one is for the exceptions
+pub const NUM_INTERRUPTS: usize = 16;
+
+/// Returns the pc to return to, `None` leaves the trap unhandled
+pub type TrapHandler = fn(&mut TrapFrame) -> Option<usize>;
+
+static mut EXCEPTION_HANDLERS: [Option<TrapHandler>; NUM_EXCEPTIONS] = [None; NUM_EXCEPTIONS];
+static mut INTERRUPT_HANDLERS: [Option<TrapHandler>; NUM_INTERRUPTS] = [None; NUM_INTERRUPTS];
+
+/// Handles the exception `cause` with `handler`, replacing the one registered before
+pub fn register_exception(cause: usize, handler: TrapHandler) {
+    unsafe {
+        if let Some(slot) = EXCEPTION_HANDLERS.get_mut(cause) {
+            *slot = Some(handler);
+        }
+    }
+}
+
+/// Handles the interrupt `cause` with `handler`, replacing the one registered before. Interrupts
+/// still have to be enabled in mie
+pub fn register_interrupt(cause: usize, handler: TrapHandler) {
+    unsafe {
+        if let Some(slot) = INTERRUPT_HANDLERS.get_mut(cause) {
+            *slot = Some(handler);
+        }
+    }
+}
+
+/// Calls the handler of the trap, `None` if there is none or it left the trap unhandled
+pub fn dispatch(trap_frame: &mut TrapFrame) -> Option<usize> {
+    let cause = trap_frame.cause();
+    let handler = unsafe {
+        if trap_frame.is_interrupt() {
+            INTERRUPT_HANDLERS.get(cause).copied().flatten()
+        } else {
+            EXCEPTION_HANDLERS.get(cause).copied().flatten()
+        }
+    };
+
+    handler.and_then(|handler| handler(trap_frame))
+}
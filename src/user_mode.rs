@@ -0,0 +1,158 @@
+use crate::cpu::{gp, Registers};
+use crate::trap_frame::{TrapFrame, KERNEL_CONTEXT_ID};
+
+use riscv::register::mstatus::MPP;
+
+// Running untrusted code in U-mode. The kernel enters it with an `ecall` of its own: the trap
+// handler stashes the kernel registers, puts the user ones into the trap frame and returns into
+// U-mode. An `ecall` with `EXIT_CALL_BIT` in a7, or any other exception (user code has no
+// syscalls), does the opposite, so that `run` returns as an ordinary function. Only one user
+// context runs at a time, but the kernel may run another one while handling the exit of the first. User code is
+// RV32 on both kernels, an RV64 one runs U-mode with UXL of 32 bits
+
+/// a7 of the kernel `ecall` that enters U-mode, a0 holds the pointer to the `UserContext`
+pub const ENTER_USER_MODE: u32 = 0x7fff_fff0;
+/// User `ecall` with this bit in a7 returns control to the kernel
+pub const EXIT_CALL_BIT: u32 = 1 << 31;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UserExit {
+    /// `ecall` with the exit bit, carries the rest of a7
+    Call(u32),
+    Exception {
+        cause: usize,
+        tval: usize,
+    },
+    /// The timer armed by `Context::resume` fired, the code continues from where it was
+    Preempted,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct UserContext {
+    pub registers: [u32; 32],
+    /// Where to continue from: after the `ecall` for calls, at the faulting instruction otherwise
+    pub pc: u32,
+    pub exit: UserExit,
+    /// Tells the contexts apart in the trap frames, assigned when the context first runs
+    pub id: u32,
+}
+
+impl UserContext {
+    #[must_use]
+    pub const fn new(pc: u32) -> Self {
+        Self {
+            registers: [0; 32],
+            pc,
+            exit: UserExit::Call(0),
+            id: KERNEL_CONTEXT_ID,
+        }
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn register(&self, register: Registers) -> u32 {
+        self.registers[gp(register)]
+    }
+
+    #[inline(always)]
+    pub fn set_register(&mut self, register: Registers, value: u32) {
+        let index = gp(register);
+        if index != 0 {
+            self.registers[index] = value;
+        }
+    }
+}
+
+/// Kernel side of the switch: its registers and where `run` continues
+struct KernelContext {
+    registers: [usize; 32],
+    pc: usize,
+    user: *mut UserContext,
+}
+
+static mut KERNEL: KernelContext = KernelContext {
+    registers: [0; 32],
+    pc: 0,
+    user: core::ptr::null_mut(),
+};
+
+/// Last id given to a context
+static mut LAST_CONTEXT_ID: u32 = KERNEL_CONTEXT_ID;
+
+/// Runs the user code until it exits, PMP regions it needs must be mapped by the caller
+pub fn run(context: &mut UserContext) -> UserExit {
+    let context = context as *mut UserContext;
+    // all registers come back as they were, as the trap handler restores the whole frame
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            in("a0") context,
+            in("a7") ENTER_USER_MODE,
+            options(nostack),
+        );
+        (*context).exit
+    }
+}
+
+/// Handles the kernel `ecall` of `run`, returns the user pc to `mret` to
+pub fn enter(trap_frame: &mut TrapFrame, epc: usize) -> usize {
+    unsafe {
+        let user = trap_frame.registers[gp(Registers::A0)] as *mut UserContext;
+        KERNEL.registers = trap_frame.registers;
+        KERNEL.pc = epc.wrapping_add(4);
+        KERNEL.user = user;
+        if (*user).id == KERNEL_CONTEXT_ID {
+            LAST_CONTEXT_ID = LAST_CONTEXT_ID.wrapping_add(1).max(KERNEL_CONTEXT_ID + 1);
+            (*user).id = LAST_CONTEXT_ID;
+        }
+        // RV64 keeps 32-bit values sign extended in the registers
+        for (register, value) in trap_frame.registers.iter_mut().zip((*user).registers) {
+            *register = value as i32 as usize;
+        }
+        trap_frame.registers[gp(Registers::Zero)] = 0;
+        trap_frame.set_previous_mode(MPP::User);
+        #[cfg(target_arch = "riscv64")]
+        trap_frame.set_user_xlen32();
+
+        (*user).pc as usize
+    }
+}
+
+/// Saves the user registers and returns to the kernel pc that continues `run`
+pub fn leave(trap_frame: &mut TrapFrame, pc: usize, exit: UserExit) -> usize {
+    unsafe {
+        let user = KERNEL.user;
+        if user.is_null() {
+            crate::rust_abort();
+        }
+        for (value, register) in (*user).registers.iter_mut().zip(trap_frame.registers) {
+            *value = register as u32;
+        }
+        (*user).pc = pc as u32;
+        (*user).exit = exit;
+        KERNEL.user = core::ptr::null_mut();
+        trap_frame.registers = KERNEL.registers;
+        trap_frame.set_previous_mode(MPP::Machine);
+
+        KERNEL.pc
+    }
+}
+
+/// Whether the exception came from U-mode code entered by `run`
+#[must_use]
+pub fn is_active() -> bool {
+    unsafe { !KERNEL.user.is_null() }
+}
+
+/// Id of the context in U-mode, `KERNEL_CONTEXT_ID` if there is none
+#[must_use]
+pub fn active_context_id() -> u32 {
+    unsafe {
+        if KERNEL.user.is_null() {
+            KERNEL_CONTEXT_ID
+        } else {
+            (*KERNEL.user).id
+        }
+    }
+}
@@ -0,0 +1,12 @@
+// Fuel schedule of the WASM interpreter, in the kernel resource ticks. Access to the state and
+// hashing are charged by `system::costs`, and calls of the precompiles by
+// `crate::precompiles`
+
+pub const INSTRUCTION: u64 = 1;
+pub const MEMORY_PAGE: u64 = 8192;
+
+pub const HOST_CALL: u64 = 10;
+
+pub const EVENT: u64 = 375;
+pub const EVENT_TOPIC: u64 = 375;
+pub const EVENT_DATA_BYTE: u64 = 8;
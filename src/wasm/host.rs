@@ -0,0 +1,491 @@
+use super::interpreter::{Executor, ExitReason};
+use super::opcodes::{TYPE_I32, TYPE_I64};
+use super::{fuel, WasmError};
+use crate::bigint::U256;
+use crate::crypto::keccak256;
+use crate::system::account::CodeType;
+use crate::system::call::CallRequest;
+use crate::system::costs;
+use crate::system::deployment::{DeploymentRequest, DeploymentScheme};
+use crate::system::events::MAX_TOPICS;
+use crate::system::interpreter::ExecutionStatus;
+use crate::system::types::{Address, Bytes32};
+use crate::system::{System, SystemError};
+
+// Host functions are imported from the "env" module. Pointers are offsets in the linear memory,
+// addresses and words are 32 bytes (words are big-endian, as in Solidity ABI).
+//
+//  calldata_size() -> i32
+//  calldata_copy(dst, offset, len)
+//  returndata_size() -> i32
+//  returndata_copy(dst, offset, len)              traps if out of bounds of the returndata
+//  storage_read(key_ptr, dst)
+//  storage_write(key_ptr, value_ptr)
+//  transient_read(key_ptr, dst)
+//  transient_write(key_ptr, value_ptr)
+//  emit_event(topics_ptr, num_topics, data_ptr, data_len)
+//  call(address_ptr, value_ptr, calldata_ptr, calldata_len, fuel: i64) -> i32
+//                                                 0 on success, 1 on revert, 2 on failure
+//  deploy(request_ptr, fuel: i64, address_dst) -> i32
+//                                                 request is nine i32: code type, code_ptr,
+//                                                 code_len, constants_ptr, constants_len,
+//                                                 calldata_ptr, calldata_len, value_ptr and
+//                                                 salt_ptr (0 for an address from the nonce),
+//                                                 the address is zeroes unless deployed, the
+//                                                 result is as for call
+//  return(ptr, len)                               finishes the execution successfully
+//  revert(ptr, len)                               finishes the execution with revert
+//  caller(dst)
+//  address(dst)
+//  call_value(dst)
+//  balance(address_ptr, dst)
+//  fuel_left() -> i64
+//  keccak256(ptr, len, dst)
+//  constants_size() -> i32
+//  constants_copy(dst, offset, len)               zeroes past the end of the constants
+//  constants_hash(address_ptr, dst)
+//
+// Precompiles are reached with `call` at their addresses, as from the EVM
+
+const I32: u8 = TYPE_I32;
+const I64: u8 = TYPE_I64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HostFunction {
+    CalldataSize,
+    CalldataCopy,
+    ReturndataSize,
+    ReturndataCopy,
+    StorageRead,
+    StorageWrite,
+    TransientRead,
+    TransientWrite,
+    EmitEvent,
+    Call,
+    Deploy,
+    Return,
+    Revert,
+    Caller,
+    Address,
+    CallValue,
+    Balance,
+    FuelLeft,
+    Keccak256,
+    ConstantsSize,
+    ConstantsCopy,
+    ConstantsHash,
+}
+
+impl HostFunction {
+    pub const ALL: [Self; 22] = [
+        Self::CalldataSize,
+        Self::CalldataCopy,
+        Self::ReturndataSize,
+        Self::ReturndataCopy,
+        Self::StorageRead,
+        Self::StorageWrite,
+        Self::TransientRead,
+        Self::TransientWrite,
+        Self::EmitEvent,
+        Self::Call,
+        Self::Deploy,
+        Self::Return,
+        Self::Revert,
+        Self::Caller,
+        Self::Address,
+        Self::CallValue,
+        Self::Balance,
+        Self::FuelLeft,
+        Self::Keccak256,
+        Self::ConstantsSize,
+        Self::ConstantsCopy,
+        Self::ConstantsHash,
+    ];
+
+    #[must_use]
+    pub const fn name(&self) -> &'static [u8] {
+        match self {
+            Self::CalldataSize => b"calldata_size",
+            Self::CalldataCopy => b"calldata_copy",
+            Self::ReturndataSize => b"returndata_size",
+            Self::ReturndataCopy => b"returndata_copy",
+            Self::StorageRead => b"storage_read",
+            Self::StorageWrite => b"storage_write",
+            Self::TransientRead => b"transient_read",
+            Self::TransientWrite => b"transient_write",
+            Self::EmitEvent => b"emit_event",
+            Self::Call => b"call",
+            Self::Deploy => b"deploy",
+            Self::Return => b"return",
+            Self::Revert => b"revert",
+            Self::Caller => b"caller",
+            Self::Address => b"address",
+            Self::CallValue => b"call_value",
+            Self::Balance => b"balance",
+            Self::FuelLeft => b"fuel_left",
+            Self::Keccak256 => b"keccak256",
+            Self::ConstantsSize => b"constants_size",
+            Self::ConstantsCopy => b"constants_copy",
+            Self::ConstantsHash => b"constants_hash",
+        }
+    }
+
+    /// Encoding of the params and results, as in the type section
+    #[must_use]
+    pub const fn signature(&self) -> &'static [u8] {
+        match self {
+            Self::CalldataSize | Self::ReturndataSize | Self::ConstantsSize => &[0, 1, I32],
+            Self::CalldataCopy | Self::ReturndataCopy | Self::Keccak256 | Self::ConstantsCopy => {
+                &[3, I32, I32, I32, 0]
+            }
+            Self::StorageRead
+            | Self::StorageWrite
+            | Self::TransientRead
+            | Self::TransientWrite
+            | Self::Return
+            | Self::Revert
+            | Self::Balance
+            | Self::ConstantsHash => &[2, I32, I32, 0],
+            Self::EmitEvent => &[4, I32, I32, I32, I32, 0],
+            Self::Call => &[5, I32, I32, I32, I32, I64, 1, I32],
+            Self::Deploy => &[3, I32, I64, I32, 1, I32],
+            Self::Caller | Self::Address | Self::CallValue => &[1, I32, 0],
+            Self::FuelLeft => &[0, 1, I64],
+        }
+    }
+
+    #[must_use]
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|function| function.name() == name)
+    }
+}
+
+impl<'a, 'b> Executor<'a, 'b> {
+    /// Runs the host function with the arguments on the value stack. Returns `Some` if it
+    /// finished the execution
+    pub fn call_host(
+        &mut self,
+        system: &mut System,
+        function: HostFunction,
+    ) -> Result<Option<ExitReason>, WasmError> {
+        self.charge(fuel::HOST_CALL)?;
+        match function {
+            HostFunction::CalldataSize => self.push(self.frame.calldata.len() as u64)?,
+            HostFunction::CalldataCopy => {
+                let [destination, offset, len] = self.pop_args()?;
+                self.charge(costs::copy_cost(len))?;
+                // reading past the end gives zeroes, as CALLDATACOPY does
+                let calldata = self.frame.calldata;
+                let source = calldata.get(offset.min(calldata.len())..).unwrap_or(&[]);
+                let to_copy = len.min(source.len());
+                let destination = self.memory_slice_mut(destination, len)?;
+                destination[..to_copy].copy_from_slice(&source[..to_copy]);
+                destination[to_copy..].fill(0);
+            }
+            HostFunction::ReturndataSize => self.push(system.returndata.as_slice().len() as u64)?,
+            HostFunction::ReturndataCopy => {
+                let [destination, offset, len] = self.pop_args()?;
+                self.charge(costs::copy_cost(len))?;
+                let source = system
+                    .returndata
+                    .as_slice()
+                    .get(offset..offset.saturating_add(len))
+                    .ok_or(WasmError::ReturnDataOutOfBounds)?;
+                self.memory_slice_mut(destination, len)?
+                    .copy_from_slice(source);
+            }
+            HostFunction::StorageRead => {
+                let [key, destination] = self.pop_args()?;
+                let key = Bytes32(self.read_word(key)?);
+                let access = system.storage.read(&self.frame.address, &key)?;
+                self.charge(if access.was_warm {
+                    costs::WARM_ACCESS
+                } else {
+                    costs::COLD_SLOAD
+                })?;
+                self.write_bytes(destination, &access.value.0)?;
+            }
+            HostFunction::StorageWrite => {
+                let [key, value] = self.pop_args()?;
+                self.check_not_static()?;
+                let key = Bytes32(self.read_word(key)?);
+                let value = Bytes32(self.read_word(value)?);
+                if self.resources.remaining() <= costs::STORAGE_STIPEND {
+                    return Err(WasmError::OutOfFuel);
+                }
+                let access = system.storage.write(&self.frame.address, &key, &value)?;
+                let (cost, refund) =
+                    costs::storage_write_cost(&access.original, &access.value, &value);
+                let cold_cost = if access.was_warm {
+                    0
+                } else {
+                    costs::COLD_SLOAD
+                };
+                self.charge(cost + cold_cost)?;
+                system.refund += refund;
+            }
+            HostFunction::TransientRead => {
+                let [key, destination] = self.pop_args()?;
+                self.charge(costs::WARM_ACCESS)?;
+                let key = Bytes32(self.read_word(key)?);
+                let access = system.transient_storage.read(&self.frame.address, &key)?;
+                self.write_bytes(destination, &access.value.0)?;
+            }
+            HostFunction::TransientWrite => {
+                let [key, value] = self.pop_args()?;
+                self.check_not_static()?;
+                self.charge(costs::WARM_ACCESS)?;
+                let key = Bytes32(self.read_word(key)?);
+                let value = Bytes32(self.read_word(value)?);
+                let _ = system
+                    .transient_storage
+                    .write(&self.frame.address, &key, &value)?;
+            }
+            HostFunction::EmitEvent => {
+                let [topics_offset, num_topics, data_offset, data_len] = self.pop_args()?;
+                self.check_not_static()?;
+                if num_topics > MAX_TOPICS {
+                    return Err(WasmError::LimitExceeded);
+                }
+                self.charge(
+                    fuel::EVENT
+                        + fuel::EVENT_TOPIC * num_topics as u64
+                        + fuel::EVENT_DATA_BYTE * data_len as u64,
+                )?;
+                let mut topics = [Bytes32::ZERO; MAX_TOPICS];
+                for (index, topic) in topics[..num_topics].iter_mut().enumerate() {
+                    *topic = Bytes32(self.read_word(topics_offset.saturating_add(index * 32))?);
+                }
+                let data = self.memory_slice(data_offset, data_len)?;
+                system
+                    .events
+                    .emit(&self.frame.address, &topics[..num_topics], data)?;
+            }
+            HostFunction::Call => {
+                let requested_fuel = self.pop()?;
+                let [address, value, calldata_offset, calldata_len] = self.pop_args()?;
+                let status = self.call_contract(
+                    system,
+                    address,
+                    value,
+                    calldata_offset,
+                    calldata_len,
+                    requested_fuel,
+                )?;
+                self.push(status)?;
+            }
+            HostFunction::Deploy => {
+                let [destination] = self.pop_args()?;
+                let requested_fuel = self.pop()?;
+                let [request] = self.pop_args()?;
+                let (status, address) = self.deploy_contract(system, request, requested_fuel)?;
+                self.write_bytes(destination, &address.0)?;
+                self.push(status)?;
+            }
+            HostFunction::Return => {
+                let [offset, len] = self.pop_args()?;
+                self.memory_slice(offset, len)?;
+                return Ok(Some(ExitReason::Return(offset, len)));
+            }
+            HostFunction::Revert => {
+                let [offset, len] = self.pop_args()?;
+                self.memory_slice(offset, len)?;
+                return Ok(Some(ExitReason::Revert(offset, len)));
+            }
+            HostFunction::Caller => {
+                let [destination] = self.pop_args()?;
+                self.write_bytes(destination, &self.frame.caller.0)?;
+            }
+            HostFunction::Address => {
+                let [destination] = self.pop_args()?;
+                self.write_bytes(destination, &self.frame.address.0)?;
+            }
+            HostFunction::CallValue => {
+                let [destination] = self.pop_args()?;
+                self.write_bytes(destination, &self.frame.value.to_be_bytes())?;
+            }
+            HostFunction::Balance => {
+                let [address, destination] = self.pop_args()?;
+                let address = Address(self.read_word(address)?);
+                self.access_account(system, &address)?;
+                let balance = system.accounts.get(&address)?.balance;
+                self.write_bytes(destination, &balance.to_be_bytes())?;
+            }
+            HostFunction::FuelLeft => self.push(self.resources.remaining())?,
+            HostFunction::Keccak256 => {
+                let [source, len, destination] = self.pop_args()?;
+                self.charge(costs::keccak256_cost(len))?;
+                let hash = keccak256(self.memory_slice(source, len)?);
+                self.write_bytes(destination, &hash)?;
+            }
+            HostFunction::ConstantsSize => self.push(self.frame.constants.len() as u64)?,
+            HostFunction::ConstantsCopy => {
+                let [destination, offset, len] = self.pop_args()?;
+                self.charge(costs::copy_cost(len))?;
+                let constants = self.frame.constants;
+                let source = constants.get(offset.min(constants.len())..).unwrap_or(&[]);
+                let to_copy = len.min(source.len());
+                let destination = self.memory_slice_mut(destination, len)?;
+                destination[..to_copy].copy_from_slice(&source[..to_copy]);
+                destination[to_copy..].fill(0);
+            }
+            HostFunction::ConstantsHash => {
+                let [address, destination] = self.pop_args()?;
+                let address = Address(self.read_word(address)?);
+                self.access_account(system, &address)?;
+                let hash = system.constants_hash(&address)?;
+                self.write_bytes(destination, &hash.0)?;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Pops i32 arguments, the last one is on top of the stack
+    fn pop_args<const N: usize>(&mut self) -> Result<[usize; N], WasmError> {
+        let mut args = [0usize; N];
+        for arg in args.iter_mut().rev() {
+            *arg = self.pop_u32()? as usize;
+        }
+
+        Ok(args)
+    }
+
+    fn read_word(&self, offset: usize) -> Result<[u8; 32], WasmError> {
+        let mut word = [0u8; 32];
+        word.copy_from_slice(self.memory_slice(offset, 32)?);
+
+        Ok(word)
+    }
+
+    fn write_bytes(&mut self, offset: usize, bytes: &[u8]) -> Result<(), WasmError> {
+        self.memory_slice_mut(offset, bytes.len())?
+            .copy_from_slice(bytes);
+
+        Ok(())
+    }
+
+    fn check_not_static(&self) -> Result<(), WasmError> {
+        if self.frame.is_static {
+            return Err(WasmError::StaticStateChange);
+        }
+
+        Ok(())
+    }
+
+    fn access_account(&mut self, system: &mut System, address: &Address) -> Result<(), WasmError> {
+        let was_warm = system.accounts.touch(address)?;
+        self.charge(if was_warm {
+            costs::WARM_ACCESS
+        } else {
+            costs::COLD_ACCOUNT_ACCESS
+        })
+    }
+
+    /// Returns 0 on success, 1 on revert and 2 on failure (including insufficient balance)
+    fn call_contract(
+        &mut self,
+        system: &mut System,
+        address: usize,
+        value: usize,
+        calldata_offset: usize,
+        calldata_len: usize,
+        requested_fuel: u64,
+    ) -> Result<u64, WasmError> {
+        let address = Address(self.read_word(address)?);
+        let value = U256::from_be_bytes(&self.read_word(value)?);
+        let calldata = self.memory_slice(calldata_offset, calldata_len)?;
+        if !value.is_zero() {
+            self.check_not_static()?;
+        }
+
+        self.access_account(system, &address)?;
+        if !value.is_zero() {
+            self.charge(costs::CALL_VALUE)?;
+        }
+
+        system.returndata.clear();
+        if system.accounts.get(&self.frame.address)?.balance < value {
+            return Ok(2);
+        }
+
+        let available = costs::all_but_one_64th(self.resources.remaining());
+        let mut callee_resources = self.resources.take(requested_fuel.min(available));
+        let request = CallRequest {
+            is_static: self.frame.is_static,
+            ..CallRequest::new(self.frame.address, address, value, calldata)
+        };
+        let status = system.call(&request, &mut callee_resources)?;
+        self.resources.reclaim(callee_resources);
+
+        Ok(match status {
+            ExecutionStatus::Success => 0,
+            ExecutionStatus::Revert => 1,
+            ExecutionStatus::Failure => 2,
+        })
+    }
+    /// Returns the status as `call_contract` does, and the address of the deployed contract
+    fn deploy_contract(
+        &mut self,
+        system: &mut System,
+        request: usize,
+        requested_fuel: u64,
+    ) -> Result<(u64, Address), WasmError> {
+        self.check_not_static()?;
+        let mut words = [0usize; 9];
+        let fields = self.memory_slice(request, words.len() * 4)?;
+        for (word, bytes) in words.iter_mut().zip(fields.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        }
+        let [code_type, code, code_len, constants, constants_len, calldata, calldata_len, value, salt] =
+            words;
+        let code_type = match CodeType::from_u32(code_type as u32) {
+            Some(CodeType::Empty) | None => return Err(WasmError::Unsupported),
+            Some(code_type) => code_type,
+        };
+        let code = self.memory_slice(code, code_len)?;
+        let constants = self.memory_slice(constants, constants_len)?;
+        let value = U256::from_be_bytes(&self.read_word(value)?);
+        let scheme = if salt == 0 {
+            DeploymentScheme::Create
+        } else {
+            DeploymentScheme::Create2 {
+                salt: Bytes32(self.read_word(salt)?),
+            }
+        };
+        self.charge(costs::deployment_cost(
+            code.len(),
+            constants.len(),
+            salt != 0,
+        ))?;
+
+        let request = DeploymentRequest {
+            deployer: self.frame.address,
+            code_type,
+            code,
+            constants,
+            constructor_calldata: self.memory_slice(calldata, calldata_len)?,
+            value,
+            scheme,
+        };
+        let available = costs::all_but_one_64th(self.resources.remaining());
+        let mut constructor_resources = self.resources.take(requested_fuel.min(available));
+        let result = match system.deploy(&request, &mut constructor_resources) {
+            Ok(result) => result,
+            // the fuel given to the constructor is lost, as with a failed constructor
+            Err(SystemError::InvalidCode | SystemError::AddressCollision) => {
+                return Ok((2, Address::ZERO));
+            }
+            Err(error) => return Err(error.into()),
+        };
+        self.resources.reclaim(constructor_resources);
+
+        Ok(match result.status {
+            ExecutionStatus::Success => (0, result.address),
+            ExecutionStatus::Revert => (1, Address::ZERO),
+            ExecutionStatus::Failure => (2, Address::ZERO),
+        })
+    }
+}
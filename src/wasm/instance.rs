@@ -0,0 +1,195 @@
+use super::host::HostFunction;
+use super::module::*;
+use super::reader::Reader;
+use super::side_table::SideTable;
+use super::validation::read_locals;
+use super::{fuel, WasmError};
+use crate::system::costs;
+use crate::system::memory::{MemoryArena, MemoryRegion};
+use crate::system::resources::Resources;
+
+/// Uninitialized table element
+pub const NULL_ELEMENT: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Function {
+    pub type_index: u32,
+    pub host: Option<HostFunction>,
+    /// First instruction of the body
+    pub start: u32,
+    /// Position after the final `end` of the body
+    pub end: u32,
+    /// Locals besides the params
+    pub num_locals: u32,
+    /// First side table entry of the body
+    pub side_table: u32,
+}
+
+impl Function {
+    const EMPTY: Self = Self {
+        type_index: 0,
+        host: None,
+        start: 0,
+        end: 0,
+        num_locals: 0,
+        side_table: 0,
+    };
+}
+
+/// Instance of the validated module, that lives in the memory arena for the duration of the call.
+/// Contracts keep their state in the storage, so there is nothing to preserve between calls
+pub struct Instance<'a> {
+    pub code: &'a [u8],
+    pub types: &'static mut [FuncType],
+    pub functions: &'static mut [Function],
+    pub globals: &'static mut [u64],
+    pub table: &'static mut [u32],
+    /// Linear memory, the last allocation of the frame so that it can grow
+    pub memory: MemoryRegion,
+    pub max_pages: u32,
+    pub call_export: Option<u32>,
+    pub deploy_export: Option<u32>,
+    pub start_function: Option<u32>,
+}
+
+impl<'a> Instance<'a> {
+    pub fn new(
+        module: &Module<'a>,
+        side_table: &SideTable,
+        arena: &mut MemoryArena,
+        resources: &mut Resources,
+    ) -> Result<Self, WasmError> {
+        let (_, types) = arena.allocate_array(module.types.count as usize, FuncType::default())?;
+        let mut reader = module.reader(&module.types);
+        for func_type in types.iter_mut() {
+            *func_type = read_func_type(&mut reader)?;
+        }
+
+        if module.num_functions() > MAX_FUNCTIONS {
+            return Err(WasmError::LimitExceeded);
+        }
+        let (_, functions) =
+            arena.allocate_array(module.num_functions() as usize, Function::EMPTY)?;
+        let mut reader = module.reader(&module.imports);
+        for function in functions[..module.num_imported_functions() as usize].iter_mut() {
+            let _ = reader.read_vec()?;
+            let name = reader.read_vec()?;
+            let _ = reader.read_u8()?;
+            function.type_index = reader.read_u32()?;
+            function.host = HostFunction::from_name(name);
+        }
+        let mut reader = module.reader(&module.functions);
+        let mut bodies = module.reader(&module.bodies);
+        let defined_functions = functions[module.num_imported_functions() as usize..].iter_mut();
+        for (index, function) in defined_functions.enumerate() {
+            function.type_index = reader.read_u32()?;
+            let size = bodies.read_u32()? as usize;
+            let end = bodies.position() + size;
+            function.num_locals = read_locals(&mut bodies)?;
+            function.start = bodies.position() as u32;
+            function.end = end as u32;
+            function.side_table = side_table.function_start(index)?;
+            bodies.set_position(end);
+        }
+
+        let (_, globals) = arena.allocate_array(module.globals.count as usize, 0u64)?;
+        let mut reader = module.reader(&module.globals);
+        for global in globals.iter_mut() {
+            let _ = reader.read_u8()?;
+            let _ = reader.read_u8()?;
+            *global = read_const_expr(&mut reader)?;
+        }
+
+        let mut reader = module.reader(&module.tables);
+        let table_size = if module.tables.count == 1 {
+            let _ = reader.read_u8()?;
+            read_limits(&mut reader)?.min
+        } else {
+            0
+        };
+        let (_, table) = arena.allocate_array(table_size as usize, NULL_ELEMENT)?;
+        let mut reader = module.reader(&module.elements);
+        for _ in 0..module.elements.count {
+            let _ = reader.read_u32()?;
+            let offset = read_const_expr(&mut reader)? as u32 as usize;
+            let len = reader.read_u32()? as usize;
+            let elements = table
+                .get_mut(offset..offset.saturating_add(len))
+                .ok_or(WasmError::UndefinedElement)?;
+            for element in elements.iter_mut() {
+                *element = reader.read_u32()?;
+            }
+        }
+
+        let (call_export, deploy_export) = find_entry_points(module)?;
+
+        let mut reader = module.reader(&module.memories);
+        let (initial_pages, max_pages) = if module.memories.count == 1 {
+            let limits = read_limits(&mut reader)?;
+            let max_pages = limits.max.map_or(MAX_PAGES, |max| max.min(MAX_PAGES));
+            (limits.min, max_pages)
+        } else {
+            (0, 0)
+        };
+        resources.charge(fuel::MEMORY_PAGE * initial_pages as u64)?;
+        let mut memory = arena.allocate(initial_pages as usize * PAGE_SIZE)?;
+        let mut reader = module.reader(&module.data);
+        for _ in 0..module.data.count {
+            let _ = reader.read_u32()?;
+            let offset = read_const_expr(&mut reader)? as u32 as usize;
+            let data = reader.read_vec()?;
+            resources.charge(costs::copy_cost(data.len()))?;
+            memory
+                .as_mut_slice()
+                .get_mut(offset..offset.saturating_add(data.len()))
+                .ok_or(WasmError::MemoryOutOfBounds)?
+                .copy_from_slice(data);
+        }
+
+        Ok(Self {
+            code: module.code,
+            types,
+            functions,
+            globals,
+            table,
+            memory,
+            max_pages,
+            call_export,
+            deploy_export,
+            start_function: module.start_function,
+        })
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn function_type(&self, function: u32) -> &FuncType {
+        &self.types[self.functions[function as usize].type_index as usize]
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn memory_pages(&self) -> u32 {
+        (self.memory.len() / PAGE_SIZE) as u32
+    }
+}
+
+fn find_entry_points(module: &Module) -> Result<(Option<u32>, Option<u32>), WasmError> {
+    let mut call_export = None;
+    let mut deploy_export = None;
+    let mut reader: Reader = module.reader(&module.exports);
+    for _ in 0..module.exports.count {
+        let name = reader.read_vec()?;
+        let kind = reader.read_u8()?;
+        let index = reader.read_u32()?;
+        if kind != EXPORT_KIND_FUNCTION {
+            continue;
+        }
+        if name == CALL_EXPORT {
+            call_export = Some(index);
+        } else if name == DEPLOY_EXPORT {
+            deploy_export = Some(index);
+        }
+    }
+
+    Ok((call_export, deploy_export))
+}
@@ -0,0 +1,710 @@
+use super::instance::{Instance, NULL_ELEMENT};
+use super::module::{FuncType, PAGE_SIZE};
+use super::opcodes::*;
+use super::reader::Reader;
+use super::side_table::{SideTable, RETURN_TARGET};
+use super::{fuel, WasmError};
+use crate::system::costs;
+use crate::system::interpreter::ExecutionFrame;
+use crate::system::memory::MemoryArena;
+use crate::system::resources::Resources;
+use crate::system::System;
+
+pub const VALUE_STACK_SIZE: usize = 1 << 16;
+pub const MAX_FRAMES: usize = 1024;
+
+/// Function called by the host rather than by other function
+const RETURN_TO_HOST: u32 = u32::MAX;
+
+pub enum ExitReason {
+    Return(usize, usize),
+    Revert(usize, usize),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub return_position: u32,
+    /// Side table entry of the caller to continue from
+    pub return_entry: u32,
+    /// Locals (params first) are on the value stack starting from here
+    pub locals: u32,
+    /// Operands of the function are above this height
+    pub operands: u32,
+    /// Position after the final `end` of the body
+    pub end: u32,
+    pub arity: u32,
+}
+
+impl Frame {
+    const EMPTY: Self = Self {
+        return_position: 0,
+        return_entry: 0,
+        locals: 0,
+        operands: 0,
+        end: 0,
+        arity: 0,
+    };
+}
+
+/// Value and call stacks of the executor, placed into the memory arena
+pub struct Stacks {
+    values: &'static mut [u64],
+    frames: &'static mut [Frame],
+}
+
+impl Stacks {
+    pub fn new(arena: &mut MemoryArena) -> Result<Self, WasmError> {
+        let (_, values) = arena.allocate_array(VALUE_STACK_SIZE, 0u64)?;
+        let (_, frames) = arena.allocate_array(MAX_FRAMES, Frame::EMPTY)?;
+
+        Ok(Self { values, frames })
+    }
+}
+
+pub struct Executor<'a, 'b> {
+    pub frame: &'a ExecutionFrame<'b>,
+    pub resources: &'a mut Resources,
+    pub instance: Instance<'b>,
+    side_table: SideTable,
+    /// Side table entry of the next branch
+    entry: usize,
+    values: &'static mut [u64],
+    height: usize,
+    frames: &'static mut [Frame],
+    num_frames: usize,
+    /// Operands of the current function are above this height
+    floor: usize,
+}
+
+/// Pops two operands of the given type and pushes the result of the expression
+macro_rules! binary {
+    ($self:ident, $type:ty, |$a:ident, $b:ident| $result:expr) => {{
+        let $b = $self.pop()? as $type;
+        let $a = $self.pop()? as $type;
+        $self.push($result as u64)?;
+    }};
+}
+
+macro_rules! unary {
+    ($self:ident, $type:ty, |$a:ident| $result:expr) => {{
+        let $a = $self.pop()? as $type;
+        $self.push($result as u64)?;
+    }};
+}
+
+impl<'a, 'b> Executor<'a, 'b> {
+    pub fn new(
+        frame: &'a ExecutionFrame<'b>,
+        resources: &'a mut Resources,
+        stacks: Stacks,
+        instance: Instance<'b>,
+        side_table: SideTable,
+    ) -> Self {
+        Self {
+            frame,
+            resources,
+            instance,
+            side_table,
+            entry: 0,
+            values: stacks.values,
+            height: 0,
+            frames: stacks.frames,
+            num_frames: 0,
+            floor: 0,
+        }
+    }
+
+    #[inline(always)]
+    pub fn charge(&mut self, ticks: u64) -> Result<(), WasmError> {
+        self.resources.charge(ticks)?;
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn push(&mut self, value: u64) -> Result<(), WasmError> {
+        if self.height == VALUE_STACK_SIZE {
+            return Err(WasmError::StackOverflow);
+        }
+        self.values[self.height] = value;
+        self.height += 1;
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn pop(&mut self) -> Result<u64, WasmError> {
+        if self.height <= self.floor {
+            return Err(WasmError::StackUnderflow);
+        }
+        self.height -= 1;
+
+        Ok(self.values[self.height])
+    }
+
+    #[inline(always)]
+    pub fn pop_u32(&mut self) -> Result<u32, WasmError> {
+        Ok(self.pop()? as u32)
+    }
+
+    /// Range of the linear memory, trapping if it's out of bounds
+    pub fn memory_range(&self, offset: u64, len: u64) -> Result<usize, WasmError> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.instance.memory.len() as u64 => Ok(offset as usize),
+            _ => Err(WasmError::MemoryOutOfBounds),
+        }
+    }
+
+    pub fn memory_slice(&self, offset: usize, len: usize) -> Result<&'static [u8], WasmError> {
+        let offset = self.memory_range(offset as u64, len as u64)?;
+
+        Ok(&self.instance.memory.as_slice()[offset..offset + len])
+    }
+
+    pub fn memory_slice_mut(
+        &mut self,
+        offset: usize,
+        len: usize,
+    ) -> Result<&'static mut [u8], WasmError> {
+        let offset = self.memory_range(offset as u64, len as u64)?;
+
+        Ok(&mut self.instance.memory.as_mut_slice()[offset..offset + len])
+    }
+
+    #[inline(always)]
+    fn load<const N: usize>(&mut self, reader: &mut Reader) -> Result<[u8; N], WasmError> {
+        let _alignment = reader.read_u32()?;
+        let offset = reader.read_u32()?;
+        let base = self.pop_u32()?;
+        let address = self.memory_range(base as u64 + offset as u64, N as u64)?;
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(&self.instance.memory.as_slice()[address..address + N]);
+
+        Ok(bytes)
+    }
+
+    #[inline(always)]
+    fn store<const N: usize>(&mut self, reader: &mut Reader) -> Result<(), WasmError> {
+        let _alignment = reader.read_u32()?;
+        let offset = reader.read_u32()?;
+        let value = self.pop()?;
+        let base = self.pop_u32()?;
+        let address = self.memory_range(base as u64 + offset as u64, N as u64)?;
+        self.instance.memory.as_mut_slice()[address..address + N]
+            .copy_from_slice(&value.to_le_bytes()[..N]);
+
+        Ok(())
+    }
+
+    /// Moves top `arity` values down to `height`
+    fn unwind(&mut self, height: usize, arity: usize) -> Result<(), WasmError> {
+        if self.height < height + arity {
+            return Err(WasmError::StackUnderflow);
+        }
+        self.values
+            .copy_within(self.height - arity..self.height, height);
+        self.height = height + arity;
+
+        Ok(())
+    }
+
+    /// Calls the function from the host, returns `Some` if it finished the execution
+    pub fn invoke(
+        &mut self,
+        system: &mut System,
+        function: u32,
+    ) -> Result<Option<ExitReason>, WasmError> {
+        let mut reader = Reader::new(self.instance.code, 0);
+        if let Some(exit) = self.call(system, function, &mut reader, RETURN_TO_HOST)? {
+            return Ok(Some(exit));
+        }
+        // host function
+        if self.num_frames == 0 {
+            return Ok(None);
+        }
+
+        self.run(system, &mut reader)
+    }
+
+    /// Enters the function, or executes it right away if it's a host function
+    fn call(
+        &mut self,
+        system: &mut System,
+        function: u32,
+        reader: &mut Reader,
+        return_position: u32,
+    ) -> Result<Option<ExitReason>, WasmError> {
+        let callee = self.instance.functions[function as usize];
+        if let Some(host) = callee.host {
+            return self.call_host(system, host);
+        }
+        let FuncType {
+            num_params,
+            num_results,
+            ..
+        } = *self.instance.function_type(function);
+        if self.num_frames == MAX_FRAMES {
+            return Err(WasmError::CallStackExhausted);
+        }
+        if self.height < self.floor + num_params as usize {
+            return Err(WasmError::StackUnderflow);
+        }
+        let locals = self.height - num_params as usize;
+        let operands = self.height + callee.num_locals as usize;
+        if operands > VALUE_STACK_SIZE {
+            return Err(WasmError::StackOverflow);
+        }
+        self.values[self.height..operands].fill(0);
+        self.height = operands;
+
+        self.frames[self.num_frames] = Frame {
+            return_position,
+            return_entry: self.entry as u32,
+            locals: locals as u32,
+            operands: operands as u32,
+            end: callee.end,
+            arity: num_results,
+        };
+        self.num_frames += 1;
+        self.floor = operands;
+        reader.set_position(callee.start as usize);
+        self.entry = callee.side_table as usize;
+
+        Ok(None)
+    }
+
+    /// Returns from the current function, `true` if it was called by the host
+    fn return_from_function(&mut self, reader: &mut Reader) -> Result<bool, WasmError> {
+        let frame = self.frames[self.num_frames - 1];
+        self.floor = frame.locals as usize;
+        self.unwind(frame.locals as usize, frame.arity as usize)?;
+        self.num_frames -= 1;
+        if self.num_frames > 0 {
+            self.floor = self.frames[self.num_frames - 1].operands as usize;
+        }
+        if frame.return_position == RETURN_TO_HOST {
+            return Ok(true);
+        }
+        reader.set_position(frame.return_position as usize);
+        self.entry = frame.return_entry as usize;
+
+        Ok(false)
+    }
+
+    /// Takes the branch of the given side table entry, `true` if it returned to the host
+    fn branch(&mut self, entry: usize, reader: &mut Reader) -> Result<bool, WasmError> {
+        let entry = self.side_table.entry(entry)?;
+        if entry.target == RETURN_TARGET {
+            return self.return_from_function(reader);
+        }
+        let height = self
+            .height
+            .checked_sub((entry.drop + entry.arity) as usize)
+            .filter(|height| *height >= self.floor)
+            .ok_or(WasmError::StackUnderflow)?;
+        self.unwind(height, entry.arity as usize)?;
+        reader.set_position(entry.target as usize);
+        self.entry = entry.target_entry as usize;
+
+        Ok(false)
+    }
+
+    fn run(
+        &mut self,
+        system: &mut System,
+        reader: &mut Reader,
+    ) -> Result<Option<ExitReason>, WasmError> {
+        loop {
+            self.charge(fuel::INSTRUCTION)?;
+            let opcode = reader.read_u8()?;
+            match opcode {
+                UNREACHABLE => return Err(WasmError::Unreachable),
+                NOP => {}
+                BLOCK | LOOP => {
+                    let _ = reader.read_u8()?;
+                }
+                IF => {
+                    let _ = reader.read_u8()?;
+                    if self.pop_u32()? != 0 {
+                        self.entry += 1;
+                    } else {
+                        self.branch(self.entry, reader)?;
+                    }
+                }
+                // end of the `then` branch, skip the `else` one
+                ELSE => {
+                    self.branch(self.entry, reader)?;
+                }
+                END => {
+                    let end = self.frames[self.num_frames - 1].end;
+                    if reader.position() == end as usize && self.return_from_function(reader)? {
+                        return Ok(None);
+                    }
+                }
+                BR => {
+                    if self.branch(self.entry, reader)? {
+                        return Ok(None);
+                    }
+                }
+                BR_IF => {
+                    let _ = reader.read_u32()?;
+                    if self.pop_u32()? == 0 {
+                        self.entry += 1;
+                    } else if self.branch(self.entry, reader)? {
+                        return Ok(None);
+                    }
+                }
+                BR_TABLE => {
+                    let index = self.pop_u32()?;
+                    let len = reader.read_u32()?;
+                    // entries of the targets follow each other, the default one is the last
+                    if self.branch(self.entry + index.min(len) as usize, reader)? {
+                        return Ok(None);
+                    }
+                }
+                RETURN => {
+                    if self.return_from_function(reader)? {
+                        return Ok(None);
+                    }
+                }
+                CALL => {
+                    let function = reader.read_u32()?;
+                    let return_position = reader.position() as u32;
+                    if let Some(exit) = self.call(system, function, reader, return_position)? {
+                        return Ok(Some(exit));
+                    }
+                }
+                CALL_INDIRECT => {
+                    let type_index = reader.read_u32()?;
+                    let _ = reader.read_u8()?;
+                    let element = self.pop_u32()?;
+                    let function = self.resolve_indirect(type_index, element)?;
+                    let return_position = reader.position() as u32;
+                    if let Some(exit) = self.call(system, function, reader, return_position)? {
+                        return Ok(Some(exit));
+                    }
+                }
+                DROP => {
+                    self.pop()?;
+                }
+                SELECT => {
+                    let condition = self.pop_u32()?;
+                    let b = self.pop()?;
+                    let a = self.pop()?;
+                    self.push(if condition != 0 { a } else { b })?;
+                }
+                LOCAL_GET => {
+                    let index = self.local_index(reader)?;
+                    self.push(self.values[index])?;
+                }
+                LOCAL_SET => {
+                    let index = self.local_index(reader)?;
+                    self.values[index] = self.pop()?;
+                }
+                LOCAL_TEE => {
+                    let index = self.local_index(reader)?;
+                    let value = self.pop()?;
+                    self.values[index] = value;
+                    self.push(value)?;
+                }
+                GLOBAL_GET => {
+                    let index = reader.read_u32()? as usize;
+                    self.push(self.instance.globals[index])?;
+                }
+                GLOBAL_SET => {
+                    let index = reader.read_u32()? as usize;
+                    self.instance.globals[index] = self.pop()?;
+                }
+                I32_LOAD => {
+                    let bytes = self.load::<4>(reader)?;
+                    self.push(u32::from_le_bytes(bytes) as u64)?;
+                }
+                I64_LOAD => {
+                    let bytes = self.load::<8>(reader)?;
+                    self.push(u64::from_le_bytes(bytes))?;
+                }
+                I32_LOAD8_S => {
+                    let bytes = self.load::<1>(reader)?;
+                    self.push(bytes[0] as i8 as i32 as u32 as u64)?;
+                }
+                I32_LOAD8_U | I64_LOAD8_U => {
+                    let bytes = self.load::<1>(reader)?;
+                    self.push(bytes[0] as u64)?;
+                }
+                I32_LOAD16_S => {
+                    let bytes = self.load::<2>(reader)?;
+                    self.push(i16::from_le_bytes(bytes) as i32 as u32 as u64)?;
+                }
+                I32_LOAD16_U | I64_LOAD16_U => {
+                    let bytes = self.load::<2>(reader)?;
+                    self.push(u16::from_le_bytes(bytes) as u64)?;
+                }
+                I64_LOAD8_S => {
+                    let bytes = self.load::<1>(reader)?;
+                    self.push(bytes[0] as i8 as i64 as u64)?;
+                }
+                I64_LOAD16_S => {
+                    let bytes = self.load::<2>(reader)?;
+                    self.push(i16::from_le_bytes(bytes) as i64 as u64)?;
+                }
+                I64_LOAD32_S => {
+                    let bytes = self.load::<4>(reader)?;
+                    self.push(i32::from_le_bytes(bytes) as i64 as u64)?;
+                }
+                I64_LOAD32_U => {
+                    let bytes = self.load::<4>(reader)?;
+                    self.push(u32::from_le_bytes(bytes) as u64)?;
+                }
+                I32_STORE | I64_STORE32 => self.store::<4>(reader)?,
+                I64_STORE => self.store::<8>(reader)?,
+                I32_STORE8 | I64_STORE8 => self.store::<1>(reader)?,
+                I32_STORE16 | I64_STORE16 => self.store::<2>(reader)?,
+                MEMORY_SIZE => {
+                    let _ = reader.read_u8()?;
+                    self.push(self.instance.memory_pages() as u64)?;
+                }
+                MEMORY_GROW => {
+                    let _ = reader.read_u8()?;
+                    let delta = self.pop_u32()?;
+                    let result = self.grow_memory(system, delta)?;
+                    self.push(result as u64)?;
+                }
+                I32_CONST => {
+                    let value = reader.read_i32()?;
+                    self.push(value as u32 as u64)?;
+                }
+                I64_CONST => {
+                    let value = reader.read_i64()?;
+                    self.push(value as u64)?;
+                }
+
+                I32_EQZ => unary!(self, u32, |a| (a == 0)),
+                I32_EQ => binary!(self, u32, |a, b| (a == b)),
+                I32_NE => binary!(self, u32, |a, b| (a != b)),
+                I32_LT_S => binary!(self, i32, |a, b| (a < b)),
+                I32_LT_U => binary!(self, u32, |a, b| (a < b)),
+                I32_GT_S => binary!(self, i32, |a, b| (a > b)),
+                I32_GT_U => binary!(self, u32, |a, b| (a > b)),
+                I32_LE_S => binary!(self, i32, |a, b| (a <= b)),
+                I32_LE_U => binary!(self, u32, |a, b| (a <= b)),
+                I32_GE_S => binary!(self, i32, |a, b| (a >= b)),
+                I32_GE_U => binary!(self, u32, |a, b| (a >= b)),
+
+                I64_EQZ => unary!(self, u64, |a| (a == 0)),
+                I64_EQ => binary!(self, u64, |a, b| (a == b)),
+                I64_NE => binary!(self, u64, |a, b| (a != b)),
+                I64_LT_S => binary!(self, i64, |a, b| (a < b)),
+                I64_LT_U => binary!(self, u64, |a, b| (a < b)),
+                I64_GT_S => binary!(self, i64, |a, b| (a > b)),
+                I64_GT_U => binary!(self, u64, |a, b| (a > b)),
+                I64_LE_S => binary!(self, i64, |a, b| (a <= b)),
+                I64_LE_U => binary!(self, u64, |a, b| (a <= b)),
+                I64_GE_S => binary!(self, i64, |a, b| (a >= b)),
+                I64_GE_U => binary!(self, u64, |a, b| (a >= b)),
+
+                I32_CLZ => unary!(self, u32, |a| a.leading_zeros()),
+                I32_CTZ => unary!(self, u32, |a| a.trailing_zeros()),
+                I32_POPCNT => unary!(self, u32, |a| a.count_ones()),
+                I32_ADD => binary!(self, u32, |a, b| a.wrapping_add(b)),
+                I32_SUB => binary!(self, u32, |a, b| a.wrapping_sub(b)),
+                I32_MUL => binary!(self, u32, |a, b| a.wrapping_mul(b)),
+                I32_DIV_S => {
+                    let b = self.pop()? as i32;
+                    let a = self.pop()? as i32;
+                    let result = a.checked_div(b).ok_or(division_error(b == 0))?;
+                    self.push(result as u32 as u64)?;
+                }
+                I32_DIV_U => {
+                    let b = self.pop_u32()?;
+                    let a = self.pop_u32()?;
+                    let result = a.checked_div(b).ok_or(WasmError::DivisionByZero)?;
+                    self.push(result as u64)?;
+                }
+                I32_REM_S => {
+                    let b = self.pop()? as i32;
+                    let a = self.pop()? as i32;
+                    if b == 0 {
+                        return Err(WasmError::DivisionByZero);
+                    }
+                    self.push(a.wrapping_rem(b) as u32 as u64)?;
+                }
+                I32_REM_U => {
+                    let b = self.pop_u32()?;
+                    let a = self.pop_u32()?;
+                    let result = a.checked_rem(b).ok_or(WasmError::DivisionByZero)?;
+                    self.push(result as u64)?;
+                }
+                I32_AND => binary!(self, u32, |a, b| a & b),
+                I32_OR => binary!(self, u32, |a, b| a | b),
+                I32_XOR => binary!(self, u32, |a, b| a ^ b),
+                I32_SHL => binary!(self, u32, |a, b| a.wrapping_shl(b)),
+                I32_SHR_S => binary!(self, u32, |a, b| (a as i32).wrapping_shr(b) as u32),
+                I32_SHR_U => binary!(self, u32, |a, b| a.wrapping_shr(b)),
+                I32_ROTL => binary!(self, u32, |a, b| a.rotate_left(b % 32)),
+                I32_ROTR => binary!(self, u32, |a, b| a.rotate_right(b % 32)),
+
+                I64_CLZ => unary!(self, u64, |a| a.leading_zeros()),
+                I64_CTZ => unary!(self, u64, |a| a.trailing_zeros()),
+                I64_POPCNT => unary!(self, u64, |a| a.count_ones()),
+                I64_ADD => binary!(self, u64, |a, b| a.wrapping_add(b)),
+                I64_SUB => binary!(self, u64, |a, b| a.wrapping_sub(b)),
+                I64_MUL => binary!(self, u64, |a, b| a.wrapping_mul(b)),
+                I64_DIV_S..=I64_REM_U => {
+                    let b = self.pop()?;
+                    let a = self.pop()?;
+                    self.push(i64_div_rem(opcode, a, b)?)?;
+                }
+                I64_AND => binary!(self, u64, |a, b| a & b),
+                I64_OR => binary!(self, u64, |a, b| a | b),
+                I64_XOR => binary!(self, u64, |a, b| a ^ b),
+                I64_SHL => binary!(self, u64, |a, b| a.wrapping_shl(b as u32)),
+                I64_SHR_S => binary!(self, u64, |a, b| (a as i64).wrapping_shr(b as u32)),
+                I64_SHR_U => binary!(self, u64, |a, b| a.wrapping_shr(b as u32)),
+                I64_ROTL => binary!(self, u64, |a, b| a.rotate_left((b % 64) as u32)),
+                I64_ROTR => binary!(self, u64, |a, b| a.rotate_right((b % 64) as u32)),
+
+                I32_WRAP_I64 => unary!(self, u32, |a| a),
+                I64_EXTEND_I32_S => unary!(self, i32, |a| a as i64),
+                I64_EXTEND_I32_U => unary!(self, u32, |a| a),
+                I32_EXTEND8_S => unary!(self, u32, |a| a as i8 as i32 as u32),
+                I32_EXTEND16_S => unary!(self, u32, |a| a as i16 as i32 as u32),
+                I64_EXTEND8_S => unary!(self, u64, |a| a as i8 as i64),
+                I64_EXTEND16_S => unary!(self, u64, |a| a as i16 as i64),
+                I64_EXTEND32_S => unary!(self, u64, |a| a as i32 as i64),
+
+                PREFIX_FC => match reader.read_u32()? {
+                    MEMORY_COPY => {
+                        let _ = reader.read_u8()?;
+                        let _ = reader.read_u8()?;
+                        let len = self.pop_u32()?;
+                        let source = self.pop_u32()?;
+                        let destination = self.pop_u32()?;
+                        self.memory_copy(destination, source, len)?;
+                    }
+                    MEMORY_FILL => {
+                        let _ = reader.read_u8()?;
+                        let len = self.pop_u32()?;
+                        let value = self.pop_u32()?;
+                        let destination = self.pop_u32()?;
+                        self.memory_fill(destination, value as u8, len)?;
+                    }
+                    _ => return Err(WasmError::Unsupported),
+                },
+                _ => return Err(WasmError::Unsupported),
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn local_index(&self, reader: &mut Reader) -> Result<usize, WasmError> {
+        let index = reader.read_u32()? as usize;
+
+        Ok(self.frames[self.num_frames - 1].locals as usize + index)
+    }
+
+    /// Function of the table element, checked against the expected type
+    pub fn resolve_indirect(&self, type_index: u32, element: u32) -> Result<u32, WasmError> {
+        let function = *self
+            .instance
+            .table
+            .get(element as usize)
+            .ok_or(WasmError::UndefinedElement)?;
+        if function == NULL_ELEMENT {
+            return Err(WasmError::UninitializedElement);
+        }
+        let code = self.instance.code;
+        let expected = self.instance.types[type_index as usize].encoding(code);
+        if self.instance.function_type(function).encoding(code) != expected {
+            return Err(WasmError::IndirectCallTypeMismatch);
+        }
+
+        Ok(function)
+    }
+
+    pub fn memory_copy(
+        &mut self,
+        destination: u32,
+        source: u32,
+        len: u32,
+    ) -> Result<(), WasmError> {
+        self.charge(costs::copy_cost(len as usize))?;
+        let source = self.memory_range(source as u64, len as u64)?;
+        let destination = self.memory_range(destination as u64, len as u64)?;
+        self.instance
+            .memory
+            .as_mut_slice()
+            .copy_within(source..source + len as usize, destination);
+
+        Ok(())
+    }
+
+    pub fn memory_fill(&mut self, destination: u32, value: u8, len: u32) -> Result<(), WasmError> {
+        self.charge(costs::copy_cost(len as usize))?;
+        self.memory_slice_mut(destination as usize, len as usize)?
+            .fill(value);
+
+        Ok(())
+    }
+
+    /// Value stack as a whole, for the native code that keeps its frames there
+    pub fn values(&mut self) -> &mut [u64] {
+        self.values
+    }
+
+    /// Makes `[floor, height)` the operands of the current function, so that the native code
+    /// can call back into the executor with the operands in place
+    pub fn set_operands(&mut self, floor: usize, height: usize) {
+        self.floor = floor;
+        self.height = height;
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the previous size in pages, or -1 if memory can't grow
+    pub fn grow_memory(&mut self, system: &mut System, delta: u32) -> Result<u32, WasmError> {
+        let pages = self.instance.memory_pages();
+        let new_pages = pages as u64 + delta as u64;
+        if new_pages > self.instance.max_pages as u64 {
+            return Ok(u32::MAX);
+        }
+        self.charge(fuel::MEMORY_PAGE * delta as u64)?;
+        if system
+            .memory
+            .grow(&mut self.instance.memory, new_pages as usize * PAGE_SIZE)
+            .is_err()
+        {
+            return Ok(u32::MAX);
+        }
+
+        Ok(pages)
+    }
+}
+
+/// 64-bit division and remainder, that the native code leaves to the kernel
+pub fn i64_div_rem(opcode: u8, a: u64, b: u64) -> Result<u64, WasmError> {
+    if b == 0 {
+        return Err(WasmError::DivisionByZero);
+    }
+    match opcode {
+        I64_DIV_S => (a as i64)
+            .checked_div(b as i64)
+            .map(|result| result as u64)
+            .ok_or(WasmError::IntegerOverflow),
+        I64_DIV_U => Ok(a / b),
+        I64_REM_S => Ok((a as i64).wrapping_rem(b as i64) as u64),
+        I64_REM_U => Ok(a % b),
+        _ => Err(WasmError::Unsupported),
+    }
+}
+
+const fn division_error(by_zero: bool) -> WasmError {
+    if by_zero {
+        WasmError::DivisionByZero
+    } else {
+        WasmError::IntegerOverflow
+    }
+}
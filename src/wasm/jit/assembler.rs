@@ -0,0 +1,405 @@
+// RV32IM encoder that writes into the artifact buffer. Running out of the buffer is sticky and
+// checked once at the end, so that the compiler doesn't have to handle it at every instruction
+
+pub type Reg = u8;
+
+pub const ZERO: Reg = 0;
+pub const RA: Reg = 1;
+pub const GP: Reg = 3;
+pub const TP: Reg = 4;
+pub const T0: Reg = 5;
+pub const T1: Reg = 6;
+pub const T2: Reg = 7;
+pub const S0: Reg = 8;
+pub const S1: Reg = 9;
+pub const A0: Reg = 10;
+pub const A1: Reg = 11;
+pub const A2: Reg = 12;
+pub const A3: Reg = 13;
+pub const A4: Reg = 14;
+pub const A5: Reg = 15;
+pub const A6: Reg = 16;
+pub const A7: Reg = 17;
+pub const S2: Reg = 18;
+pub const S3: Reg = 19;
+pub const S4: Reg = 20;
+pub const S5: Reg = 21;
+pub const S6: Reg = 22;
+pub const S7: Reg = 23;
+pub const S8: Reg = 24;
+pub const S9: Reg = 25;
+pub const S10: Reg = 26;
+pub const S11: Reg = 27;
+pub const T3: Reg = 28;
+pub const T4: Reg = 29;
+pub const T5: Reg = 30;
+pub const T6: Reg = 31;
+
+const OP: u32 = 0b0110011;
+const OP_IMM: u32 = 0b0010011;
+const LOAD: u32 = 0b0000011;
+const STORE: u32 = 0b0100011;
+const BRANCH: u32 = 0b1100011;
+const LUI: u32 = 0b0110111;
+const AUIPC: u32 = 0b0010111;
+const JAL: u32 = 0b1101111;
+const JALR: u32 = 0b1100111;
+const SYSTEM: u32 = 0b1110011;
+
+/// Conditions of the branches, as their funct3
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+    Eq = 0,
+    Ne = 1,
+    Lt = 4,
+    Ge = 5,
+    Ltu = 6,
+    Geu = 7,
+}
+
+impl Condition {
+    #[must_use]
+    pub const fn inverted(self) -> Self {
+        match self {
+            Self::Eq => Self::Ne,
+            Self::Ne => Self::Eq,
+            Self::Lt => Self::Ge,
+            Self::Ge => Self::Lt,
+            Self::Ltu => Self::Geu,
+            Self::Geu => Self::Ltu,
+        }
+    }
+}
+
+#[must_use]
+#[inline(always)]
+pub const fn fits_i12(value: i32) -> bool {
+    value >= -2048 && value < 2048
+}
+
+#[must_use]
+#[inline(always)]
+const fn r_type(funct7: u32, rs2: Reg, rs1: Reg, funct3: u32, rd: Reg) -> u32 {
+    funct7 << 25 | (rs2 as u32) << 20 | (rs1 as u32) << 15 | funct3 << 12 | (rd as u32) << 7 | OP
+}
+
+#[must_use]
+#[inline(always)]
+const fn i_type(imm: i32, rs1: Reg, funct3: u32, rd: Reg, opcode: u32) -> u32 {
+    ((imm as u32) & 0xfff) << 20 | (rs1 as u32) << 15 | funct3 << 12 | (rd as u32) << 7 | opcode
+}
+
+#[must_use]
+#[inline(always)]
+const fn s_type(imm: i32, rs2: Reg, rs1: Reg, funct3: u32) -> u32 {
+    let imm = imm as u32;
+    (imm >> 5 & 0x7f) << 25
+        | (rs2 as u32) << 20
+        | (rs1 as u32) << 15
+        | funct3 << 12
+        | (imm & 0x1f) << 7
+        | STORE
+}
+
+#[must_use]
+#[inline(always)]
+const fn b_type(offset: i32, rs2: Reg, rs1: Reg, funct3: u32) -> u32 {
+    let imm = offset as u32;
+    (imm >> 12 & 1) << 31
+        | (imm >> 5 & 0x3f) << 25
+        | (rs2 as u32) << 20
+        | (rs1 as u32) << 15
+        | funct3 << 12
+        | (imm >> 1 & 0xf) << 8
+        | (imm >> 11 & 1) << 7
+        | BRANCH
+}
+
+#[must_use]
+#[inline(always)]
+pub const fn jal(rd: Reg, offset: i32) -> u32 {
+    let imm = offset as u32;
+    (imm >> 20 & 1) << 31
+        | (imm >> 1 & 0x3ff) << 21
+        | (imm >> 11 & 1) << 20
+        | (imm >> 12 & 0xff) << 12
+        | (rd as u32) << 7
+        | JAL
+}
+
+/// Upper and lower parts of the constant for `lui` + `addi`
+#[must_use]
+#[inline(always)]
+const fn split_constant(value: u32) -> (u32, i32) {
+    let upper = value.wrapping_add(0x800) & 0xffff_f000;
+    let lower = value.wrapping_sub(upper) as i32;
+    (upper, lower)
+}
+
+pub struct Assembler<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+    overflow: bool,
+}
+
+impl<'a> Assembler<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self {
+            buffer,
+            len: 0,
+            overflow: false,
+        }
+    }
+
+    /// Offset of the next instruction
+    #[must_use]
+    #[inline(always)]
+    pub const fn position(&self) -> u32 {
+        self.len as u32
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn overflow(&self) -> bool {
+        self.overflow
+    }
+
+    /// Length of the code, the buffer itself is given back by dropping the assembler
+    #[must_use]
+    pub const fn finish(self) -> usize {
+        self.len
+    }
+
+    pub fn emit(&mut self, instruction: u32) {
+        if self.len + 4 > self.buffer.len() {
+            self.overflow = true;
+            return;
+        }
+        self.buffer[self.len..self.len + 4].copy_from_slice(&instruction.to_le_bytes());
+        self.len += 4;
+    }
+
+    #[must_use]
+    pub fn read(&self, position: u32) -> u32 {
+        let position = position as usize;
+        match self.buffer.get(position..position + 4) {
+            Some(bytes) if position + 4 <= self.len => {
+                u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn patch(&mut self, position: u32, instruction: u32) {
+        let position = position as usize;
+        if position + 4 <= self.len {
+            self.buffer[position..position + 4].copy_from_slice(&instruction.to_le_bytes());
+        }
+    }
+
+    /// Relative offset from `position` to `target`
+    #[must_use]
+    #[inline(always)]
+    pub const fn offset(position: u32, target: u32) -> i32 {
+        target.wrapping_sub(position) as i32
+    }
+
+    // register-register
+
+    pub fn add(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(0, rs2, rs1, 0, rd));
+    }
+
+    pub fn sub(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(0b0100000, rs2, rs1, 0, rd));
+    }
+
+    pub fn sll(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(0, rs2, rs1, 1, rd));
+    }
+
+    pub fn slt(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(0, rs2, rs1, 2, rd));
+    }
+
+    pub fn sltu(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(0, rs2, rs1, 3, rd));
+    }
+
+    pub fn xor(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(0, rs2, rs1, 4, rd));
+    }
+
+    pub fn srl(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(0, rs2, rs1, 5, rd));
+    }
+
+    pub fn sra(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(0b0100000, rs2, rs1, 5, rd));
+    }
+
+    pub fn or(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(0, rs2, rs1, 6, rd));
+    }
+
+    pub fn and(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(0, rs2, rs1, 7, rd));
+    }
+
+    pub fn mul(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(1, rs2, rs1, 0, rd));
+    }
+
+    pub fn mulhu(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(1, rs2, rs1, 3, rd));
+    }
+
+    pub fn div(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(1, rs2, rs1, 4, rd));
+    }
+
+    pub fn divu(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(1, rs2, rs1, 5, rd));
+    }
+
+    pub fn rem(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(1, rs2, rs1, 6, rd));
+    }
+
+    pub fn remu(&mut self, rd: Reg, rs1: Reg, rs2: Reg) {
+        self.emit(r_type(1, rs2, rs1, 7, rd));
+    }
+
+    // register-immediate
+
+    pub fn addi(&mut self, rd: Reg, rs1: Reg, imm: i32) {
+        self.emit(i_type(imm, rs1, 0, rd, OP_IMM));
+    }
+
+    pub fn sltiu(&mut self, rd: Reg, rs1: Reg, imm: i32) {
+        self.emit(i_type(imm, rs1, 3, rd, OP_IMM));
+    }
+
+    pub fn xori(&mut self, rd: Reg, rs1: Reg, imm: i32) {
+        self.emit(i_type(imm, rs1, 4, rd, OP_IMM));
+    }
+
+    pub fn andi(&mut self, rd: Reg, rs1: Reg, imm: i32) {
+        self.emit(i_type(imm, rs1, 7, rd, OP_IMM));
+    }
+
+    pub fn slli(&mut self, rd: Reg, rs1: Reg, shamt: u32) {
+        self.emit(i_type((shamt & 31) as i32, rs1, 1, rd, OP_IMM));
+    }
+
+    pub fn srli(&mut self, rd: Reg, rs1: Reg, shamt: u32) {
+        self.emit(i_type((shamt & 31) as i32, rs1, 5, rd, OP_IMM));
+    }
+
+    pub fn srai(&mut self, rd: Reg, rs1: Reg, shamt: u32) {
+        self.emit(i_type((shamt & 31) as i32 | 0x400, rs1, 5, rd, OP_IMM));
+    }
+
+    pub fn mv(&mut self, rd: Reg, rs: Reg) {
+        self.addi(rd, rs, 0);
+    }
+
+    pub fn lui(&mut self, rd: Reg, upper: u32) {
+        self.emit((upper & 0xffff_f000) | (rd as u32) << 7 | LUI);
+    }
+
+    pub fn auipc(&mut self, rd: Reg, upper: u32) {
+        self.emit((upper & 0xffff_f000) | (rd as u32) << 7 | AUIPC);
+    }
+
+    /// Loads the constant in one or two instructions
+    pub fn li(&mut self, rd: Reg, value: u32) {
+        if fits_i12(value as i32) {
+            self.addi(rd, ZERO, value as i32);
+            return;
+        }
+        let (upper, lower) = split_constant(value);
+        self.lui(rd, upper);
+        if lower != 0 {
+            self.addi(rd, rd, lower);
+        }
+    }
+
+    /// Loads the constant in exactly two instructions, so that it can be patched later
+    pub fn li_fixed(&mut self, rd: Reg, value: u32) {
+        let (upper, lower) = split_constant(value);
+        self.lui(rd, upper);
+        self.addi(rd, rd, lower);
+    }
+
+    pub fn patch_li_fixed(&mut self, position: u32, rd: Reg, value: u32) {
+        let (upper, lower) = split_constant(value);
+        self.patch(position, (upper & 0xffff_f000) | (rd as u32) << 7 | LUI);
+        self.patch(position + 4, i_type(lower, rd, 0, rd, OP_IMM));
+    }
+
+    // memory
+
+    pub fn lb(&mut self, rd: Reg, rs1: Reg, imm: i32) {
+        self.emit(i_type(imm, rs1, 0, rd, LOAD));
+    }
+
+    pub fn lh(&mut self, rd: Reg, rs1: Reg, imm: i32) {
+        self.emit(i_type(imm, rs1, 1, rd, LOAD));
+    }
+
+    pub fn lw(&mut self, rd: Reg, rs1: Reg, imm: i32) {
+        self.emit(i_type(imm, rs1, 2, rd, LOAD));
+    }
+
+    pub fn lbu(&mut self, rd: Reg, rs1: Reg, imm: i32) {
+        self.emit(i_type(imm, rs1, 4, rd, LOAD));
+    }
+
+    pub fn lhu(&mut self, rd: Reg, rs1: Reg, imm: i32) {
+        self.emit(i_type(imm, rs1, 5, rd, LOAD));
+    }
+
+    pub fn sb(&mut self, rs2: Reg, rs1: Reg, imm: i32) {
+        self.emit(s_type(imm, rs2, rs1, 0));
+    }
+
+    pub fn sh(&mut self, rs2: Reg, rs1: Reg, imm: i32) {
+        self.emit(s_type(imm, rs2, rs1, 1));
+    }
+
+    pub fn sw(&mut self, rs2: Reg, rs1: Reg, imm: i32) {
+        self.emit(s_type(imm, rs2, rs1, 2));
+    }
+
+    // control transfer
+
+    /// Branch with the offset relative to itself, which must fit into 13 bits
+    pub fn branch(&mut self, condition: Condition, rs1: Reg, rs2: Reg, offset: i32) {
+        self.emit(b_type(offset, rs2, rs1, condition as u32));
+    }
+
+    pub fn patch_branch(
+        &mut self,
+        position: u32,
+        condition: Condition,
+        rs1: Reg,
+        rs2: Reg,
+        target: u32,
+    ) {
+        let offset = Self::offset(position, target);
+        self.patch(position, b_type(offset, rs2, rs1, condition as u32));
+    }
+
+    pub fn jal(&mut self, rd: Reg, offset: i32) {
+        self.emit(jal(rd, offset));
+    }
+
+    pub fn jalr(&mut self, rd: Reg, rs1: Reg, imm: i32) {
+        self.emit(i_type(imm, rs1, 0, rd, JALR));
+    }
+
+    pub fn ecall(&mut self) {
+        self.emit(SYSTEM);
+    }
+}
@@ -0,0 +1,1379 @@
+use super::assembler::*;
+use super::*;
+use crate::system::SystemError;
+use crate::wasm::module::{Module, Signatures, MAX_FUNCTIONS};
+use crate::wasm::opcodes::*;
+use crate::wasm::reader::Reader;
+use crate::wasm::validation::{read_locals, MAX_NESTING};
+use crate::wasm::WasmError;
+
+// Single-pass compiler of the validated bodies. Operands are cached in registers as pairs of
+// 32-bit halves (the high half of i32 results is x0) and spilled to their slots on the value
+// stack when registers run out. At every label, branch and call all of them are flushed, so
+// control flow always meets with the operands in memory.
+//
+// Frame of a function, 8 bytes per slot starting from s0: locals (params first), the saved
+// return address and s0 of the caller, operands. Callee's frame starts at the first operand
+// of the call, so params are in place and results come back in the same slots.
+//
+// Registers:
+//  - s0: frame, s4: globals (the start of the value stack), s5: end of the value stack
+//  - s1, s2: linear memory and its size in bytes
+//  - s3: fuel left, as a signed 32-bit number, checked at loop headers and function entries
+//  - s6: frames left before the call stack is exhausted
+//  - t5, t6, a7: scratch, a7 also carries the exits to the kernel
+//  - the rest besides sp: operand cache
+
+const FRAME: Reg = S0;
+const MEMORY: Reg = S1;
+const MEMORY_LEN: Reg = S2;
+const FUEL: Reg = S3;
+const GLOBALS: Reg = S4;
+const STACK_END: Reg = S5;
+const FRAMES_LEFT: Reg = S6;
+/// Base of the slots that are too far for the 12-bit offsets
+const ADDRESS: Reg = A7;
+
+const ALLOCATABLE: [Reg; 19] = [
+    A0, A1, A2, A3, A4, A5, A6, T0, T1, T2, T3, T4, S7, S8, S9, S10, S11, GP, TP,
+];
+
+/// Deeper operand stacks are left to the interpreter
+pub const MAX_OPERANDS: usize = 1024;
+/// End of the chain of jumps waiting for their target
+const NO_JUMP: u32 = u32::MAX;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Value {
+    /// In its slot on the value stack
+    Slot,
+    /// Low and high halves
+    Register(Reg, Reg),
+}
+
+#[derive(Clone, Copy)]
+struct Label {
+    is_loop: bool,
+    /// Operand stack height at the start of the block
+    height: usize,
+    arity: usize,
+    /// Where branches to a loop go
+    start: u32,
+    /// Chain of the jumps to the end of the block
+    pending: u32,
+    /// Jump of the `if` to its `else` branch
+    else_jump: u32,
+}
+
+impl Label {
+    const EMPTY: Self = Self {
+        is_loop: false,
+        height: 0,
+        arity: 0,
+        start: 0,
+        pending: NO_JUMP,
+        else_jump: NO_JUMP,
+    };
+}
+
+struct Compiler<'a, 'b> {
+    asm: Assembler<'a>,
+    signatures: &'b Signatures,
+    num_imported_functions: u32,
+    /// Offsets of the compiled functions
+    offsets: [u32; MAX_FUNCTIONS as usize],
+    /// Chains of the calls to the functions that are not compiled yet
+    calls: [u32; MAX_FUNCTIONS as usize],
+
+    // state of the current function
+    num_locals: usize,
+    num_results: usize,
+    values: [Value; MAX_OPERANDS],
+    height: usize,
+    max_height: usize,
+    /// One bit per free register
+    free: u32,
+    labels: [Label; MAX_NESTING],
+    depth: usize,
+    /// Instructions executed since the fuel was last deducted
+    fuel: u32,
+    /// Code after an unconditional branch, that is skipped up to the end of its block
+    unreachable: bool,
+    /// Blocks nested in the skipped code
+    skipped_depth: usize,
+}
+
+/// Compiles the validated module into the native section of the artifact, returns its length
+pub fn compile(module: &Module, buffer: &mut [u8]) -> Result<usize, WasmError> {
+    let signatures = Signatures::read(module)?;
+    let num_functions = module.num_functions() as usize;
+    let table_len = (HEADER_WORDS + num_functions) * 4;
+    if table_len > buffer.len() {
+        return Err(WasmError::System(SystemError::BlobStorageFull));
+    }
+    let (table, code) = buffer.split_at_mut(table_len);
+    let code_len = code.len().min(MAX_CODE_SIZE);
+
+    let mut compiler = Compiler {
+        asm: Assembler::new(&mut code[..code_len]),
+        signatures: &signatures,
+        num_imported_functions: module.num_imported_functions(),
+        offsets: [NO_CODE; MAX_FUNCTIONS as usize],
+        calls: [NO_JUMP; MAX_FUNCTIONS as usize],
+        num_locals: 0,
+        num_results: 0,
+        values: [Value::Slot; MAX_OPERANDS],
+        height: 0,
+        max_height: 0,
+        free: 0,
+        labels: [Label::EMPTY; MAX_NESTING],
+        depth: 0,
+        fuel: 0,
+        unreachable: false,
+        skipped_depth: 0,
+    };
+    compiler.emit_stubs();
+
+    let mut reader = module.reader(&module.bodies);
+    for index in 0..module.functions.count {
+        let size = reader.read_u32()? as usize;
+        let end = reader.position() + size;
+        let mut body = Reader::new(&module.code[..end], reader.position());
+        compiler.compile_function(&mut body, module.num_imported_functions() + index)?;
+        reader.set_position(end);
+    }
+    if compiler.asm.overflow() {
+        return Err(WasmError::LimitExceeded);
+    }
+
+    let Compiler { asm, offsets, .. } = compiler;
+    let code_len = asm.finish();
+    table[..4].copy_from_slice(&(num_functions as u32).to_le_bytes());
+    table[4..8].copy_from_slice(&(code_len as u32).to_le_bytes());
+    for (word, offset) in table[HEADER_WORDS * 4..]
+        .chunks_exact_mut(4)
+        .zip(offsets.iter())
+    {
+        word.copy_from_slice(&offset.to_le_bytes());
+    }
+
+    Ok(table_len + code_len)
+}
+
+impl<'a, 'b> Compiler<'a, 'b> {
+    fn emit_stubs(&mut self) {
+        self.asm.li_fixed(A7, exit_call(EXIT_RETURN, 0));
+        self.asm.ecall();
+        for trap in 0..TRAPS.len() as u32 {
+            self.asm.li_fixed(A7, exit_call(EXIT_TRAP, trap));
+            self.asm.ecall();
+        }
+    }
+
+    fn compile_function(&mut self, reader: &mut Reader, function: u32) -> Result<(), WasmError> {
+        let (num_params, num_results) = self.signatures.of_function(function);
+        self.num_locals = (num_params + read_locals(reader)?) as usize;
+        self.num_results = num_results as usize;
+        self.height = 0;
+        self.max_height = 0;
+        self.free = ALLOCATABLE.iter().fold(0, |free, reg| free | 1 << reg);
+        self.depth = 0;
+        self.fuel = 0;
+        self.unreachable = false;
+        self.skipped_depth = 0;
+
+        let position = self.asm.position();
+        self.offsets[function as usize] = position;
+        let calls = core::mem::replace(&mut self.calls[function as usize], NO_JUMP);
+        self.resolve(calls, position, RA);
+        let frame_size = self.prologue(num_params as usize);
+
+        loop {
+            let opcode = reader.read_u8()?;
+            if self.unreachable && self.skip(opcode, reader)? {
+                continue;
+            }
+            if !self.unreachable {
+                self.fuel += 1;
+            }
+            if opcode == END && self.depth == 0 {
+                if !self.unreachable {
+                    self.flush();
+                    self.sync_fuel();
+                    self.emit_return();
+                }
+                break;
+            }
+            self.compile_instruction(opcode, reader)?;
+        }
+
+        let size = (self.num_locals + 1 + self.max_height) * 8;
+        self.asm.patch_li_fixed(frame_size, T6, size as u32);
+        if self.asm.position() as usize >= MAX_CODE_SIZE {
+            return Err(WasmError::LimitExceeded);
+        }
+
+        Ok(())
+    }
+
+    /// Checks the limits, saves the return address and zeroes the declared locals. Returns the
+    /// position of the frame size, that is known at the end of the body
+    fn prologue(&mut self, num_params: usize) -> u32 {
+        let frame_size = self.asm.position();
+        self.asm.li_fixed(T6, 0);
+        self.asm.add(T6, FRAME, T6);
+        self.trap_if(Condition::Ltu, STACK_END, T6, TRAP_STACK_OVERFLOW);
+        self.asm.addi(FRAMES_LEFT, FRAMES_LEFT, -1);
+        self.trap_if(Condition::Lt, FRAMES_LEFT, ZERO, TRAP_CALL_STACK_EXHAUSTED);
+        self.trap_if(Condition::Lt, FUEL, ZERO, TRAP_OUT_OF_FUEL);
+
+        // caller's frame is in t5
+        let (base, offset) = self.address(FRAME, self.header_offset());
+        self.asm.sw(RA, base, offset);
+        self.asm.sw(T5, base, offset + 4);
+
+        let num_declared = self.num_locals - num_params;
+        if num_declared <= 8 {
+            for local in num_params..self.num_locals {
+                self.store_pair(ZERO, ZERO, FRAME, local as i32 * 8);
+            }
+        } else {
+            self.asm.li(ADDRESS, num_params as u32 * 8);
+            self.asm.add(ADDRESS, FRAME, ADDRESS);
+            self.asm.li(T6, num_declared as u32 * 8);
+            self.asm.add(T6, ADDRESS, T6);
+            self.asm.sw(ZERO, ADDRESS, 0);
+            self.asm.sw(ZERO, ADDRESS, 4);
+            self.asm.addi(ADDRESS, ADDRESS, 8);
+            self.asm.branch(Condition::Ltu, ADDRESS, T6, -12);
+        }
+
+        frame_size
+    }
+
+    /// Copies the result into the first slot of the frame and returns to the caller
+    fn emit_return(&mut self) {
+        let (base, offset) = self.address(FRAME, self.header_offset());
+        self.asm.lw(RA, base, offset);
+        self.asm.lw(T5, base, offset + 4);
+        if self.num_results == 1 {
+            let (base, offset) = self.address(FRAME, self.slot(self.height - 1));
+            self.asm.lw(T6, base, offset);
+            self.asm.sw(T6, FRAME, 0);
+            self.asm.lw(T6, base, offset + 4);
+            self.asm.sw(T6, FRAME, 4);
+        }
+        self.asm.addi(FRAMES_LEFT, FRAMES_LEFT, 1);
+        self.asm.mv(FRAME, T5);
+        self.asm.jalr(ZERO, RA, 0);
+    }
+
+    #[inline(always)]
+    fn header_offset(&self) -> i32 {
+        self.num_locals as i32 * 8
+    }
+
+    /// Offset of the operand from the frame
+    #[inline(always)]
+    fn slot(&self, index: usize) -> i32 {
+        ((self.num_locals + 1 + index) * 8) as i32
+    }
+
+    /// Base register and offset of the 8-byte slot, computing the address if the offset
+    /// doesn't fit into the instructions
+    fn address(&mut self, base: Reg, offset: i32) -> (Reg, i32) {
+        if fits_i12(offset + 4) {
+            return (base, offset);
+        }
+        self.asm.li(ADDRESS, offset as u32);
+        self.asm.add(ADDRESS, base, ADDRESS);
+
+        (ADDRESS, 0)
+    }
+
+    fn load_pair(&mut self, low: Reg, high: Reg, base: Reg, offset: i32) {
+        let (base, offset) = self.address(base, offset);
+        self.asm.lw(low, base, offset);
+        self.asm.lw(high, base, offset + 4);
+    }
+
+    fn store_pair(&mut self, low: Reg, high: Reg, base: Reg, offset: i32) {
+        let (base, offset) = self.address(base, offset);
+        self.asm.sw(low, base, offset);
+        self.asm.sw(high, base, offset + 4);
+    }
+
+    /// `rd = base + offset` for any offset
+    fn add_offset(&mut self, rd: Reg, base: Reg, offset: i32) {
+        if fits_i12(offset) {
+            self.asm.addi(rd, base, offset);
+        } else {
+            self.asm.li(ADDRESS, offset as u32);
+            self.asm.add(rd, base, ADDRESS);
+        }
+    }
+
+    // operand cache
+
+    fn alloc(&mut self) -> Result<Reg, WasmError> {
+        while self.free == 0 {
+            // the deepest operand is needed the last
+            let index = self.values[..self.height]
+                .iter()
+                .position(|value| *value != Value::Slot)
+                .ok_or(WasmError::LimitExceeded)?;
+            self.spill(index);
+        }
+        let reg = self.free.trailing_zeros() as Reg;
+        self.free &= !(1 << reg);
+
+        Ok(reg)
+    }
+
+    #[inline(always)]
+    fn release(&mut self, reg: Reg) {
+        if reg != ZERO {
+            self.free |= 1 << reg;
+        }
+    }
+
+    fn spill(&mut self, index: usize) {
+        if let Value::Register(low, high) = self.values[index] {
+            self.store_pair(low, high, FRAME, self.slot(index));
+            self.release(low);
+            self.release(high);
+            self.values[index] = Value::Slot;
+        }
+    }
+
+    /// Moves all operands into their slots
+    fn flush(&mut self) {
+        for index in 0..self.height {
+            self.spill(index);
+        }
+    }
+
+    fn push_value(&mut self, value: Value) -> Result<(), WasmError> {
+        if self.height == MAX_OPERANDS {
+            return Err(WasmError::LimitExceeded);
+        }
+        self.values[self.height] = value;
+        self.height += 1;
+        self.max_height = self.max_height.max(self.height);
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn push(&mut self, low: Reg, high: Reg) -> Result<(), WasmError> {
+        self.push_value(Value::Register(low, high))
+    }
+
+    fn pop_value(&mut self) -> Result<Value, WasmError> {
+        if self.height == 0 {
+            return Err(WasmError::Malformed);
+        }
+        self.height -= 1;
+
+        Ok(self.values[self.height])
+    }
+
+    fn pop(&mut self) -> Result<(Reg, Reg), WasmError> {
+        match self.pop_value()? {
+            Value::Register(low, high) => Ok((low, high)),
+            Value::Slot => {
+                let low = self.alloc()?;
+                let high = self.alloc()?;
+                self.load_pair(low, high, FRAME, self.slot(self.height));
+                Ok((low, high))
+            }
+        }
+    }
+
+    /// Pops the operand that only its low half matters for
+    fn pop_low(&mut self) -> Result<Reg, WasmError> {
+        match self.pop_value()? {
+            Value::Register(low, high) => {
+                self.release(high);
+                Ok(low)
+            }
+            Value::Slot => {
+                let low = self.alloc()?;
+                let (base, offset) = self.address(FRAME, self.slot(self.height));
+                self.asm.lw(low, base, offset);
+                Ok(low)
+            }
+        }
+    }
+
+    fn discard(&mut self) -> Result<(), WasmError> {
+        if let Value::Register(low, high) = self.pop_value()? {
+            self.release(low);
+            self.release(high);
+        }
+
+        Ok(())
+    }
+
+    /// Forgets the cached operands at the label, where they all are in their slots
+    fn reset_operands(&mut self, height: usize) {
+        self.height = height;
+        self.values[..height].fill(Value::Slot);
+        self.free = ALLOCATABLE.iter().fold(0, |free, reg| free | 1 << reg);
+    }
+
+    // control flow
+
+    /// Deducts the fuel of the instructions since the previous deduction
+    fn sync_fuel(&mut self) {
+        while self.fuel > 0 {
+            let amount = self.fuel.min(2048);
+            self.asm.addi(FUEL, FUEL, -(amount as i32));
+            self.fuel -= amount;
+        }
+    }
+
+    fn jump_to_trap(&mut self, trap: u32) {
+        let offset = Assembler::offset(self.asm.position(), trap_stub(trap));
+        self.asm.jal(ZERO, offset);
+    }
+
+    /// Traps if `condition(rs1, rs2)` holds
+    fn trap_if(&mut self, condition: Condition, rs1: Reg, rs2: Reg, trap: u32) {
+        self.asm.branch(condition.inverted(), rs1, rs2, 8);
+        self.jump_to_trap(trap);
+    }
+
+    /// Points the chain of jumps at the target
+    fn resolve(&mut self, mut chain: u32, target: u32, rd: Reg) {
+        // code is discarded anyway, and the chain may be broken
+        if self.asm.overflow() {
+            return;
+        }
+        while chain != NO_JUMP {
+            let next = self.asm.read(chain);
+            self.asm
+                .patch(chain, jal(rd, Assembler::offset(chain, target)));
+            chain = next;
+        }
+    }
+
+    /// Placeholder of the jump, chained after `chain`, returns the new head
+    fn emit_pending(&mut self, chain: u32) -> u32 {
+        let position = self.asm.position();
+        self.asm.emit(chain);
+        position
+    }
+
+    #[inline(always)]
+    fn label(&self, depth: u32) -> Result<usize, WasmError> {
+        match depth as usize {
+            depth if depth < self.depth => Ok(self.depth - 1 - depth),
+            _ => Err(WasmError::Malformed),
+        }
+    }
+
+    /// Whether the branch is more than a single jump
+    fn needs_stub(&self, depth: u32) -> Result<bool, WasmError> {
+        if depth as usize == self.depth {
+            return Ok(true);
+        }
+        let label = self.labels[self.label(depth)?];
+
+        Ok(!label.is_loop && label.arity != 0 && label.height + label.arity != self.height)
+    }
+
+    /// Branch to the label `depth` levels up, with the operands already flushed
+    fn branch(&mut self, depth: u32) -> Result<(), WasmError> {
+        if depth as usize == self.depth {
+            self.emit_return();
+            return Ok(());
+        }
+        let index = self.label(depth)?;
+        let label = self.labels[index];
+        if label.is_loop {
+            let offset = Assembler::offset(self.asm.position(), label.start);
+            self.asm.jal(ZERO, offset);
+            return Ok(());
+        }
+        if label.arity > 0 && label.height + label.arity != self.height {
+            let from = self.slot(self.height - 1);
+            let to = self.slot(label.height);
+            let (base, offset) = self.address(FRAME, from);
+            self.asm.lw(T5, base, offset);
+            self.asm.lw(T6, base, offset + 4);
+            self.store_pair(T5, T6, FRAME, to);
+        }
+        self.labels[index].pending = self.emit_pending(label.pending);
+
+        Ok(())
+    }
+
+    fn enter(&mut self, is_loop: bool, reader: &mut Reader) -> Result<(), WasmError> {
+        let arity = match reader.read_u8()? {
+            BLOCK_TYPE_EMPTY => 0,
+            _ => 1,
+        };
+        if self.depth == MAX_NESTING {
+            return Err(WasmError::LimitExceeded);
+        }
+        self.labels[self.depth] = Label {
+            is_loop,
+            height: self.height,
+            arity,
+            start: self.asm.position(),
+            ..Label::EMPTY
+        };
+        self.depth += 1;
+
+        Ok(())
+    }
+
+    /// Skips the instruction in unreachable code, `false` if it ends the unreachable part
+    fn skip(&mut self, opcode: u8, reader: &mut Reader) -> Result<bool, WasmError> {
+        match opcode {
+            BLOCK | LOOP | IF => {
+                let _ = reader.read_u8()?;
+                self.skipped_depth += 1;
+            }
+            END | ELSE if self.skipped_depth == 0 => return Ok(false),
+            END => self.skipped_depth -= 1,
+            ELSE => {}
+            BR | BR_IF | CALL | LOCAL_GET | LOCAL_SET | LOCAL_TEE | GLOBAL_GET | GLOBAL_SET => {
+                let _ = reader.read_u32()?;
+            }
+            BR_TABLE => {
+                let len = reader.read_u32()?;
+                for _ in 0..=len {
+                    let _ = reader.read_u32()?;
+                }
+            }
+            CALL_INDIRECT => {
+                let _ = reader.read_u32()?;
+                let _ = reader.read_u8()?;
+            }
+            I32_LOAD..=I64_STORE32 => {
+                let _ = reader.read_u32()?;
+                let _ = reader.read_u32()?;
+            }
+            MEMORY_SIZE | MEMORY_GROW => {
+                let _ = reader.read_u8()?;
+            }
+            I32_CONST => {
+                let _ = reader.read_i32()?;
+            }
+            I64_CONST => {
+                let _ = reader.read_i64()?;
+            }
+            PREFIX_FC => {
+                let bytes = if reader.read_u32()? == MEMORY_COPY {
+                    2
+                } else {
+                    1
+                };
+                reader.read_bytes(bytes)?;
+            }
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    /// Leaves the operands to the kernel: they are flushed, t6 points to the first of them and
+    /// the results are written in their place
+    fn exit_to_kernel(
+        &mut self,
+        kind: u32,
+        argument: u32,
+        inputs: usize,
+        outputs: usize,
+    ) -> Result<(), WasmError> {
+        self.flush();
+        self.sync_fuel();
+        let operands = self
+            .height
+            .checked_sub(inputs)
+            .ok_or(WasmError::Malformed)?;
+        self.add_offset(T6, FRAME, self.slot(operands));
+        self.asm.li(A7, exit_call(kind, argument));
+        self.asm.ecall();
+        self.height = operands;
+        for _ in 0..outputs {
+            self.push_value(Value::Slot)?;
+        }
+
+        Ok(())
+    }
+
+    /// Enters the callee's frame at the first operand and jumps with `jump`
+    fn call(
+        &mut self,
+        num_params: usize,
+        num_results: usize,
+        jump: impl FnOnce(&mut Self),
+    ) -> Result<(), WasmError> {
+        let operands = self
+            .height
+            .checked_sub(num_params)
+            .ok_or(WasmError::Malformed)?;
+        self.asm.mv(T5, FRAME);
+        self.add_offset(FRAME, FRAME, self.slot(operands));
+        jump(self);
+        self.height = operands;
+        for _ in 0..num_results {
+            self.push_value(Value::Slot)?;
+        }
+
+        Ok(())
+    }
+
+    fn compile_instruction(&mut self, opcode: u8, reader: &mut Reader) -> Result<(), WasmError> {
+        match opcode {
+            UNREACHABLE => {
+                self.sync_fuel();
+                self.jump_to_trap(TRAP_UNREACHABLE);
+                self.unreachable = true;
+            }
+            NOP => {}
+            BLOCK => {
+                self.flush();
+                self.enter(false, reader)?;
+            }
+            LOOP => {
+                self.flush();
+                self.sync_fuel();
+                self.enter(true, reader)?;
+                self.trap_if(Condition::Lt, FUEL, ZERO, TRAP_OUT_OF_FUEL);
+            }
+            IF => {
+                let condition = self.pop_low()?;
+                self.flush();
+                self.sync_fuel();
+                self.asm.branch(Condition::Ne, condition, ZERO, 8);
+                let else_jump = self.emit_pending(NO_JUMP);
+                self.release(condition);
+                self.enter(false, reader)?;
+                self.labels[self.depth - 1].else_jump = else_jump;
+            }
+            ELSE => {
+                let index = self.depth.checked_sub(1).ok_or(WasmError::Malformed)?;
+                if !self.unreachable {
+                    self.flush();
+                    self.sync_fuel();
+                    self.labels[index].pending = self.emit_pending(self.labels[index].pending);
+                }
+                let label = self.labels[index];
+                self.resolve(label.else_jump, self.asm.position(), ZERO);
+                self.labels[index].else_jump = NO_JUMP;
+                self.reset_operands(label.height);
+                self.fuel = 0;
+                self.unreachable = false;
+            }
+            END => {
+                if !self.unreachable {
+                    self.flush();
+                    self.sync_fuel();
+                }
+                self.depth -= 1;
+                let label = self.labels[self.depth];
+                let position = self.asm.position();
+                self.resolve(label.else_jump, position, ZERO);
+                self.resolve(label.pending, position, ZERO);
+                self.reset_operands(label.height + label.arity);
+                self.fuel = 0;
+                self.unreachable = false;
+            }
+            BR => {
+                let depth = reader.read_u32()?;
+                self.flush();
+                self.sync_fuel();
+                self.branch(depth)?;
+                self.unreachable = true;
+            }
+            BR_IF => {
+                let depth = reader.read_u32()?;
+                let condition = self.pop_low()?;
+                self.flush();
+                self.sync_fuel();
+                let skip = self.asm.position();
+                self.asm.emit(0);
+                self.branch(depth)?;
+                let target = self.asm.position();
+                self.asm
+                    .patch_branch(skip, Condition::Eq, condition, ZERO, target);
+                self.release(condition);
+            }
+            BR_TABLE => {
+                let len = reader.read_u32()?;
+                let index = self.pop_low()?;
+                self.flush();
+                self.sync_fuel();
+                // out of range index takes the default target, that is the last one
+                self.asm.li(T6, len);
+                self.asm.branch(Condition::Ltu, index, T6, 8);
+                self.asm.mv(index, T6);
+                self.asm.slli(T6, index, 2);
+                self.asm.auipc(T5, 0);
+                self.asm.add(T5, T5, T6);
+                self.asm.jalr(ZERO, T5, 12);
+                self.release(index);
+                let table = self.asm.position();
+                for _ in 0..=len {
+                    self.asm.emit(0);
+                }
+                for target in 0..=len {
+                    let entry = table + 4 * target;
+                    let depth = reader.read_u32()?;
+                    if self.needs_stub(depth)? {
+                        let stub = self.asm.position();
+                        self.asm
+                            .patch(entry, jal(ZERO, Assembler::offset(entry, stub)));
+                        self.branch(depth)?;
+                        continue;
+                    }
+                    let label_index = self.label(depth)?;
+                    let label = self.labels[label_index];
+                    if label.is_loop {
+                        self.asm
+                            .patch(entry, jal(ZERO, Assembler::offset(entry, label.start)));
+                    } else {
+                        self.asm.patch(entry, label.pending);
+                        self.labels[label_index].pending = entry;
+                    }
+                }
+                self.unreachable = true;
+            }
+            RETURN => {
+                self.flush();
+                self.sync_fuel();
+                self.emit_return();
+                self.unreachable = true;
+            }
+            CALL => {
+                let function = reader.read_u32()?;
+                let (num_params, num_results) = self.signatures.of_function(function);
+                if function < self.num_imported_functions {
+                    let (inputs, outputs) = (num_params as usize, num_results as usize);
+                    return self.exit_to_kernel(EXIT_HOST_CALL, function, inputs, outputs);
+                }
+                self.flush();
+                self.sync_fuel();
+                self.call(num_params as usize, num_results as usize, |this| {
+                    let callee = this.offsets[function as usize];
+                    if callee != NO_CODE {
+                        let offset = Assembler::offset(this.asm.position(), callee);
+                        this.asm.jal(RA, offset);
+                    } else {
+                        let chain = this.calls[function as usize];
+                        this.calls[function as usize] = this.emit_pending(chain);
+                    }
+                })?;
+            }
+            CALL_INDIRECT => {
+                let type_index = reader.read_u32()?;
+                let _ = reader.read_u8()?;
+                let (num_params, num_results) = self.signatures.of_type(type_index);
+                let element = self.pop_low()?;
+                self.flush();
+                self.sync_fuel();
+                self.asm.mv(T6, element);
+                self.release(element);
+                let operands = self
+                    .height
+                    .checked_sub(num_params as usize)
+                    .ok_or(WasmError::Malformed)?;
+                self.add_offset(T5, FRAME, self.slot(operands));
+                self.asm.li(A7, exit_call(EXIT_CALL_INDIRECT, type_index));
+                self.asm.ecall();
+                // the kernel already called the host function
+                let skip = self.asm.position();
+                self.asm.emit(0);
+                self.call(num_params as usize, num_results as usize, |this| {
+                    this.asm.jalr(RA, T6, 0);
+                })?;
+                let target = self.asm.position();
+                self.asm.patch_branch(skip, Condition::Eq, T6, ZERO, target);
+            }
+            DROP => self.discard()?,
+            SELECT => {
+                let condition = self.pop_low()?;
+                let (b_low, b_high) = self.pop()?;
+                let (a_low, a_high) = self.pop()?;
+                let low = self.alloc()?;
+                let high = self.alloc()?;
+                self.asm.mv(low, a_low);
+                self.asm.mv(high, a_high);
+                self.asm.branch(Condition::Ne, condition, ZERO, 12);
+                self.asm.mv(low, b_low);
+                self.asm.mv(high, b_high);
+                for reg in [condition, a_low, a_high, b_low, b_high] {
+                    self.release(reg);
+                }
+                self.push(low, high)?;
+            }
+            LOCAL_GET => {
+                let offset = reader.read_u32()? as i32 * 8;
+                let low = self.alloc()?;
+                let high = self.alloc()?;
+                self.load_pair(low, high, FRAME, offset);
+                self.push(low, high)?;
+            }
+            LOCAL_SET | LOCAL_TEE => {
+                let offset = reader.read_u32()? as i32 * 8;
+                let (low, high) = self.pop()?;
+                self.store_pair(low, high, FRAME, offset);
+                if opcode == LOCAL_TEE {
+                    self.push(low, high)?;
+                } else {
+                    self.release(low);
+                    self.release(high);
+                }
+            }
+            GLOBAL_GET => {
+                let offset = reader.read_u32()? as i32 * 8;
+                let low = self.alloc()?;
+                let high = self.alloc()?;
+                self.load_pair(low, high, GLOBALS, offset);
+                self.push(low, high)?;
+            }
+            GLOBAL_SET => {
+                let offset = reader.read_u32()? as i32 * 8;
+                let (low, high) = self.pop()?;
+                self.store_pair(low, high, GLOBALS, offset);
+                self.release(low);
+                self.release(high);
+            }
+            I32_LOAD..=I64_LOAD32_U => self.load(opcode, reader)?,
+            I32_STORE..=I64_STORE32 => self.store(opcode, reader)?,
+            MEMORY_SIZE => {
+                let _ = reader.read_u8()?;
+                let rd = self.alloc()?;
+                self.asm.srli(rd, MEMORY_LEN, 16);
+                self.push(rd, ZERO)?;
+            }
+            MEMORY_GROW => {
+                let _ = reader.read_u8()?;
+                self.exit_to_kernel(EXIT_MEMORY_GROW, 0, 1, 1)?;
+            }
+            I32_CONST => {
+                let value = reader.read_i32()? as u32;
+                let low = self.constant(value)?;
+                self.push(low, ZERO)?;
+            }
+            I64_CONST => {
+                let value = reader.read_i64()? as u64;
+                let low = self.constant(value as u32)?;
+                let high = self.constant((value >> 32) as u32)?;
+                self.push(low, high)?;
+            }
+            I32_EQZ => {
+                let a = self.pop_low()?;
+                let rd = self.alloc()?;
+                self.asm.sltiu(rd, a, 1);
+                self.release(a);
+                self.push(rd, ZERO)?;
+            }
+            I64_EQZ => {
+                let (low, high) = self.pop()?;
+                let rd = self.alloc()?;
+                self.asm.or(rd, low, high);
+                self.asm.sltiu(rd, rd, 1);
+                self.release(low);
+                self.release(high);
+                self.push(rd, ZERO)?;
+            }
+            I32_EQ..=I32_GE_U | I32_ADD..=I32_ROTR => {
+                let b = self.pop_low()?;
+                let a = self.pop_low()?;
+                let rd = self.alloc()?;
+                self.binary32(opcode, rd, a, b);
+                self.release(a);
+                self.release(b);
+                self.push(rd, ZERO)?;
+            }
+            I32_CLZ | I32_CTZ | I32_POPCNT => {
+                let a = self.pop_low()?;
+                let rd = self.alloc()?;
+                match opcode {
+                    I32_CLZ => self.clz32(rd, a),
+                    I32_CTZ => self.ctz32(rd, a),
+                    _ => self.popcnt32(rd, a),
+                }
+                self.release(a);
+                self.push(rd, ZERO)?;
+            }
+            I64_EQ..=I64_GE_U => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                let rd = self.alloc()?;
+                self.compare64(opcode, rd, a, b);
+                for reg in [a.0, a.1, b.0, b.1] {
+                    self.release(reg);
+                }
+                self.push(rd, ZERO)?;
+            }
+            I64_CLZ | I64_CTZ | I64_POPCNT => {
+                let (low, high) = self.pop()?;
+                let rd = self.alloc()?;
+                let other = self.alloc()?;
+                self.count64(opcode, rd, other, low, high);
+                for reg in [low, high, other] {
+                    self.release(reg);
+                }
+                self.push(rd, ZERO)?;
+            }
+            I64_DIV_S..=I64_REM_U => {
+                self.exit_to_kernel(EXIT_I64_DIV_REM, opcode as u32, 2, 1)?;
+            }
+            I64_ADD..=I64_MUL | I64_AND..=I64_ROTR => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                let low = self.alloc()?;
+                let high = self.alloc()?;
+                let x = self.alloc()?;
+                let y = self.alloc()?;
+                self.binary64(opcode, (low, high), a, b, (x, y));
+                for reg in [a.0, a.1, b.0, b.1, x, y] {
+                    self.release(reg);
+                }
+                self.push(low, high)?;
+            }
+            I32_WRAP_I64 | I64_EXTEND_I32_U => {
+                let low = self.pop_low()?;
+                self.push(low, ZERO)?;
+            }
+            I64_EXTEND_I32_S => {
+                let low = self.pop_low()?;
+                let high = self.alloc()?;
+                self.asm.srai(high, low, 31);
+                self.push(low, high)?;
+            }
+            I32_EXTEND8_S | I32_EXTEND16_S => {
+                let a = self.pop_low()?;
+                let rd = self.alloc()?;
+                let shift = if opcode == I32_EXTEND8_S { 24 } else { 16 };
+                self.asm.slli(rd, a, shift);
+                self.asm.srai(rd, rd, shift);
+                self.release(a);
+                self.push(rd, ZERO)?;
+            }
+            I64_EXTEND8_S | I64_EXTEND16_S | I64_EXTEND32_S => {
+                let a = self.pop_low()?;
+                let low = self.alloc()?;
+                let high = self.alloc()?;
+                match opcode {
+                    I64_EXTEND8_S => {
+                        self.asm.slli(low, a, 24);
+                        self.asm.srai(low, low, 24);
+                    }
+                    I64_EXTEND16_S => {
+                        self.asm.slli(low, a, 16);
+                        self.asm.srai(low, low, 16);
+                    }
+                    _ => self.asm.mv(low, a),
+                }
+                self.asm.srai(high, low, 31);
+                self.release(a);
+                self.push(low, high)?;
+            }
+            PREFIX_FC => match reader.read_u32()? {
+                MEMORY_COPY => {
+                    reader.read_bytes(2)?;
+                    self.exit_to_kernel(EXIT_MEMORY_COPY, 0, 3, 0)?;
+                }
+                MEMORY_FILL => {
+                    reader.read_bytes(1)?;
+                    self.exit_to_kernel(EXIT_MEMORY_FILL, 0, 3, 0)?;
+                }
+                _ => return Err(WasmError::Unsupported),
+            },
+            _ => return Err(WasmError::Unsupported),
+        }
+
+        Ok(())
+    }
+
+    /// Register with the constant, zero needs none
+    fn constant(&mut self, value: u32) -> Result<Reg, WasmError> {
+        if value == 0 {
+            return Ok(ZERO);
+        }
+        let rd = self.alloc()?;
+        self.asm.li(rd, value);
+
+        Ok(rd)
+    }
+
+    // memory
+
+    /// Checks `[address + offset, address + offset + size)` against the memory size and leaves
+    /// the host address of its end in t6. Returns the offset of the access from t6
+    fn memory_access(&mut self, address: Reg, offset: u32, size: u32) -> i32 {
+        let end = offset as u64 + size as u64;
+        if end > u32::MAX as u64 {
+            self.jump_to_trap(TRAP_MEMORY_OUT_OF_BOUNDS);
+            return 0;
+        }
+        self.add_offset(T6, address, end as u32 as i32);
+        // the sum wraps around, or goes past the end of the memory
+        self.trap_if(Condition::Ltu, T6, address, TRAP_MEMORY_OUT_OF_BOUNDS);
+        self.trap_if(Condition::Ltu, MEMORY_LEN, T6, TRAP_MEMORY_OUT_OF_BOUNDS);
+        self.asm.add(T6, T6, MEMORY);
+
+        -(size as i32)
+    }
+
+    fn load(&mut self, opcode: u8, reader: &mut Reader) -> Result<(), WasmError> {
+        let _alignment = reader.read_u32()?;
+        let offset = reader.read_u32()?;
+        let size = match opcode {
+            I64_LOAD => 8,
+            I32_LOAD | I64_LOAD32_S | I64_LOAD32_U => 4,
+            I32_LOAD16_S | I32_LOAD16_U | I64_LOAD16_S | I64_LOAD16_U => 2,
+            _ => 1,
+        };
+        let address = self.pop_low()?;
+        let low = self.alloc()?;
+        let high = match opcode {
+            I64_LOAD | I64_LOAD8_S | I64_LOAD16_S | I64_LOAD32_S => self.alloc()?,
+            _ => ZERO,
+        };
+        let at = self.memory_access(address, offset, size);
+        match opcode {
+            I32_LOAD | I64_LOAD | I64_LOAD32_S | I64_LOAD32_U => self.asm.lw(low, T6, at),
+            I32_LOAD8_S | I64_LOAD8_S => self.asm.lb(low, T6, at),
+            I32_LOAD8_U | I64_LOAD8_U => self.asm.lbu(low, T6, at),
+            I32_LOAD16_S | I64_LOAD16_S => self.asm.lh(low, T6, at),
+            _ => self.asm.lhu(low, T6, at),
+        }
+        match opcode {
+            I64_LOAD => self.asm.lw(high, T6, at + 4),
+            I64_LOAD8_S | I64_LOAD16_S | I64_LOAD32_S => self.asm.srai(high, low, 31),
+            _ => {}
+        }
+        self.release(address);
+
+        self.push(low, high)
+    }
+
+    fn store(&mut self, opcode: u8, reader: &mut Reader) -> Result<(), WasmError> {
+        let _alignment = reader.read_u32()?;
+        let offset = reader.read_u32()?;
+        let (low, high) = if opcode == I64_STORE {
+            self.pop()?
+        } else {
+            (self.pop_low()?, ZERO)
+        };
+        let address = self.pop_low()?;
+        let size = match opcode {
+            I64_STORE => 8,
+            I32_STORE | I64_STORE32 => 4,
+            I32_STORE16 | I64_STORE16 => 2,
+            _ => 1,
+        };
+        let at = self.memory_access(address, offset, size);
+        match size {
+            8 => {
+                self.asm.sw(low, T6, at);
+                self.asm.sw(high, T6, at + 4);
+            }
+            4 => self.asm.sw(low, T6, at),
+            2 => self.asm.sh(low, T6, at),
+            _ => self.asm.sb(low, T6, at),
+        }
+        for reg in [low, high, address] {
+            self.release(reg);
+        }
+
+        Ok(())
+    }
+
+    // arithmetic
+
+    fn binary32(&mut self, opcode: u8, rd: Reg, a: Reg, b: Reg) {
+        let asm = &mut self.asm;
+        match opcode {
+            I32_EQ | I32_NE => {
+                asm.xor(rd, a, b);
+                if opcode == I32_EQ {
+                    asm.sltiu(rd, rd, 1);
+                } else {
+                    asm.sltu(rd, ZERO, rd);
+                }
+            }
+            I32_LT_S => asm.slt(rd, a, b),
+            I32_LT_U => asm.sltu(rd, a, b),
+            I32_GT_S => asm.slt(rd, b, a),
+            I32_GT_U => asm.sltu(rd, b, a),
+            I32_LE_S | I32_LE_U | I32_GE_S | I32_GE_U => {
+                // negation of the strict comparison the other way around
+                let (x, y) = if matches!(opcode, I32_LE_S | I32_LE_U) {
+                    (b, a)
+                } else {
+                    (a, b)
+                };
+                if matches!(opcode, I32_LE_S | I32_GE_S) {
+                    asm.slt(rd, x, y);
+                } else {
+                    asm.sltu(rd, x, y);
+                }
+                asm.xori(rd, rd, 1);
+            }
+            I32_ADD => asm.add(rd, a, b),
+            I32_SUB => asm.sub(rd, a, b),
+            I32_MUL => asm.mul(rd, a, b),
+            I32_DIV_S | I32_DIV_U | I32_REM_S | I32_REM_U => {
+                self.trap_if(Condition::Eq, b, ZERO, TRAP_DIVISION_BY_ZERO);
+                let asm = &mut self.asm;
+                match opcode {
+                    I32_DIV_S => {
+                        // i32::MIN / -1 overflows, the remainder of it is just zero
+                        asm.addi(T6, ZERO, -1);
+                        asm.branch(Condition::Ne, b, T6, 16);
+                        asm.lui(T5, 0x8000_0000);
+                        asm.branch(Condition::Ne, a, T5, 8);
+                        self.jump_to_trap(TRAP_INTEGER_OVERFLOW);
+                        self.asm.div(rd, a, b);
+                    }
+                    I32_DIV_U => asm.divu(rd, a, b),
+                    I32_REM_S => asm.rem(rd, a, b),
+                    _ => asm.remu(rd, a, b),
+                }
+            }
+            I32_AND => asm.and(rd, a, b),
+            I32_OR => asm.or(rd, a, b),
+            I32_XOR => asm.xor(rd, a, b),
+            I32_SHL => asm.sll(rd, a, b),
+            I32_SHR_S => asm.sra(rd, a, b),
+            I32_SHR_U => asm.srl(rd, a, b),
+            I32_ROTL | I32_ROTR => {
+                // shifts take the amount modulo 32, so `-b` shifts by `32 - b`
+                asm.sub(T6, ZERO, b);
+                if opcode == I32_ROTL {
+                    asm.sll(T5, a, b);
+                    asm.srl(T6, a, T6);
+                } else {
+                    asm.srl(T5, a, b);
+                    asm.sll(T6, a, T6);
+                }
+                asm.or(rd, T5, T6);
+            }
+            _ => {}
+        }
+    }
+
+    fn clz32(&mut self, rd: Reg, a: Reg) {
+        let asm = &mut self.asm;
+        asm.mv(T6, a);
+        asm.addi(rd, ZERO, 32);
+        asm.branch(Condition::Eq, T6, ZERO, 24);
+        asm.addi(rd, ZERO, 0);
+        asm.branch(Condition::Lt, T6, ZERO, 16);
+        asm.slli(T6, T6, 1);
+        asm.addi(rd, rd, 1);
+        asm.jal(ZERO, -12);
+    }
+
+    fn ctz32(&mut self, rd: Reg, a: Reg) {
+        let asm = &mut self.asm;
+        asm.mv(T6, a);
+        asm.addi(rd, ZERO, 32);
+        asm.branch(Condition::Eq, T6, ZERO, 28);
+        asm.addi(rd, ZERO, 0);
+        asm.andi(T5, T6, 1);
+        asm.branch(Condition::Ne, T5, ZERO, 16);
+        asm.srli(T6, T6, 1);
+        asm.addi(rd, rd, 1);
+        asm.jal(ZERO, -16);
+    }
+
+    fn popcnt32(&mut self, rd: Reg, a: Reg) {
+        let asm = &mut self.asm;
+        asm.mv(T6, a);
+        asm.addi(rd, ZERO, 0);
+        asm.branch(Condition::Eq, T6, ZERO, 20);
+        asm.addi(T5, T6, -1);
+        asm.and(T6, T6, T5);
+        asm.addi(rd, rd, 1);
+        asm.jal(ZERO, -16);
+    }
+
+    /// Counts in the half that decides first, and continues into the other one if it's all zeroes
+    fn count64(&mut self, opcode: u8, rd: Reg, other: Reg, low: Reg, high: Reg) {
+        if opcode == I64_POPCNT {
+            self.popcnt32(rd, low);
+            self.popcnt32(other, high);
+            self.asm.add(rd, rd, other);
+            return;
+        }
+        let (first, second) = if opcode == I64_CLZ {
+            (high, low)
+        } else {
+            (low, high)
+        };
+        if opcode == I64_CLZ {
+            self.clz32(rd, first);
+        } else {
+            self.ctz32(rd, first);
+        }
+        self.asm.addi(T5, ZERO, 32);
+        let skip = self.asm.position();
+        self.asm.emit(0);
+        if opcode == I64_CLZ {
+            self.clz32(other, second);
+        } else {
+            self.ctz32(other, second);
+        }
+        self.asm.add(rd, rd, other);
+        let target = self.asm.position();
+        self.asm.patch_branch(skip, Condition::Ne, rd, T5, target);
+    }
+
+    /// `a < b` of the 64-bit operands, the high halves decide unless they are equal
+    fn less64(&mut self, rd: Reg, a: (Reg, Reg), b: (Reg, Reg), signed: bool) {
+        if signed {
+            self.asm.slt(rd, a.1, b.1);
+        } else {
+            self.asm.sltu(rd, a.1, b.1);
+        }
+        self.asm.branch(Condition::Ne, a.1, b.1, 8);
+        self.asm.sltu(rd, a.0, b.0);
+    }
+
+    fn compare64(&mut self, opcode: u8, rd: Reg, a: (Reg, Reg), b: (Reg, Reg)) {
+        match opcode {
+            I64_EQ | I64_NE => {
+                self.asm.xor(T5, a.0, b.0);
+                self.asm.xor(T6, a.1, b.1);
+                self.asm.or(T5, T5, T6);
+                if opcode == I64_EQ {
+                    self.asm.sltiu(rd, T5, 1);
+                } else {
+                    self.asm.sltu(rd, ZERO, T5);
+                }
+            }
+            I64_LT_S => self.less64(rd, a, b, true),
+            I64_LT_U => self.less64(rd, a, b, false),
+            I64_GT_S => self.less64(rd, b, a, true),
+            I64_GT_U => self.less64(rd, b, a, false),
+            _ => {
+                let signed = matches!(opcode, I64_LE_S | I64_GE_S);
+                if matches!(opcode, I64_LE_S | I64_LE_U) {
+                    self.less64(rd, b, a, signed);
+                } else {
+                    self.less64(rd, a, b, signed);
+                }
+                self.asm.xori(rd, rd, 1);
+            }
+        }
+    }
+
+    fn binary64(
+        &mut self,
+        opcode: u8,
+        (low, high): (Reg, Reg),
+        a: (Reg, Reg),
+        b: (Reg, Reg),
+        (x, y): (Reg, Reg),
+    ) {
+        let asm = &mut self.asm;
+        match opcode {
+            I64_ADD => {
+                asm.add(low, a.0, b.0);
+                asm.sltu(T6, low, a.0);
+                asm.add(high, a.1, b.1);
+                asm.add(high, high, T6);
+            }
+            I64_SUB => {
+                asm.sltu(T6, a.0, b.0);
+                asm.sub(low, a.0, b.0);
+                asm.sub(high, a.1, b.1);
+                asm.sub(high, high, T6);
+            }
+            I64_MUL => {
+                asm.mul(T5, a.0, b.1);
+                asm.mul(T6, a.1, b.0);
+                asm.add(T5, T5, T6);
+                asm.mulhu(T6, a.0, b.0);
+                asm.add(high, T5, T6);
+                asm.mul(low, a.0, b.0);
+            }
+            I64_AND => {
+                asm.and(low, a.0, b.0);
+                asm.and(high, a.1, b.1);
+            }
+            I64_OR => {
+                asm.or(low, a.0, b.0);
+                asm.or(high, a.1, b.1);
+            }
+            I64_XOR => {
+                asm.xor(low, a.0, b.0);
+                asm.xor(high, a.1, b.1);
+            }
+            I64_SHL => {
+                asm.andi(T6, b.0, 63);
+                asm.andi(T5, T6, 32);
+                asm.branch(Condition::Eq, T5, ZERO, 16);
+                // by 32 and more, shifts take the amount modulo 32
+                asm.sll(high, a.0, T6);
+                asm.addi(low, ZERO, 0);
+                asm.jal(ZERO, 28);
+                asm.sll(high, a.1, T6);
+                asm.sll(low, a.0, T6);
+                asm.branch(Condition::Eq, T6, ZERO, 16);
+                asm.sub(T5, ZERO, T6);
+                asm.srl(T5, a.0, T5);
+                asm.or(high, high, T5);
+            }
+            I64_SHR_S | I64_SHR_U => {
+                asm.andi(T6, b.0, 63);
+                asm.andi(T5, T6, 32);
+                asm.branch(Condition::Eq, T5, ZERO, 16);
+                if opcode == I64_SHR_S {
+                    asm.sra(low, a.1, T6);
+                    asm.srai(high, a.1, 31);
+                } else {
+                    asm.srl(low, a.1, T6);
+                    asm.addi(high, ZERO, 0);
+                }
+                asm.jal(ZERO, 28);
+                asm.srl(low, a.0, T6);
+                if opcode == I64_SHR_S {
+                    asm.sra(high, a.1, T6);
+                } else {
+                    asm.srl(high, a.1, T6);
+                }
+                asm.branch(Condition::Eq, T6, ZERO, 16);
+                asm.sub(T5, ZERO, T6);
+                asm.sll(T5, a.1, T5);
+                asm.or(low, low, T5);
+            }
+            I64_ROTL | I64_ROTR => {
+                // rotation to the right is the one to the left by `64 - b`
+                if opcode == I64_ROTL {
+                    asm.andi(T6, b.0, 63);
+                } else {
+                    asm.sub(T6, ZERO, b.0);
+                    asm.andi(T6, T6, 63);
+                }
+                // by 32 swaps the halves, then rotate by the rest
+                asm.andi(T5, T6, 32);
+                asm.mv(low, a.0);
+                asm.mv(high, a.1);
+                asm.branch(Condition::Eq, T5, ZERO, 12);
+                asm.mv(low, a.1);
+                asm.mv(high, a.0);
+                asm.andi(T6, T6, 31);
+                asm.branch(Condition::Eq, T6, ZERO, 32);
+                asm.sub(T5, ZERO, T6);
+                asm.srl(x, high, T5);
+                asm.srl(y, low, T5);
+                asm.sll(low, low, T6);
+                asm.or(low, low, x);
+                asm.sll(high, high, T6);
+                asm.or(high, high, y);
+            }
+            _ => {}
+        }
+    }
+}
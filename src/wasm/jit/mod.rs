@@ -0,0 +1,133 @@
+pub mod assembler;
+pub mod compiler;
+pub mod runtime;
+
+pub use self::compiler::compile;
+pub use self::runtime::invoke;
+
+use super::WasmError;
+use crate::system::SystemError;
+
+// Native code of WASM modules. Validated modules are compiled to RV32IM at deployment, in a single
+// pass over every body, and the code is appended to the artifact after the side table. It runs in
+// U-mode: PMP maps its code execute-only, and lets it touch nothing but the value stack (with the
+// globals below it) and the linear memory. Modules the compiler gives up on (too much code,
+// too deep operand stacks) are left to the interpreter, that gets the same results for the
+// same fuel, except for the stack limits that native code checks per function rather than
+// per value.
+//
+// Native code gets back to the kernel with an `ecall` that has `EXIT_CALL_BIT` in a7: the low byte
+// is the kind of the exit, the rest is its argument. Exits that take operands point t6 at the
+// first of them on the value stack, and get the results written in their place.
+//
+// Section layout (all little-endian u32 words):
+//  - header: `[num_functions, code_len]`
+//  - offset of every function in the code, `NO_CODE` for the imported ones
+//  - code, starting with the stubs of the exits that aren't calls
+
+pub const HEADER_WORDS: usize = 2;
+pub const NO_CODE: u32 = u32::MAX;
+/// Code has to be reachable by `jal` from anywhere in it
+pub const MAX_CODE_SIZE: usize = 1 << 20;
+
+/// Function called by the kernel returned
+pub const EXIT_RETURN: u32 = 0;
+/// Argument is the index in `TRAPS`
+pub const EXIT_TRAP: u32 = 1;
+/// Argument is the imported function
+pub const EXIT_HOST_CALL: u32 = 2;
+pub const EXIT_MEMORY_GROW: u32 = 3;
+pub const EXIT_MEMORY_COPY: u32 = 4;
+pub const EXIT_MEMORY_FILL: u32 = 5;
+/// Argument is the expected type, t6 is the table element and t5 points to the call operands.
+/// Kernel puts the address of the callee into t6, or zero if it called the host function itself
+pub const EXIT_CALL_INDIRECT: u32 = 6;
+/// Argument is the opcode, there are no 64-bit divisions in RV32IM
+pub const EXIT_I64_DIV_REM: u32 = 7;
+
+pub const TRAP_UNREACHABLE: u32 = 0;
+pub const TRAP_MEMORY_OUT_OF_BOUNDS: u32 = 1;
+pub const TRAP_DIVISION_BY_ZERO: u32 = 2;
+pub const TRAP_INTEGER_OVERFLOW: u32 = 3;
+pub const TRAP_STACK_OVERFLOW: u32 = 4;
+pub const TRAP_CALL_STACK_EXHAUSTED: u32 = 5;
+pub const TRAP_OUT_OF_FUEL: u32 = 6;
+
+pub const TRAPS: [WasmError; 7] = [
+    WasmError::Unreachable,
+    WasmError::MemoryOutOfBounds,
+    WasmError::DivisionByZero,
+    WasmError::IntegerOverflow,
+    WasmError::StackOverflow,
+    WasmError::CallStackExhausted,
+    WasmError::OutOfFuel,
+];
+
+/// Every stub loads a7 in two instructions and makes the call
+const STUB_SIZE: u32 = 12;
+pub const RETURN_STUB: u32 = 0;
+
+#[must_use]
+#[inline(always)]
+pub const fn trap_stub(trap: u32) -> u32 {
+    STUB_SIZE * (1 + trap)
+}
+
+#[must_use]
+#[inline(always)]
+pub const fn exit_call(kind: u32, argument: u32) -> u32 {
+    crate::user_mode::EXIT_CALL_BIT | argument << 8 | kind
+}
+
+/// Native code of the executing module, read from the artifact
+pub struct NativeCode {
+    offsets: &'static [u32],
+    code: &'static [u8],
+}
+
+impl NativeCode {
+    /// Section is empty if the module wasn't compiled
+    pub fn from_artifact(section: &'static [u8]) -> Result<Option<Self>, WasmError> {
+        if section.is_empty() {
+            return Ok(None);
+        }
+        if section.len() & 3 != 0 || section.len() < HEADER_WORDS * 4 {
+            return Err(WasmError::System(SystemError::InvalidCode));
+        }
+        // artifacts are word-aligned, and so is the side table before the section
+        let words: &'static [u32] = unsafe {
+            core::slice::from_raw_parts(section.as_ptr().cast::<u32>(), section.len() / 4)
+        };
+        let num_functions = words[0] as usize;
+        let code_len = words[1] as usize;
+        let code_start = (HEADER_WORDS + num_functions) * 4;
+        if code_start + code_len != section.len() {
+            return Err(WasmError::System(SystemError::InvalidCode));
+        }
+
+        Ok(Some(Self {
+            offsets: &words[HEADER_WORDS..HEADER_WORDS + num_functions],
+            code: &section[code_start..],
+        }))
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn code(&self) -> &'static [u8] {
+        self.code
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub fn address(&self, offset: u32) -> u32 {
+        (self.code.as_ptr() as usize as u32).wrapping_add(offset)
+    }
+
+    /// Address of the compiled function
+    pub fn function(&self, function: u32) -> Result<u32, WasmError> {
+        match self.offsets.get(function as usize) {
+            Some(&offset) if offset != NO_CODE => Ok(self.address(offset)),
+            _ => Err(WasmError::System(SystemError::InvalidCode)),
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use super::*;
+use crate::context::Context;
+use crate::cpu::Registers;
+use crate::pmp::{PMP_REGION_CODE, PMP_REGION_MEMORY, PMP_REGION_STACK};
+use crate::system::System;
+use crate::user_mode::UserExit;
+use crate::wasm::interpreter::{self, Executor, ExitReason, MAX_FRAMES, VALUE_STACK_SIZE};
+
+use riscv::register::Permission;
+
+/// Calls the function from the host on its native code, returns `Some` if it finished
+/// the execution
+pub fn invoke(
+    executor: &mut Executor,
+    system: &mut System,
+    native: &NativeCode,
+    function: u32,
+) -> Result<Option<ExitReason>, WasmError> {
+    if let Some(host) = executor.instance.functions[function as usize].host {
+        return executor.call_host(system, host);
+    }
+
+    // globals live at the bottom of the value stack while the native code runs
+    let num_globals = executor.instance.globals.len();
+    for index in 0..num_globals {
+        let value = executor.instance.globals[index];
+        executor.values()[index] = value;
+    }
+    let base = stack_base(executor);
+    let frame = base + num_globals as u32 * 8;
+    let mut context = Context::new(native.function(function)?);
+    context.set_register(Registers::Ra, native.address(RETURN_STUB));
+    context.set_register(Registers::S0, frame);
+    context.set_register(Registers::T5, frame);
+    context.set_register(Registers::S4, base);
+    context.set_register(Registers::S5, base + (VALUE_STACK_SIZE * 8) as u32);
+    context.set_register(Registers::S6, MAX_FRAMES as u32);
+
+    let result = run(executor, system, native, &mut context);
+    for index in 0..num_globals {
+        executor.instance.globals[index] = executor.values()[index];
+    }
+
+    result
+}
+
+/// Handles the exits of the native code until it returns to the host or traps
+fn run(
+    executor: &mut Executor,
+    system: &mut System,
+    native: &NativeCode,
+    context: &mut Context,
+) -> Result<Option<ExitReason>, WasmError> {
+    loop {
+        let UserExit::Call(call) = resume(executor, native, context)? else {
+            return Err(WasmError::NativeCodeFault);
+        };
+        let argument = call >> 8;
+        match call & 0xff {
+            EXIT_RETURN => return Ok(None),
+            EXIT_TRAP => {
+                return Err(TRAPS
+                    .get(argument as usize)
+                    .copied()
+                    .unwrap_or(WasmError::NativeCodeFault))
+            }
+            EXIT_HOST_CALL => {
+                let operands = operands(executor, context.register(Registers::T6), 0)?;
+                if let Some(exit) = call_host(executor, system, argument, operands)? {
+                    return Ok(Some(exit));
+                }
+            }
+            EXIT_MEMORY_GROW => {
+                let operands = operands(executor, context.register(Registers::T6), 1)?;
+                let delta = executor.values()[operands] as u32;
+                let result = executor.grow_memory(system, delta)?;
+                executor.values()[operands] = result as u64;
+            }
+            EXIT_MEMORY_COPY | EXIT_MEMORY_FILL => {
+                let operands = operands(executor, context.register(Registers::T6), 3)?;
+                let values = executor.values();
+                let destination = values[operands] as u32;
+                let source = values[operands + 1] as u32;
+                let len = values[operands + 2] as u32;
+                if call & 0xff == EXIT_MEMORY_COPY {
+                    executor.memory_copy(destination, source, len)?;
+                } else {
+                    executor.memory_fill(destination, source as u8, len)?;
+                }
+            }
+            EXIT_CALL_INDIRECT => {
+                let element = context.register(Registers::T6);
+                let function = executor.resolve_indirect(argument, element)?;
+                let callee = if executor.instance.functions[function as usize]
+                    .host
+                    .is_some()
+                {
+                    let operands = operands(executor, context.register(Registers::T5), 0)?;
+                    if let Some(exit) = call_host(executor, system, function, operands)? {
+                        return Ok(Some(exit));
+                    }
+                    0
+                } else {
+                    native.function(function)?
+                };
+                context.set_register(Registers::T6, callee);
+            }
+            EXIT_I64_DIV_REM => {
+                let operands = operands(executor, context.register(Registers::T6), 2)?;
+                let values = executor.values();
+                let (a, b) = (values[operands], values[operands + 1]);
+                values[operands] = interpreter::i64_div_rem(argument as u8, a, b)?;
+            }
+            _ => return Err(WasmError::NativeCodeFault),
+        }
+    }
+}
+
+/// Runs the native code until its next exit, with the fuel it spent charged
+fn resume(
+    executor: &mut Executor,
+    native: &NativeCode,
+    context: &mut Context,
+) -> Result<UserExit, WasmError> {
+    // s3 is signed, and no block has anywhere near i32::MAX ticks for the cap to show
+    let given = executor.resources.remaining().min(i32::MAX as u64) as u32;
+    let memory = executor.instance.memory.as_slice();
+    context.set_register(Registers::S1, memory.as_ptr() as usize as u32);
+    context.set_register(Registers::S2, memory.len() as u32);
+    context.set_register(Registers::S3, given);
+
+    // memory may grow during the exits, so the mappings are updated on every resume
+    let code = native.code();
+    context.pmp.map(
+        PMP_REGION_CODE,
+        code.as_ptr() as usize,
+        code.len(),
+        Permission::X,
+    );
+    context.pmp.map(
+        PMP_REGION_MEMORY,
+        memory.as_ptr() as usize,
+        memory.len(),
+        Permission::RW,
+    );
+    let stack = executor.values();
+    context.pmp.map(
+        PMP_REGION_STACK,
+        stack.as_ptr() as usize,
+        stack.len() * 8,
+        Permission::RW,
+    );
+    // fuel is metered by the code itself, in Wasm instructions, so there is no deadline
+    let (exit, _) = context.resume(u64::MAX);
+
+    // fuel goes below zero by at most the instructions since the last check
+    let left = context.register(Registers::S3) as i32 as i64;
+    executor.charge((given as i64 - left) as u64)?;
+
+    Ok(exit)
+}
+
+#[inline(always)]
+fn stack_base(executor: &mut Executor) -> u32 {
+    executor.values().as_ptr() as usize as u32
+}
+
+/// Index on the value stack of the operands at `address`, with room for `count` of them
+fn operands(executor: &mut Executor, address: u32, count: usize) -> Result<usize, WasmError> {
+    let offset = address.wrapping_sub(stack_base(executor)) as usize;
+    let index = offset / 8;
+    if offset & 7 != 0 || index + count > VALUE_STACK_SIZE {
+        return Err(WasmError::NativeCodeFault);
+    }
+
+    Ok(index)
+}
+
+/// Calls the imported function with its params at `operands`, the results are written in
+/// their place
+fn call_host(
+    executor: &mut Executor,
+    system: &mut System,
+    function: u32,
+    operands: usize,
+) -> Result<Option<ExitReason>, WasmError> {
+    let host = executor
+        .instance
+        .functions
+        .get(function as usize)
+        .and_then(|function| function.host)
+        .ok_or(WasmError::NativeCodeFault)?;
+    let num_params = executor.instance.function_type(function).num_params as usize;
+    if operands + num_params > VALUE_STACK_SIZE {
+        return Err(WasmError::NativeCodeFault);
+    }
+    executor.set_operands(operands, operands + num_params);
+
+    executor.call_host(system, host)
+}
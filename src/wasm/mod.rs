@@ -0,0 +1,161 @@
+pub mod fuel;
+pub mod host;
+pub mod instance;
+pub mod interpreter;
+pub mod jit;
+pub mod module;
+pub mod opcodes;
+pub mod reader;
+pub mod side_table;
+pub mod validation;
+
+use self::instance::Instance;
+use self::interpreter::{Executor, ExitReason, Stacks};
+use self::module::Module;
+use self::side_table::{SideTable, SideTableWriter};
+use crate::system::interpreter::{ExecutionFrame, ExecutionStatus, Interpreter};
+use crate::system::resources::Resources;
+use crate::system::{System, SystemError};
+
+/// Deployment-time errors (malformed or unsupported module) and traps. All of them, except
+/// `System`, consume the resources of the frame and fail it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WasmError {
+    Malformed,
+    Unsupported,
+    LimitExceeded,
+    Unreachable,
+    MemoryOutOfBounds,
+    OutOfMemory,
+    DivisionByZero,
+    IntegerOverflow,
+    UndefinedElement,
+    UninitializedElement,
+    IndirectCallTypeMismatch,
+    StackOverflow,
+    StackUnderflow,
+    CallStackExhausted,
+    OutOfFuel,
+    StaticStateChange,
+    ReturnDataOutOfBounds,
+    /// Native code exited in a way the compiler never emits
+    NativeCodeFault,
+    System(SystemError),
+}
+
+impl From<SystemError> for WasmError {
+    fn from(error: SystemError) -> Self {
+        match error {
+            SystemError::OutOfResources => Self::OutOfFuel,
+            SystemError::OutOfMemory => Self::OutOfMemory,
+            error => Self::System(error),
+        }
+    }
+}
+
+pub const INTERPRETER: Interpreter = Interpreter {
+    validate,
+    preprocess: Some(preprocess),
+    execute,
+    constructor_returns_code: false,
+};
+
+fn validate(code: &[u8]) -> bool {
+    match Module::parse(code) {
+        Ok(module) => {
+            validation::validate_module(&module, &mut SideTableWriter::discarding()).is_ok()
+        }
+        Err(_) => false,
+    }
+}
+
+/// Validates the code once more, this time writing the side table into the artifact
+fn preprocess(code: &[u8], artifact: &mut [u8]) -> Result<usize, SystemError> {
+    let result = Module::parse(code).and_then(|module| {
+        let mut side_table = SideTableWriter::new(artifact, module.functions.count)?;
+        validation::validate_module(&module, &mut side_table)?;
+        let len = side_table.finish();
+        // modules the compiler gives up on are interpreted, with an empty native section
+        Ok(len + jit::compile(&module, &mut artifact[len..]).unwrap_or(0))
+    });
+
+    match result {
+        Ok(len) => Ok(len),
+        Err(WasmError::System(error)) => Err(error),
+        Err(_) => Err(SystemError::InvalidCode),
+    }
+}
+
+fn execute(
+    system: &mut System,
+    frame: &ExecutionFrame,
+    resources: &mut Resources,
+) -> Result<ExecutionStatus, SystemError> {
+    // everything the frame allocates is released at once by resetting the arena to this point
+    let frame_start = system.memory.allocate(0)?;
+    let result = execute_in_arena(system, frame, resources);
+    system.memory.release(frame_start);
+
+    match result {
+        Ok(status) => Ok(status),
+        Err(WasmError::System(error)) => Err(error),
+        Err(_) => {
+            resources.burn();
+            system.returndata.clear();
+            Ok(ExecutionStatus::Failure)
+        }
+    }
+}
+
+fn execute_in_arena(
+    system: &mut System,
+    frame: &ExecutionFrame,
+    resources: &mut Resources,
+) -> Result<ExecutionStatus, WasmError> {
+    // code was validated on deployment
+    let module = Module::parse(frame.code)?;
+    let (side_table, native) = SideTable::from_artifact(frame.artifact)?;
+    let native = jit::NativeCode::from_artifact(native)?;
+    let stacks = Stacks::new(&mut system.memory)?;
+    // linear memory goes last, as it's the only allocation that grows
+    let instance = Instance::new(&module, &side_table, &mut system.memory, resources)?;
+    let entry_point = if frame.is_constructor {
+        instance.deploy_export
+    } else {
+        instance.call_export
+    };
+    let start_function = instance.start_function;
+    let mut executor = Executor::new(frame, resources, stacks, instance, side_table);
+
+    let mut invoke = |executor: &mut Executor, function| match &native {
+        Some(native) => jit::invoke(executor, system, native, function),
+        None => executor.invoke(system, function),
+    };
+    let mut exit = None;
+    if let Some(start_function) = start_function {
+        exit = invoke(&mut executor, start_function)?;
+    }
+    if exit.is_none() {
+        match entry_point {
+            Some(entry_point) => exit = invoke(&mut executor, entry_point)?,
+            // contract can skip deployment logic, but not the call
+            None if frame.is_constructor => {}
+            None => return Err(WasmError::UndefinedElement),
+        }
+    }
+
+    match exit {
+        None => {
+            system.returndata.clear();
+            Ok(ExecutionStatus::Success)
+        }
+        Some(ExitReason::Return(offset, len)) => {
+            system.returndata.set(executor.memory_slice(offset, len)?)?;
+            Ok(ExecutionStatus::Success)
+        }
+        Some(ExitReason::Revert(offset, len)) => {
+            system.returndata.set(executor.memory_slice(offset, len)?)?;
+            Ok(ExecutionStatus::Revert)
+        }
+    }
+}
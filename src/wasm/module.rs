@@ -0,0 +1,336 @@
+use super::opcodes::*;
+use super::reader::Reader;
+use super::WasmError;
+
+pub const WASM_MAGIC: [u8; 4] = *b"\0asm";
+pub const WASM_VERSION: [u8; 4] = [1, 0, 0, 0];
+
+pub const SECTION_CUSTOM: u8 = 0;
+pub const SECTION_TYPE: u8 = 1;
+pub const SECTION_IMPORT: u8 = 2;
+pub const SECTION_FUNCTION: u8 = 3;
+pub const SECTION_TABLE: u8 = 4;
+pub const SECTION_MEMORY: u8 = 5;
+pub const SECTION_GLOBAL: u8 = 6;
+pub const SECTION_EXPORT: u8 = 7;
+pub const SECTION_START: u8 = 8;
+pub const SECTION_ELEMENT: u8 = 9;
+pub const SECTION_CODE: u8 = 10;
+pub const SECTION_DATA: u8 = 11;
+pub const SECTION_DATA_COUNT: u8 = 12;
+
+pub const IMPORT_KIND_FUNCTION: u8 = 0x00;
+pub const EXPORT_KIND_FUNCTION: u8 = 0x00;
+
+pub const PAGE_SIZE: usize = 1 << 16;
+
+// limits on the module, so that the instance always fits into the memory arena
+pub const MAX_TYPES: u32 = 1024;
+pub const MAX_FUNCTIONS: u32 = 8192;
+pub const MAX_GLOBALS: u32 = 1024;
+pub const MAX_TABLE_SIZE: u32 = 8192;
+pub const MAX_PAGES: u32 = 256;
+pub const MAX_LOCALS: u32 = 4096;
+pub const MAX_PARAMS: u32 = 128;
+
+/// Name of the export that is called on every call to the contract
+pub const CALL_EXPORT: &[u8] = b"call";
+/// Name of the optional export that is called once on deployment
+pub const DEPLOY_EXPORT: &[u8] = b"deploy";
+/// The only module functions can be imported from
+pub const HOST_MODULE: &[u8] = b"env";
+
+/// Vector section of the module: its entries are in `[start, end)`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Section {
+    pub start: usize,
+    pub end: usize,
+    pub count: u32,
+}
+
+/// Function signature, that points into the type section
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FuncType {
+    /// Encoding of the params and results, used to compare signatures
+    pub start: u32,
+    pub end: u32,
+    pub num_params: u32,
+    pub num_results: u32,
+}
+
+impl FuncType {
+    #[must_use]
+    pub fn encoding<'a>(&self, code: &'a [u8]) -> &'a [u8] {
+        &code[self.start as usize..self.end as usize]
+    }
+}
+
+/// Params and results of every type and function, for the deployment-time passes over the bodies
+pub struct Signatures {
+    pub types: [(u8, u8); MAX_TYPES as usize],
+    /// Type of every function, including the imported ones
+    pub functions: [u16; MAX_FUNCTIONS as usize],
+}
+
+impl Signatures {
+    pub const EMPTY: Self = Self {
+        types: [(0, 0); MAX_TYPES as usize],
+        functions: [0; MAX_FUNCTIONS as usize],
+    };
+
+    /// Reads the signatures of the module that was validated already
+    pub fn read(module: &Module) -> Result<Self, WasmError> {
+        if module.types.count > MAX_TYPES || module.num_functions() > MAX_FUNCTIONS {
+            return Err(WasmError::LimitExceeded);
+        }
+        let mut signatures = Self::EMPTY;
+        let mut reader = module.reader(&module.types);
+        for signature in signatures.types[..module.types.count as usize].iter_mut() {
+            let func_type = read_func_type(&mut reader)?;
+            *signature = (func_type.num_params as u8, func_type.num_results as u8);
+        }
+        let mut reader = module.reader(&module.imports);
+        let num_imported_functions = module.num_imported_functions() as usize;
+        for function_type in signatures.functions[..num_imported_functions].iter_mut() {
+            let _ = reader.read_vec()?;
+            let _ = reader.read_vec()?;
+            let _ = reader.read_u8()?;
+            *function_type = reader.read_u32()? as u16;
+        }
+        let mut reader = module.reader(&module.functions);
+        let num_functions = module.num_functions() as usize;
+        for function_type in signatures.functions[num_imported_functions..num_functions].iter_mut()
+        {
+            *function_type = reader.read_u32()? as u16;
+        }
+
+        Ok(signatures)
+    }
+
+    /// Number of params and results of the type
+    #[inline(always)]
+    pub fn of_type(&self, type_index: u32) -> (u32, u32) {
+        let (num_params, num_results) = self.types[type_index as usize];
+        (num_params as u32, num_results as u32)
+    }
+
+    #[inline(always)]
+    pub fn of_function(&self, function: u32) -> (u32, u32) {
+        self.of_type(self.functions[function as usize] as u32)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    pub min: u32,
+    pub max: Option<u32>,
+}
+
+/// Positions of the sections of the binary module. Nothing is copied, entries are read from
+/// the code when the module is validated or instantiated
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Module<'a> {
+    pub code: &'a [u8],
+    pub types: Section,
+    pub imports: Section,
+    pub functions: Section,
+    pub tables: Section,
+    pub memories: Section,
+    pub globals: Section,
+    pub exports: Section,
+    pub start_function: Option<u32>,
+    pub elements: Section,
+    pub bodies: Section,
+    pub data: Section,
+}
+
+impl<'a> Module<'a> {
+    pub fn parse(code: &'a [u8]) -> Result<Self, WasmError> {
+        let mut module = Self {
+            code,
+            ..Self::default()
+        };
+        let mut reader = Reader::new(code, 0);
+        if reader.read_bytes(4)? != WASM_MAGIC || reader.read_bytes(4)? != WASM_VERSION {
+            return Err(WasmError::Malformed);
+        }
+
+        let mut last_id = 0;
+        while !reader.is_at(code.len()) {
+            let id = reader.read_u8()?;
+            let size = reader.read_u32()? as usize;
+            let start = reader.position();
+            let end = start.checked_add(size).ok_or(WasmError::Malformed)?;
+            if end > code.len() {
+                return Err(WasmError::Malformed);
+            }
+            reader.set_position(end);
+            if id == SECTION_CUSTOM {
+                continue;
+            }
+            // data count section goes between the elements and the code
+            let order = match id {
+                SECTION_DATA_COUNT => SECTION_ELEMENT + 1,
+                SECTION_CODE | SECTION_DATA => id + 1,
+                _ => id,
+            };
+            if order <= last_id || id > SECTION_DATA_COUNT {
+                return Err(WasmError::Malformed);
+            }
+            last_id = order;
+
+            let mut section_reader = Reader::new(&code[..end], start);
+            if id == SECTION_START {
+                module.start_function = Some(section_reader.read_u32()?);
+                if !section_reader.is_at(end) {
+                    return Err(WasmError::Malformed);
+                }
+                continue;
+            }
+            let count = section_reader.read_u32()?;
+            let section = Section {
+                start: section_reader.position(),
+                end,
+                count,
+            };
+            match id {
+                SECTION_TYPE => module.types = section,
+                SECTION_IMPORT => module.imports = section,
+                SECTION_FUNCTION => module.functions = section,
+                SECTION_TABLE => module.tables = section,
+                SECTION_MEMORY => module.memories = section,
+                SECTION_GLOBAL => module.globals = section,
+                SECTION_EXPORT => module.exports = section,
+                SECTION_ELEMENT => module.elements = section,
+                SECTION_CODE => module.bodies = section,
+                SECTION_DATA => module.data = section,
+                // the count itself is redundant for us
+                _ => {}
+            }
+        }
+
+        Ok(module)
+    }
+
+    #[must_use]
+    pub fn reader(&self, section: &Section) -> Reader<'a> {
+        Reader::new(&self.code[..section.end], section.start)
+    }
+
+    /// Only function imports are supported, so all imports are functions
+    #[must_use]
+    pub const fn num_imported_functions(&self) -> u32 {
+        self.imports.count
+    }
+
+    /// Saturated, so that the limit checks catch the counts that would wrap around
+    #[must_use]
+    pub const fn num_functions(&self) -> u32 {
+        self.imports.count.saturating_add(self.functions.count)
+    }
+}
+
+pub fn read_value_type(reader: &mut Reader) -> Result<u8, WasmError> {
+    match reader.read_u8()? {
+        TYPE_I32 => Ok(TYPE_I32),
+        TYPE_I64 => Ok(TYPE_I64),
+        _ => Err(WasmError::Unsupported),
+    }
+}
+
+pub fn read_func_type(reader: &mut Reader) -> Result<FuncType, WasmError> {
+    if reader.read_u8()? != TYPE_FUNC {
+        return Err(WasmError::Malformed);
+    }
+    let start = reader.position() as u32;
+    let num_params = reader.read_u32()?;
+    if num_params > MAX_PARAMS {
+        return Err(WasmError::LimitExceeded);
+    }
+    for _ in 0..num_params {
+        read_value_type(reader)?;
+    }
+    let num_results = reader.read_u32()?;
+    // multi-value is not a part of MVP
+    if num_results > 1 {
+        return Err(WasmError::Unsupported);
+    }
+    for _ in 0..num_results {
+        read_value_type(reader)?;
+    }
+
+    Ok(FuncType {
+        start,
+        end: reader.position() as u32,
+        num_params,
+        num_results,
+    })
+}
+
+pub fn read_limits(reader: &mut Reader) -> Result<Limits, WasmError> {
+    let flags = reader.read_u8()?;
+    let min = reader.read_u32()?;
+    let max = match flags {
+        0 => None,
+        1 => Some(reader.read_u32()?),
+        _ => return Err(WasmError::Malformed),
+    };
+    if max.is_some_and(|max| max < min) {
+        return Err(WasmError::Malformed);
+    }
+
+    Ok(Limits { min, max })
+}
+
+/// Constant expression. Imported globals are not supported, so it can only be a constant
+pub fn read_const_expr(reader: &mut Reader) -> Result<u64, WasmError> {
+    let value = match reader.read_u8()? {
+        I32_CONST => reader.read_i32()? as u32 as u64,
+        I64_CONST => reader.read_i64()? as u64,
+        _ => return Err(WasmError::Unsupported),
+    };
+    if reader.read_u8()? != END {
+        return Err(WasmError::Malformed);
+    }
+
+    Ok(value)
+}
+
+/// Linear scan through the type section, only used at deployment
+pub fn func_type(module: &Module, index: u32) -> Result<FuncType, WasmError> {
+    if index >= module.types.count {
+        return Err(WasmError::Malformed);
+    }
+    let mut reader = module.reader(&module.types);
+    for _ in 0..index {
+        read_func_type(&mut reader)?;
+    }
+
+    read_func_type(&mut reader)
+}
+
+/// Type index of the function, only used at deployment
+pub fn function_type_index(module: &Module, function: u32) -> Result<u32, WasmError> {
+    let mut reader = module.reader(&module.imports);
+    if function < module.num_imported_functions() {
+        for i in 0..=function {
+            let _ = reader.read_vec()?;
+            let _ = reader.read_vec()?;
+            let _ = reader.read_u8()?;
+            let type_index = reader.read_u32()?;
+            if i == function {
+                return Ok(type_index);
+            }
+        }
+    }
+    let mut reader = module.reader(&module.functions);
+    let local_index = function - module.num_imported_functions();
+    if local_index >= module.functions.count {
+        return Err(WasmError::Malformed);
+    }
+    for _ in 0..local_index {
+        reader.read_u32()?;
+    }
+
+    reader.read_u32()
+}
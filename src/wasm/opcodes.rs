@@ -0,0 +1,136 @@
+// Integer subset of the WASM MVP, plus sign extension and bulk memory copy/fill that
+// current compilers emit by default. Floating point is not supported, as its results
+// (NaN payloads) are not deterministic across implementations
+
+pub const UNREACHABLE: u8 = 0x00;
+pub const NOP: u8 = 0x01;
+pub const BLOCK: u8 = 0x02;
+pub const LOOP: u8 = 0x03;
+pub const IF: u8 = 0x04;
+pub const ELSE: u8 = 0x05;
+pub const END: u8 = 0x0b;
+pub const BR: u8 = 0x0c;
+pub const BR_IF: u8 = 0x0d;
+pub const BR_TABLE: u8 = 0x0e;
+pub const RETURN: u8 = 0x0f;
+pub const CALL: u8 = 0x10;
+pub const CALL_INDIRECT: u8 = 0x11;
+
+pub const DROP: u8 = 0x1a;
+pub const SELECT: u8 = 0x1b;
+
+pub const LOCAL_GET: u8 = 0x20;
+pub const LOCAL_SET: u8 = 0x21;
+pub const LOCAL_TEE: u8 = 0x22;
+pub const GLOBAL_GET: u8 = 0x23;
+pub const GLOBAL_SET: u8 = 0x24;
+
+pub const I32_LOAD: u8 = 0x28;
+pub const I64_LOAD: u8 = 0x29;
+pub const I32_LOAD8_S: u8 = 0x2c;
+pub const I32_LOAD8_U: u8 = 0x2d;
+pub const I32_LOAD16_S: u8 = 0x2e;
+pub const I32_LOAD16_U: u8 = 0x2f;
+pub const I64_LOAD8_S: u8 = 0x30;
+pub const I64_LOAD8_U: u8 = 0x31;
+pub const I64_LOAD16_S: u8 = 0x32;
+pub const I64_LOAD16_U: u8 = 0x33;
+pub const I64_LOAD32_S: u8 = 0x34;
+pub const I64_LOAD32_U: u8 = 0x35;
+pub const I32_STORE: u8 = 0x36;
+pub const I64_STORE: u8 = 0x37;
+pub const I32_STORE8: u8 = 0x3a;
+pub const I32_STORE16: u8 = 0x3b;
+pub const I64_STORE8: u8 = 0x3c;
+pub const I64_STORE16: u8 = 0x3d;
+pub const I64_STORE32: u8 = 0x3e;
+pub const MEMORY_SIZE: u8 = 0x3f;
+pub const MEMORY_GROW: u8 = 0x40;
+
+pub const I32_CONST: u8 = 0x41;
+pub const I64_CONST: u8 = 0x42;
+
+pub const I32_EQZ: u8 = 0x45;
+pub const I32_EQ: u8 = 0x46;
+pub const I32_NE: u8 = 0x47;
+pub const I32_LT_S: u8 = 0x48;
+pub const I32_LT_U: u8 = 0x49;
+pub const I32_GT_S: u8 = 0x4a;
+pub const I32_GT_U: u8 = 0x4b;
+pub const I32_LE_S: u8 = 0x4c;
+pub const I32_LE_U: u8 = 0x4d;
+pub const I32_GE_S: u8 = 0x4e;
+pub const I32_GE_U: u8 = 0x4f;
+
+pub const I64_EQZ: u8 = 0x50;
+pub const I64_EQ: u8 = 0x51;
+pub const I64_NE: u8 = 0x52;
+pub const I64_LT_S: u8 = 0x53;
+pub const I64_LT_U: u8 = 0x54;
+pub const I64_GT_S: u8 = 0x55;
+pub const I64_GT_U: u8 = 0x56;
+pub const I64_LE_S: u8 = 0x57;
+pub const I64_LE_U: u8 = 0x58;
+pub const I64_GE_S: u8 = 0x59;
+pub const I64_GE_U: u8 = 0x5a;
+
+pub const I32_CLZ: u8 = 0x67;
+pub const I32_CTZ: u8 = 0x68;
+pub const I32_POPCNT: u8 = 0x69;
+pub const I32_ADD: u8 = 0x6a;
+pub const I32_SUB: u8 = 0x6b;
+pub const I32_MUL: u8 = 0x6c;
+pub const I32_DIV_S: u8 = 0x6d;
+pub const I32_DIV_U: u8 = 0x6e;
+pub const I32_REM_S: u8 = 0x6f;
+pub const I32_REM_U: u8 = 0x70;
+pub const I32_AND: u8 = 0x71;
+pub const I32_OR: u8 = 0x72;
+pub const I32_XOR: u8 = 0x73;
+pub const I32_SHL: u8 = 0x74;
+pub const I32_SHR_S: u8 = 0x75;
+pub const I32_SHR_U: u8 = 0x76;
+pub const I32_ROTL: u8 = 0x77;
+pub const I32_ROTR: u8 = 0x78;
+
+pub const I64_CLZ: u8 = 0x79;
+pub const I64_CTZ: u8 = 0x7a;
+pub const I64_POPCNT: u8 = 0x7b;
+pub const I64_ADD: u8 = 0x7c;
+pub const I64_SUB: u8 = 0x7d;
+pub const I64_MUL: u8 = 0x7e;
+pub const I64_DIV_S: u8 = 0x7f;
+pub const I64_DIV_U: u8 = 0x80;
+pub const I64_REM_S: u8 = 0x81;
+pub const I64_REM_U: u8 = 0x82;
+pub const I64_AND: u8 = 0x83;
+pub const I64_OR: u8 = 0x84;
+pub const I64_XOR: u8 = 0x85;
+pub const I64_SHL: u8 = 0x86;
+pub const I64_SHR_S: u8 = 0x87;
+pub const I64_SHR_U: u8 = 0x88;
+pub const I64_ROTL: u8 = 0x89;
+pub const I64_ROTR: u8 = 0x8a;
+
+pub const I32_WRAP_I64: u8 = 0xa7;
+pub const I64_EXTEND_I32_S: u8 = 0xac;
+pub const I64_EXTEND_I32_U: u8 = 0xad;
+
+pub const I32_EXTEND8_S: u8 = 0xc0;
+pub const I32_EXTEND16_S: u8 = 0xc1;
+pub const I64_EXTEND8_S: u8 = 0xc2;
+pub const I64_EXTEND16_S: u8 = 0xc3;
+pub const I64_EXTEND32_S: u8 = 0xc4;
+
+/// Prefix of the multi-byte opcodes, followed by LEB128 sub-opcode
+pub const PREFIX_FC: u8 = 0xfc;
+pub const MEMORY_COPY: u32 = 10;
+pub const MEMORY_FILL: u32 = 11;
+
+// value and block types
+
+pub const TYPE_I32: u8 = 0x7f;
+pub const TYPE_I64: u8 = 0x7e;
+pub const TYPE_FUNC: u8 = 0x60;
+pub const TYPE_FUNCREF: u8 = 0x70;
+pub const BLOCK_TYPE_EMPTY: u8 = 0x40;
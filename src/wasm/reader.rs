@@ -0,0 +1,106 @@
+use super::WasmError;
+
+/// Cursor over the binary encoding of the module
+#[derive(Clone, Copy)]
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    #[must_use]
+    pub const fn new(bytes: &'a [u8], position: usize) -> Self {
+        Self { bytes, position }
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn position(&self) -> usize {
+        self.position
+    }
+
+    #[inline(always)]
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn is_at(&self, end: usize) -> bool {
+        self.position >= end
+    }
+
+    #[inline(always)]
+    pub fn read_u8(&mut self) -> Result<u8, WasmError> {
+        let byte = *self.bytes.get(self.position).ok_or(WasmError::Malformed)?;
+        self.position += 1;
+
+        Ok(byte)
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], WasmError> {
+        let end = self.position.checked_add(len).ok_or(WasmError::Malformed)?;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(WasmError::Malformed)?;
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    /// Length-prefixed byte string, e.g. a name
+    pub fn read_vec(&mut self) -> Result<&'a [u8], WasmError> {
+        let len = self.read_u32()? as usize;
+
+        self.read_bytes(len)
+    }
+
+    /// Unsigned LEB128
+    #[inline(always)]
+    pub fn read_u32(&mut self) -> Result<u32, WasmError> {
+        let mut result = 0u32;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            // the last of 5 bytes can only hold 4 bits
+            if shift == 28 && byte & 0xf0 != 0 {
+                return Err(WasmError::Malformed);
+            }
+            result |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Signed LEB128
+    pub fn read_i32(&mut self) -> Result<i32, WasmError> {
+        Ok(self.read_signed(32)? as i32)
+    }
+
+    /// Signed LEB128
+    pub fn read_i64(&mut self) -> Result<i64, WasmError> {
+        self.read_signed(64)
+    }
+
+    fn read_signed(&mut self, bits: u32) -> Result<i64, WasmError> {
+        let mut result = 0i64;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift >= bits {
+                return Err(WasmError::Malformed);
+            }
+            result |= ((byte & 0x7f) as i64) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    result |= -1i64 << shift;
+                }
+                return Ok(result);
+            }
+        }
+    }
+}
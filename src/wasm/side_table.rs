@@ -0,0 +1,210 @@
+use super::module::MAX_FUNCTIONS;
+use super::WasmError;
+use crate::system::SystemError;
+
+// Side table of the control flow, emitted by the validation at deployment and stored as the
+// preprocessing artifact, so that the interpreter never scans the code for matching block ends.
+//
+// Every instruction that can transfer control (`br`, `br_if`, `br_table`, `if`, `else`) owns
+// entries in the order they appear in the code: one each, except `br_table` that owns one per
+// target including the default one. The interpreter keeps the index of the next entry alongside
+// the position in the code: falling through an instruction skips its entries, and taking
+// a branch continues from the index stored in the entry.
+//
+// Artifact layout (all little-endian u32 words):
+//  - header: `[num_functions, num_entries]`
+//  - index of the first entry of every function defined in the module
+//  - entries, 4 words each
+//  - native code of the module, if it was compiled (see `jit/mod.rs`)
+
+pub const HEADER_WORDS: usize = 2;
+pub const ENTRY_WORDS: usize = 4;
+/// Target of the branches to the outermost label, that return from the function
+pub const RETURN_TARGET: u32 = u32::MAX;
+/// End of the chain of entries waiting for the end of their block
+pub const NO_ENTRY: u32 = u32::MAX;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// Position in the code to continue from, or `RETURN_TARGET`
+    pub target: u32,
+    /// Index of the next entry at the target
+    pub target_entry: u32,
+    /// Values carried to the target from the top of the stack
+    pub arity: u32,
+    /// Values discarded below them
+    pub drop: u32,
+}
+
+/// Entries are written during the validation. The writer without a buffer only counts them,
+/// which is enough to validate the code without preprocessing it
+pub struct SideTableWriter<'a> {
+    buffer: Option<&'a mut [u8]>,
+    num_functions: u32,
+    num_entries: u32,
+}
+
+impl<'a> SideTableWriter<'a> {
+    pub fn new(buffer: &'a mut [u8], num_functions: u32) -> Result<Self, WasmError> {
+        if num_functions > MAX_FUNCTIONS {
+            return Err(WasmError::LimitExceeded);
+        }
+        if (HEADER_WORDS + num_functions as usize) * 4 > buffer.len() {
+            return Err(WasmError::System(SystemError::BlobStorageFull));
+        }
+
+        Ok(Self {
+            buffer: Some(buffer),
+            num_functions,
+            num_entries: 0,
+        })
+    }
+
+    #[must_use]
+    pub const fn discarding() -> Self {
+        Self {
+            buffer: None,
+            num_functions: 0,
+            num_entries: 0,
+        }
+    }
+
+    #[must_use]
+    #[inline(always)]
+    pub const fn num_entries(&self) -> u32 {
+        self.num_entries
+    }
+
+    /// Records the first entry of the `index`-th function defined in the module
+    pub fn start_function(&mut self, index: u32) {
+        let num_entries = self.num_entries;
+        self.set_word(HEADER_WORDS + index as usize, num_entries);
+    }
+
+    /// Appends the entry and returns its index
+    pub fn push(&mut self, entry: Entry) -> Result<u32, WasmError> {
+        let index = self.num_entries;
+        if let Some(buffer) = &self.buffer {
+            if (self.entry_word(index) + ENTRY_WORDS) * 4 > buffer.len() {
+                return Err(WasmError::System(SystemError::BlobStorageFull));
+            }
+        }
+        let word = self.entry_word(index);
+        self.set_word(word, entry.target);
+        self.set_word(word + 1, entry.target_entry);
+        self.set_word(word + 2, entry.arity);
+        self.set_word(word + 3, entry.drop);
+        self.num_entries += 1;
+
+        Ok(index)
+    }
+
+    /// Appends the entry with the target that is not known yet. Such entries are chained
+    /// through their `target` starting from `pending`, and the new head is returned
+    pub fn push_pending(&mut self, pending: u32, arity: u32, drop: u32) -> Result<u32, WasmError> {
+        self.push(Entry {
+            target: pending,
+            target_entry: 0,
+            arity,
+            drop,
+        })
+    }
+
+    /// Points all the chained entries at the `target`, that continues from the next entry
+    pub fn resolve(&mut self, mut pending: u32, target: u32) {
+        if self.buffer.is_none() {
+            return;
+        }
+        let num_entries = self.num_entries;
+        while pending != NO_ENTRY {
+            let word = self.entry_word(pending);
+            let next = self.word(word);
+            self.set_word(word, target);
+            self.set_word(word + 1, num_entries);
+            pending = next;
+        }
+    }
+
+    /// Writes the header and returns the length of the artifact
+    pub fn finish(mut self) -> usize {
+        let num_functions = self.num_functions;
+        let num_entries = self.num_entries;
+        self.set_word(0, num_functions);
+        self.set_word(1, num_entries);
+
+        self.entry_word(num_entries) * 4
+    }
+
+    #[inline(always)]
+    const fn entry_word(&self, index: u32) -> usize {
+        HEADER_WORDS + self.num_functions as usize + ENTRY_WORDS * index as usize
+    }
+
+    #[inline(always)]
+    fn word(&self, word: usize) -> u32 {
+        match &self.buffer {
+            Some(buffer) => u32::from_le_bytes([
+                buffer[4 * word],
+                buffer[4 * word + 1],
+                buffer[4 * word + 2],
+                buffer[4 * word + 3],
+            ]),
+            None => 0,
+        }
+    }
+
+    #[inline(always)]
+    fn set_word(&mut self, word: usize, value: u32) {
+        if let Some(buffer) = &mut self.buffer {
+            buffer[4 * word..4 * word + 4].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+/// Side table of the executing module, read from the artifact
+pub struct SideTable {
+    starts: &'static [u32],
+    entries: &'static [Entry],
+}
+
+impl SideTable {
+    /// Reads the side table from the start of the artifact and returns the rest of it
+    pub fn from_artifact(artifact: &'static [u8]) -> Result<(Self, &'static [u8]), WasmError> {
+        if artifact.len() & 3 != 0 || artifact.len() < HEADER_WORDS * 4 {
+            return Err(WasmError::System(SystemError::InvalidCode));
+        }
+        // artifacts are word-aligned
+        let words: &'static [u32] = unsafe {
+            core::slice::from_raw_parts(artifact.as_ptr().cast::<u32>(), artifact.len() / 4)
+        };
+        let num_functions = words[0] as usize;
+        let num_entries = words[1] as usize;
+        let len = HEADER_WORDS + num_functions + ENTRY_WORDS * num_entries;
+        if len > words.len() {
+            return Err(WasmError::System(SystemError::InvalidCode));
+        }
+        let (starts, entries) = words[HEADER_WORDS..len].split_at(num_functions);
+        let entries: &'static [Entry] =
+            unsafe { core::slice::from_raw_parts(entries.as_ptr().cast::<Entry>(), num_entries) };
+
+        Ok((Self { starts, entries }, &artifact[len * 4..]))
+    }
+
+    /// First entry of the `index`-th function defined in the module
+    #[inline(always)]
+    pub fn function_start(&self, index: usize) -> Result<u32, WasmError> {
+        self.starts
+            .get(index)
+            .copied()
+            .ok_or(WasmError::System(SystemError::InvalidCode))
+    }
+
+    #[inline(always)]
+    pub fn entry(&self, index: usize) -> Result<Entry, WasmError> {
+        self.entries
+            .get(index)
+            .copied()
+            .ok_or(WasmError::System(SystemError::InvalidCode))
+    }
+}
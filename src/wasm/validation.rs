@@ -0,0 +1,640 @@
+use super::host::HostFunction;
+use super::module::*;
+use super::opcodes::*;
+use super::reader::Reader;
+use super::side_table::{Entry, SideTableWriter, NO_ENTRY, RETURN_TARGET};
+use super::WasmError;
+
+// Deployment-time validation. It's structural: every index, label and immediate is checked,
+// blocks are properly nested and operand stack heights are tracked, but operand types are not.
+// Values are untyped 64-bit slots for the interpreter, so ill-typed code can only compute
+// garbage inside of its own instance. Knowing the heights, validation emits the side table
+// of the branches (see `side_table.rs`)
+
+pub const MAX_NESTING: usize = 1024;
+pub const MAX_BR_TABLE_LEN: u32 = 1 << 16;
+
+const CONTROL_BLOCK: u8 = 0;
+const CONTROL_LOOP: u8 = 1;
+const CONTROL_IF: u8 = 2;
+const CONTROL_ELSE: u8 = 3;
+
+/// What the code of a function can refer to
+struct Context {
+    num_types: u32,
+    num_functions: u32,
+    signatures: Signatures,
+    num_globals: u32,
+    /// One bit per global
+    mutable_globals: [u8; (MAX_GLOBALS / 8) as usize],
+    has_memory: bool,
+    has_table: bool,
+}
+
+/// Validates the module and writes the side table of its functions
+pub fn validate_module(module: &Module, side_table: &mut SideTableWriter) -> Result<(), WasmError> {
+    if module.types.count > MAX_TYPES
+        || module.num_functions() > MAX_FUNCTIONS
+        || module.globals.count > MAX_GLOBALS
+    {
+        return Err(WasmError::LimitExceeded);
+    }
+
+    let mut context = Context {
+        num_types: module.types.count,
+        num_functions: module.num_functions(),
+        signatures: Signatures::EMPTY,
+        num_globals: module.globals.count,
+        mutable_globals: [0u8; (MAX_GLOBALS / 8) as usize],
+        has_memory: module.memories.count == 1,
+        has_table: module.tables.count == 1,
+    };
+
+    let mut reader = module.reader(&module.types);
+    for signature in context.signatures.types[..module.types.count as usize].iter_mut() {
+        let func_type = read_func_type(&mut reader)?;
+        *signature = (func_type.num_params as u8, func_type.num_results as u8);
+    }
+    expect_end(&reader, &module.types)?;
+
+    let mut reader = module.reader(&module.imports);
+    for index in 0..module.imports.count as usize {
+        let module_name = reader.read_vec()?;
+        let name = reader.read_vec()?;
+        if module_name != HOST_MODULE || reader.read_u8()? != IMPORT_KIND_FUNCTION {
+            return Err(WasmError::Unsupported);
+        }
+        let host = HostFunction::from_name(name).ok_or(WasmError::Unsupported)?;
+        let type_index = reader.read_u32()?;
+        if func_type(module, type_index)?.encoding(module.code) != host.signature() {
+            return Err(WasmError::Malformed);
+        }
+        context.signatures.functions[index] = type_index as u16;
+    }
+    expect_end(&reader, &module.imports)?;
+
+    let mut reader = module.reader(&module.functions);
+    let num_imported_functions = module.num_imported_functions() as usize;
+    let num_functions = module.num_functions() as usize;
+    for function_type in
+        context.signatures.functions[num_imported_functions..num_functions].iter_mut()
+    {
+        let type_index = reader.read_u32()?;
+        if type_index >= module.types.count {
+            return Err(WasmError::Malformed);
+        }
+        *function_type = type_index as u16;
+    }
+    expect_end(&reader, &module.functions)?;
+
+    if module.tables.count > 1 || module.memories.count > 1 {
+        return Err(WasmError::Unsupported);
+    }
+    let mut reader = module.reader(&module.tables);
+    if module.tables.count == 1 {
+        if reader.read_u8()? != TYPE_FUNCREF {
+            return Err(WasmError::Malformed);
+        }
+        if read_limits(&mut reader)?.min > MAX_TABLE_SIZE {
+            return Err(WasmError::LimitExceeded);
+        }
+    }
+    expect_end(&reader, &module.tables)?;
+
+    let mut reader = module.reader(&module.memories);
+    if module.memories.count == 1 && read_limits(&mut reader)?.min > MAX_PAGES {
+        return Err(WasmError::LimitExceeded);
+    }
+    expect_end(&reader, &module.memories)?;
+
+    let mut reader = module.reader(&module.globals);
+    for index in 0..module.globals.count as usize {
+        read_value_type(&mut reader)?;
+        match reader.read_u8()? {
+            0 => {}
+            1 => context.mutable_globals[index / 8] |= 1 << (index % 8),
+            _ => return Err(WasmError::Malformed),
+        }
+        read_const_expr(&mut reader)?;
+    }
+    expect_end(&reader, &module.globals)?;
+
+    let mut reader = module.reader(&module.exports);
+    for _ in 0..module.exports.count {
+        let name = reader.read_vec()?;
+        let kind = reader.read_u8()?;
+        let index = reader.read_u32()?;
+        if kind == EXPORT_KIND_FUNCTION {
+            if index >= module.num_functions() {
+                return Err(WasmError::Malformed);
+            }
+            if name == CALL_EXPORT || name == DEPLOY_EXPORT {
+                expect_entry_point(&context, index)?;
+            }
+        }
+    }
+    expect_end(&reader, &module.exports)?;
+
+    if let Some(start) = module.start_function {
+        if start >= module.num_functions() {
+            return Err(WasmError::Malformed);
+        }
+        expect_entry_point(&context, start)?;
+    }
+
+    let mut reader = module.reader(&module.elements);
+    for _ in 0..module.elements.count {
+        // only active segments of the MVP
+        if reader.read_u32()? != 0 || !context.has_table {
+            return Err(WasmError::Unsupported);
+        }
+        read_const_expr(&mut reader)?;
+        let len = reader.read_u32()?;
+        for _ in 0..len {
+            if reader.read_u32()? >= module.num_functions() {
+                return Err(WasmError::Malformed);
+            }
+        }
+    }
+    expect_end(&reader, &module.elements)?;
+
+    if module.bodies.count != module.functions.count {
+        return Err(WasmError::Malformed);
+    }
+    let mut reader = module.reader(&module.bodies);
+    for index in 0..module.functions.count {
+        let size = reader.read_u32()? as usize;
+        let end = reader
+            .position()
+            .checked_add(size)
+            .ok_or(WasmError::Malformed)?;
+        let function = module.num_imported_functions() + index;
+        let mut body_reader = Reader::new(&module.code[..end], reader.position());
+        side_table.start_function(index);
+        validate_body(&mut body_reader, &context, function, side_table)?;
+        if !body_reader.is_at(end) {
+            return Err(WasmError::Malformed);
+        }
+        reader.set_position(end);
+    }
+    expect_end(&reader, &module.bodies)?;
+
+    let mut reader = module.reader(&module.data);
+    for _ in 0..module.data.count {
+        if reader.read_u32()? != 0 || !context.has_memory {
+            return Err(WasmError::Unsupported);
+        }
+        read_const_expr(&mut reader)?;
+        reader.read_vec()?;
+    }
+    expect_end(&reader, &module.data)?;
+
+    Ok(())
+}
+
+fn expect_end(reader: &Reader, section: &Section) -> Result<(), WasmError> {
+    if reader.position() != section.end && section.end != 0 {
+        return Err(WasmError::Malformed);
+    }
+
+    Ok(())
+}
+
+/// Entry points take and return nothing, everything goes through the host functions
+fn expect_entry_point(context: &Context, function: u32) -> Result<(), WasmError> {
+    if context.signatures.of_function(function) != (0, 0) {
+        return Err(WasmError::Malformed);
+    }
+
+    Ok(())
+}
+
+/// Reads the declarations of locals and returns their total number
+pub fn read_locals(reader: &mut Reader) -> Result<u32, WasmError> {
+    let num_groups = reader.read_u32()?;
+    let mut num_locals = 0u32;
+    for _ in 0..num_groups {
+        let count = reader.read_u32()?;
+        num_locals = num_locals
+            .checked_add(count)
+            .filter(|num_locals| *num_locals <= MAX_LOCALS)
+            .ok_or(WasmError::LimitExceeded)?;
+        read_value_type(reader)?;
+    }
+
+    Ok(num_locals)
+}
+
+/// Returns the number of results of the block
+fn read_block_type(reader: &mut Reader) -> Result<u32, WasmError> {
+    match reader.read_u8()? {
+        BLOCK_TYPE_EMPTY => Ok(0),
+        TYPE_I32 | TYPE_I64 => Ok(1),
+        _ => Err(WasmError::Unsupported),
+    }
+}
+
+/// Checks alignment hint against the natural alignment, and skips the offset
+fn read_memarg(reader: &mut Reader, context: &Context, size_log2: u32) -> Result<(), WasmError> {
+    if !context.has_memory || reader.read_u32()? > size_log2 {
+        return Err(WasmError::Malformed);
+    }
+    reader.read_u32()?;
+
+    Ok(())
+}
+
+fn expect_zero_byte(reader: &mut Reader) -> Result<(), WasmError> {
+    if reader.read_u8()? != 0 {
+        return Err(WasmError::Malformed);
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+struct Control {
+    kind: u8,
+    /// Operand stack height at the start of the block
+    height: u32,
+    /// Results of the block
+    arity: u32,
+    /// Where branches to a loop go, and their next entry
+    loop_start: u32,
+    loop_entry: u32,
+    /// Chain of the entries that branch to the end of the block
+    pending: u32,
+    /// Entry of the `if` that skips to the `else` branch
+    else_entry: u32,
+}
+
+impl Control {
+    const EMPTY: Self = Self {
+        kind: CONTROL_BLOCK,
+        height: 0,
+        arity: 0,
+        loop_start: 0,
+        loop_entry: 0,
+        pending: NO_ENTRY,
+        else_entry: NO_ENTRY,
+    };
+}
+
+/// Operand stack heights and the labels of the function being validated
+struct Body {
+    control: [Control; MAX_NESTING],
+    depth: usize,
+    height: u32,
+    /// Code after an unconditional branch, where the stack is polymorphic
+    unreachable: bool,
+    num_results: u32,
+}
+
+impl Body {
+    /// Height of the current block's operands
+    #[inline(always)]
+    fn base(&self) -> u32 {
+        match self.depth {
+            0 => 0,
+            depth => self.control[depth - 1].height,
+        }
+    }
+
+    fn pop(&mut self, count: u32) -> Result<(), WasmError> {
+        let base = self.base();
+        if self.height >= base + count {
+            self.height -= count;
+        } else if self.unreachable {
+            self.height = base;
+        } else {
+            return Err(WasmError::Malformed);
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn push(&mut self, count: u32) {
+        self.height += count;
+    }
+
+    /// Pops `inputs` and pushes `outputs`
+    fn apply(&mut self, inputs: u32, outputs: u32) -> Result<(), WasmError> {
+        self.pop(inputs)?;
+        self.push(outputs);
+
+        Ok(())
+    }
+
+    fn enter(
+        &mut self,
+        kind: u8,
+        arity: u32,
+        reader: &Reader,
+        entry: u32,
+    ) -> Result<(), WasmError> {
+        if self.depth == MAX_NESTING {
+            return Err(WasmError::LimitExceeded);
+        }
+        self.control[self.depth] = Control {
+            kind,
+            height: self.height,
+            arity,
+            loop_start: reader.position() as u32,
+            loop_entry: entry,
+            ..Control::EMPTY
+        };
+        self.depth += 1;
+
+        Ok(())
+    }
+
+    /// Checks that the block produced exactly its results
+    fn expect_results(&mut self) -> Result<(), WasmError> {
+        let control = self.control[self.depth - 1];
+        if !self.unreachable && self.height != control.height + control.arity {
+            return Err(WasmError::Malformed);
+        }
+        self.height = control.height + control.arity;
+        self.unreachable = false;
+
+        Ok(())
+    }
+
+    /// Arity of the branch to the label `depth` levels up
+    fn label_arity(&self, depth: u32) -> Result<u32, WasmError> {
+        let depth = depth as usize;
+        if depth > self.depth {
+            return Err(WasmError::Malformed);
+        }
+        if depth == self.depth {
+            return Ok(self.num_results);
+        }
+        let control = &self.control[self.depth - 1 - depth];
+
+        Ok(if control.kind == CONTROL_LOOP {
+            0
+        } else {
+            control.arity
+        })
+    }
+
+    /// Emits the entry of the branch to the label `depth` levels up
+    fn branch(&mut self, depth: u32, side_table: &mut SideTableWriter) -> Result<(), WasmError> {
+        let arity = self.label_arity(depth)?;
+        self.apply(arity, arity)?;
+        let depth = depth as usize;
+        if depth == self.depth {
+            side_table.push(Entry {
+                target: RETURN_TARGET,
+                target_entry: 0,
+                arity,
+                drop: 0,
+            })?;
+            return Ok(());
+        }
+
+        let index = self.depth - 1 - depth;
+        let control = self.control[index];
+        // in unreachable code the branch never runs, so the adjustment doesn't matter
+        let drop = self.height.saturating_sub(control.height + arity);
+        if control.kind == CONTROL_LOOP {
+            side_table.push(Entry {
+                target: control.loop_start,
+                target_entry: control.loop_entry,
+                arity,
+                drop,
+            })?;
+        } else {
+            self.control[index].pending = side_table.push_pending(control.pending, arity, drop)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_body(
+    reader: &mut Reader,
+    context: &Context,
+    function: u32,
+    side_table: &mut SideTableWriter,
+) -> Result<(), WasmError> {
+    let (num_params, num_results) = context.signatures.of_function(function);
+    let num_locals = num_params + read_locals(reader)?;
+    let mut body = Body {
+        control: [Control::EMPTY; MAX_NESTING],
+        depth: 0,
+        height: 0,
+        unreachable: false,
+        num_results,
+    };
+
+    loop {
+        let opcode = reader.read_u8()?;
+        match opcode {
+            NOP => {}
+            UNREACHABLE => body.unreachable = true,
+            RETURN => {
+                body.pop(num_results)?;
+                body.unreachable = true;
+            }
+            DROP => body.pop(1)?,
+            SELECT => body.apply(3, 1)?,
+            BLOCK => {
+                let arity = read_block_type(reader)?;
+                body.enter(CONTROL_BLOCK, arity, reader, side_table.num_entries())?;
+            }
+            LOOP => {
+                let arity = read_block_type(reader)?;
+                body.enter(CONTROL_LOOP, arity, reader, side_table.num_entries())?;
+            }
+            IF => {
+                let arity = read_block_type(reader)?;
+                body.pop(1)?;
+                let else_entry = side_table.push_pending(NO_ENTRY, 0, 0)?;
+                body.enter(CONTROL_IF, arity, reader, side_table.num_entries())?;
+                body.control[body.depth - 1].else_entry = else_entry;
+            }
+            ELSE => {
+                if body.depth == 0 || body.control[body.depth - 1].kind != CONTROL_IF {
+                    return Err(WasmError::Malformed);
+                }
+                body.expect_results()?;
+                let control = &mut body.control[body.depth - 1];
+                control.kind = CONTROL_ELSE;
+                control.pending = side_table.push_pending(control.pending, control.arity, 0)?;
+                // `if` skips to the first instruction of the `else` branch
+                side_table.resolve(control.else_entry, reader.position() as u32);
+                control.else_entry = NO_ENTRY;
+                body.height = control.height;
+            }
+            END => {
+                if body.depth == 0 {
+                    if !body.unreachable && body.height != num_results {
+                        return Err(WasmError::Malformed);
+                    }
+                    return Ok(());
+                }
+                body.expect_results()?;
+                let control = body.control[body.depth - 1];
+                // `if` without `else` must leave the stack as it was
+                if control.else_entry != NO_ENTRY && control.arity != 0 {
+                    return Err(WasmError::Malformed);
+                }
+                let end = reader.position() as u32;
+                side_table.resolve(control.else_entry, end);
+                side_table.resolve(control.pending, end);
+                body.depth -= 1;
+            }
+            BR => {
+                body.branch(reader.read_u32()?, side_table)?;
+                body.unreachable = true;
+            }
+            BR_IF => {
+                body.pop(1)?;
+                body.branch(reader.read_u32()?, side_table)?;
+            }
+            BR_TABLE => {
+                body.pop(1)?;
+                let len = reader.read_u32()?;
+                if len > MAX_BR_TABLE_LEN {
+                    return Err(WasmError::LimitExceeded);
+                }
+                // targets and the default one
+                let mut arity = None;
+                for _ in 0..=len {
+                    let depth = reader.read_u32()?;
+                    let label_arity = body.label_arity(depth)?;
+                    if *arity.get_or_insert(label_arity) != label_arity {
+                        return Err(WasmError::Malformed);
+                    }
+                    body.branch(depth, side_table)?;
+                }
+                body.unreachable = true;
+            }
+            CALL => {
+                let callee = reader.read_u32()?;
+                if callee >= context.num_functions {
+                    return Err(WasmError::Malformed);
+                }
+                let (num_params, num_results) = context.signatures.of_function(callee);
+                body.apply(num_params, num_results)?;
+            }
+            CALL_INDIRECT => {
+                let type_index = reader.read_u32()?;
+                if type_index >= context.num_types || !context.has_table {
+                    return Err(WasmError::Malformed);
+                }
+                expect_zero_byte(reader)?;
+                let (num_params, num_results) = context.signatures.of_type(type_index);
+                body.apply(num_params + 1, num_results)?;
+            }
+            LOCAL_GET | LOCAL_SET | LOCAL_TEE => {
+                if reader.read_u32()? >= num_locals {
+                    return Err(WasmError::Malformed);
+                }
+                match opcode {
+                    LOCAL_GET => body.push(1),
+                    LOCAL_SET => body.pop(1)?,
+                    _ => body.apply(1, 1)?,
+                }
+            }
+            GLOBAL_GET => {
+                if reader.read_u32()? >= context.num_globals {
+                    return Err(WasmError::Malformed);
+                }
+                body.push(1);
+            }
+            GLOBAL_SET => {
+                let index = reader.read_u32()?;
+                if index >= context.num_globals
+                    || context.mutable_globals[index as usize / 8] & (1 << (index % 8)) == 0
+                {
+                    return Err(WasmError::Malformed);
+                }
+                body.pop(1)?;
+            }
+            I32_LOAD8_S | I32_LOAD8_U | I64_LOAD8_S | I64_LOAD8_U => {
+                read_memarg(reader, context, 0)?;
+                body.apply(1, 1)?;
+            }
+            I32_LOAD16_S | I32_LOAD16_U | I64_LOAD16_S | I64_LOAD16_U => {
+                read_memarg(reader, context, 1)?;
+                body.apply(1, 1)?;
+            }
+            I32_LOAD | I64_LOAD32_S | I64_LOAD32_U => {
+                read_memarg(reader, context, 2)?;
+                body.apply(1, 1)?;
+            }
+            I64_LOAD => {
+                read_memarg(reader, context, 3)?;
+                body.apply(1, 1)?;
+            }
+            I32_STORE8 | I64_STORE8 => {
+                read_memarg(reader, context, 0)?;
+                body.pop(2)?;
+            }
+            I32_STORE16 | I64_STORE16 => {
+                read_memarg(reader, context, 1)?;
+                body.pop(2)?;
+            }
+            I32_STORE | I64_STORE32 => {
+                read_memarg(reader, context, 2)?;
+                body.pop(2)?;
+            }
+            I64_STORE => {
+                read_memarg(reader, context, 3)?;
+                body.pop(2)?;
+            }
+            MEMORY_SIZE | MEMORY_GROW => {
+                if !context.has_memory {
+                    return Err(WasmError::Malformed);
+                }
+                expect_zero_byte(reader)?;
+                if opcode == MEMORY_SIZE {
+                    body.push(1);
+                } else {
+                    body.apply(1, 1)?;
+                }
+            }
+            I32_CONST => {
+                reader.read_i32()?;
+                body.push(1);
+            }
+            I64_CONST => {
+                reader.read_i64()?;
+                body.push(1);
+            }
+            I32_EQZ
+            | I64_EQZ
+            | I32_CLZ
+            | I32_CTZ
+            | I32_POPCNT
+            | I64_CLZ
+            | I64_CTZ
+            | I64_POPCNT
+            | I32_WRAP_I64
+            | I64_EXTEND_I32_S
+            | I64_EXTEND_I32_U
+            | I32_EXTEND8_S..=I64_EXTEND32_S => body.apply(1, 1)?,
+            I32_EQ..=I32_GE_U | I64_EQ..=I64_GE_U | I32_ADD..=I32_ROTR | I64_ADD..=I64_ROTR => {
+                body.apply(2, 1)?
+            }
+            PREFIX_FC => {
+                if !context.has_memory {
+                    return Err(WasmError::Malformed);
+                }
+                match reader.read_u32()? {
+                    MEMORY_COPY => {
+                        expect_zero_byte(reader)?;
+                        expect_zero_byte(reader)?;
+                    }
+                    MEMORY_FILL => expect_zero_byte(reader)?,
+                    _ => return Err(WasmError::Unsupported),
+                }
+                body.pop(3)?;
+            }
+            _ => return Err(WasmError::Unsupported),
+        }
+    }
+}